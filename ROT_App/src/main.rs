@@ -25,6 +25,7 @@ struct Game {
 
     //light
     lights: Vec<rot::Light>,
+    light_animator: rot::Animator<rot::Light>,
 
     //input
     mouse_pos: (f64, f64),
@@ -39,6 +40,7 @@ impl Game {
             object: Vec::new(),
             cameras: Vec::new(),
             lights: Vec::new(),
+            light_animator: rot::Animator::new(),
             mouse_pos: (0.0, 0.0),
             space_toggle: false,
         };
@@ -48,7 +50,7 @@ impl Game {
 }
 
 impl rot::Layer for Game {
-    fn on_attach(&mut self, renderer: &mut rot::Renderer) {
+    fn on_attach(&mut self, renderer: &mut rot::Renderer, _systems: &mut rot::Systems) {
         let num_of_instances_per_row: u32 = 10;
         let instance_displacement: f32 = 3.0;
 
@@ -99,16 +101,27 @@ impl rot::Layer for Game {
 
         let light = rot::Light::new(renderer, [2.0, 2.0, 2.0], [1.0, 1.0, 1.0], "light");
 
+        //orbits around the Y axis, a turn every 4 seconds
+        let start = light.position();
+        self.light_animator.add(
+            rot::Tween::new(0.0, 2.0 * std::f32::consts::PI, 4.0, rot::Easing::Linear)
+                .with_loop_mode(rot::LoopMode::Loop),
+            move |light: &mut rot::Light, angle: f32| {
+                let rotation = na::UnitQuaternion::from_axis_angle(&na::Vector3::y_axis(), angle);
+                light.set_position(rotation * start);
+            },
+        );
+
         self.lights.push(light);
     }
 
     fn on_event(&mut self, event: &rot::Event) {
+        self.cameras[0].on_event(event);
         match event {
             rot::Event::MouseMovement(ev) => {
                 self.mouse_pos = (ev.position.x / 1280 as f64, ev.position.y / 720 as f64)
             }
             rot::Event::KeyboardInput(ev) => {
-                self.cameras[0].on_event(event);
                 match ev.state {
                     rot::State::Pressed => match ev.virtual_keycode {
                         None => {}
@@ -124,15 +137,26 @@ impl rot::Layer for Game {
         }
     }
 
-    fn on_update(&mut self, renderer: &mut rot::Renderer, delta_time: f64) {
+    fn on_update(
+        &mut self,
+        renderer: &mut rot::Renderer,
+        _systems: &mut rot::Systems,
+        delta_time: f64,
+    ) {
         let clear_color = (self.mouse_pos.0, self.mouse_pos.1, 0.2);
-        renderer.set_clear_color([clear_color.0, clear_color.1, clear_color.2])
+        renderer.set_clear_color([clear_color.0, clear_color.1, clear_color.2]);
 
         for object in self.object.iter_mut() {
             object.on_update(renderer)
         }
         for camera in self.cameras.iter_mut() {
-            camera.on_update(renderer);
+            camera.on_update(renderer, delta_time as f32);
+        }
+        if let Some(light) = self.lights.first_mut() {
+            self.light_animator.update(light, delta_time as f32);
+        }
+        for light in self.lights.iter_mut() {
+            light.on_update(renderer);
         }
         renderer.set_camera(&self.cameras[0]);
 
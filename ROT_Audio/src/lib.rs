@@ -0,0 +1,9 @@
+mod rot_clip;
+mod rot_decoder;
+mod rot_mixer;
+mod rot_output;
+
+pub use rot_clip::AudioClip;
+pub use rot_decoder::{Decoder, FlacDecoder, OggDecoder, WavDecoder};
+pub use rot_mixer::{BusId, Listener, PlaySettings, Spatial, VoiceId};
+pub use rot_output::AudioEngine;
@@ -0,0 +1,89 @@
+use crate::rot_decoder::{self, Decoder};
+use std::error::Error;
+use std::path::Path;
+use std::sync::Arc;
+
+//Decoded whole into memory, for short sounds played often. Music is better streamed,
+//see AudioEngine::stream. Cloning shares the samples
+#[derive(Clone)]
+pub struct AudioClip {
+    pub name: String,
+
+    //interleaved
+    samples: Arc<Vec<f32>>,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl AudioClip {
+    //wav, ogg or flac
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref();
+        let name = path
+            .file_stem()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        AudioClip::from_decoder(rot_decoder::open(path)?, &name)
+    }
+
+    pub fn from_decoder(mut decoder: Box<dyn Decoder>, name: &str) -> Result<Self, Box<dyn Error>> {
+        let mut samples = Vec::new();
+        while decoder.decode(&mut samples)? {}
+
+        Ok(AudioClip::from_samples(
+            samples,
+            decoder.channels(),
+            decoder.sample_rate(),
+            name,
+        ))
+    }
+
+    //interleaved, in -1.0..1.0
+    pub fn from_samples(samples: Vec<f32>, channels: u16, sample_rate: u32, name: &str) -> Self {
+        let channels = channels.max(1);
+        let mut samples = samples;
+        samples.truncate(samples.len() - samples.len() % channels as usize);
+
+        Self {
+            name: name.to_string(),
+            samples: Arc::new(samples),
+            channels,
+            sample_rate: sample_rate.max(1),
+        }
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.channels as usize
+    }
+
+    //in seconds
+    pub fn duration(&self) -> f32 {
+        self.frames() as f32 / self.sample_rate as f32
+    }
+
+    //left and right, mono is copied to both
+    pub(crate) fn frame(&self, index: usize) -> Option<[f32; 2]> {
+        let channels = self.channels as usize;
+        let frame = self.samples.get(index * channels..(index + 1) * channels)?;
+
+        Some(stereo(frame))
+    }
+}
+
+//the channels past the second are dropped
+pub(crate) fn stereo(frame: &[f32]) -> [f32; 2] {
+    match frame {
+        [mono] => [*mono, *mono],
+        [left, right, ..] => [*left, *right],
+        [] => [0.0, 0.0],
+    }
+}
@@ -0,0 +1,56 @@
+use crate::rot_decoder::Decoder;
+use std::error::Error;
+use std::fs::File;
+use std::path::Path;
+
+pub struct FlacDecoder {
+    reader: claxon::FlacReader<File>,
+    //reused by every block
+    buffer: Vec<i32>,
+    channels: u16,
+    sample_rate: u32,
+    scale: f32,
+}
+
+impl FlacDecoder {
+    pub fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let reader = claxon::FlacReader::open(path)?;
+        let info = reader.streaminfo();
+
+        Ok(Self {
+            reader,
+            buffer: Vec::new(),
+            channels: info.channels as u16,
+            sample_rate: info.sample_rate,
+            scale: 1.0 / (1u64 << (info.bits_per_sample - 1)) as f32,
+        })
+    }
+}
+
+impl Decoder for FlacDecoder {
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn decode(&mut self, samples: &mut Vec<f32>) -> Result<bool, Box<dyn Error>> {
+        let buffer = std::mem::take(&mut self.buffer);
+        let block = match self.reader.blocks().read_next_or_eof(buffer)? {
+            Some(block) => block,
+            None => return Ok(false),
+        };
+
+        //blocks are stored channel after channel
+        for frame in 0..block.duration() {
+            for channel in 0..block.channels() {
+                samples.push(block.sample(channel, frame) as f32 * self.scale);
+            }
+        }
+        self.buffer = block.into_buffer();
+
+        Ok(true)
+    }
+}
@@ -0,0 +1,35 @@
+mod flac;
+mod ogg;
+mod wav;
+
+pub use flac::FlacDecoder;
+pub use ogg::OggDecoder;
+pub use wav::WavDecoder;
+
+use std::error::Error;
+use std::path::Path;
+
+//Reads a file a little at a time, clips decode it whole and streams as they play
+pub trait Decoder: Send {
+    fn channels(&self) -> u16;
+
+    fn sample_rate(&self) -> u32;
+
+    //appends the next interleaved samples, in -1.0..1.0. Ok(false) once the file ended
+    fn decode(&mut self, samples: &mut Vec<f32>) -> Result<bool, Box<dyn Error>>;
+}
+
+//picked by the extension: wav, ogg or flac
+pub fn open(path: &Path) -> Result<Box<dyn Decoder>, Box<dyn Error>> {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase());
+
+    match extension.as_deref() {
+        Some("wav") | Some("wave") => Ok(Box::new(WavDecoder::open(path)?)),
+        Some("ogg") | Some("oga") => Ok(Box::new(OggDecoder::open(path)?)),
+        Some("flac") => Ok(Box::new(FlacDecoder::open(path)?)),
+        _ => Err(format!("Unsupported audio file {}", path.display()).into()),
+    }
+}
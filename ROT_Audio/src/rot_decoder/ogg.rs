@@ -0,0 +1,43 @@
+use crate::rot_decoder::Decoder;
+use lewton::inside_ogg::OggStreamReader;
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+//Vorbis in an Ogg container
+pub struct OggDecoder {
+    reader: OggStreamReader<BufReader<File>>,
+}
+
+impl OggDecoder {
+    pub fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let reader = OggStreamReader::new(BufReader::new(File::open(path)?))?;
+
+        Ok(Self { reader })
+    }
+}
+
+impl Decoder for OggDecoder {
+    fn channels(&self) -> u16 {
+        self.reader.ident_hdr.audio_channels as u16
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.reader.ident_hdr.audio_sample_rate
+    }
+
+    fn decode(&mut self, samples: &mut Vec<f32>) -> Result<bool, Box<dyn Error>> {
+        //the first packet of a stream decodes to nothing
+        loop {
+            match self.reader.read_dec_packet_itl()? {
+                Some(packet) if packet.is_empty() => continue,
+                Some(packet) => {
+                    samples.extend(packet.iter().map(|&sample| sample as f32 / 32768.0));
+                    return Ok(true);
+                }
+                None => return Ok(false),
+            }
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use crate::rot_decoder::Decoder;
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+const BLOCK_FRAMES: usize = 4096;
+
+pub struct WavDecoder {
+    reader: hound::WavReader<BufReader<File>>,
+    spec: hound::WavSpec,
+}
+
+impl WavDecoder {
+    pub fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let reader = hound::WavReader::open(path)?;
+        let spec = reader.spec();
+
+        Ok(Self { reader, spec })
+    }
+}
+
+impl Decoder for WavDecoder {
+    fn channels(&self) -> u16 {
+        self.spec.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.spec.sample_rate
+    }
+
+    fn decode(&mut self, samples: &mut Vec<f32>) -> Result<bool, Box<dyn Error>> {
+        let count = BLOCK_FRAMES * self.spec.channels as usize;
+        let start = samples.len();
+
+        match self.spec.sample_format {
+            hound::SampleFormat::Float => {
+                for sample in self.reader.samples::<f32>().take(count) {
+                    samples.push(sample?);
+                }
+            }
+            hound::SampleFormat::Int => {
+                let scale = 1.0 / (1u64 << (self.spec.bits_per_sample - 1)) as f32;
+                for sample in self.reader.samples::<i32>().take(count) {
+                    samples.push(sample? as f32 * scale);
+                }
+            }
+        }
+
+        Ok(samples.len() - start == count)
+    }
+}
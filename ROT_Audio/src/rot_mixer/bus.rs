@@ -0,0 +1,30 @@
+//Groups voices under one volume, like music, effects or dialogue. Every bus plays into the master
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub struct BusId(pub(crate) u32);
+
+impl BusId {
+    pub const MASTER: BusId = BusId(0);
+}
+
+pub(crate) struct Bus {
+    pub name: String,
+    pub volume: f32,
+    pub muted: bool,
+}
+
+impl Bus {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            volume: 1.0,
+            muted: false,
+        }
+    }
+
+    pub fn gain(&self) -> f32 {
+        match self.muted {
+            true => 0.0,
+            false => self.volume,
+        }
+    }
+}
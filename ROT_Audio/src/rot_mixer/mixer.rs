@@ -0,0 +1,100 @@
+use crate::rot_mixer::{Bus, BusId, Listener, Voice, VoiceId};
+
+//Shared by the game thread, which adds and changes voices, and the output, which renders them
+pub(crate) struct Mixer {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub listener: Listener,
+
+    //indexed by BusId, the master is the first
+    pub buses: Vec<Bus>,
+    pub voices: Vec<(VoiceId, Voice)>,
+    next_voice_id: u64,
+
+    //stereo, reused by every render
+    mix: Vec<[f32; 2]>,
+}
+
+impl Mixer {
+    pub fn new(sample_rate: u32, channels: u16) -> Self {
+        Self {
+            sample_rate: sample_rate.max(1),
+            channels: channels.max(1),
+            listener: Listener::default(),
+            buses: vec![Bus::new("master")],
+            voices: Vec::new(),
+            next_voice_id: 0,
+            mix: Vec::new(),
+        }
+    }
+
+    pub fn add_voice(&mut self, voice: Voice) -> VoiceId {
+        let id = VoiceId(self.next_voice_id);
+        self.next_voice_id += 1;
+        self.voices.push((id, voice));
+
+        id
+    }
+
+    pub fn voice_mut(&mut self, id: VoiceId) -> Option<&mut Voice> {
+        self.voices
+            .iter_mut()
+            .find(|(voice_id, _)| *voice_id == id)
+            .map(|(_, voice)| voice)
+    }
+
+    pub fn remove_voice(&mut self, id: VoiceId) -> bool {
+        let count = self.voices.len();
+        self.voices.retain(|(voice_id, _)| *voice_id != id);
+
+        self.voices.len() != count
+    }
+
+    pub fn bus_mut(&mut self, id: BusId) -> Option<&mut Bus> {
+        self.buses.get_mut(id.0 as usize)
+    }
+
+    //interleaved in the output channels, the channels past the second are left silent
+    pub fn render(&mut self, output: &mut [f32]) {
+        let channels = self.channels as usize;
+        let frames = output.len() / channels;
+
+        self.mix.clear();
+        self.mix.resize(frames, [0.0, 0.0]);
+
+        let master = self.buses[0].gain();
+        let mut index = 0;
+        while index < self.voices.len() {
+            let voice = &mut self.voices[index].1;
+            let bus_gain = match voice.settings.bus {
+                BusId::MASTER => master,
+                bus => self.buses.get(bus.0 as usize).map_or(0.0, Bus::gain) * master,
+            };
+
+            match voice.render(&mut self.mix, self.sample_rate, &self.listener, bus_gain) {
+                true => index += 1,
+                false => {
+                    self.voices.swap_remove(index);
+                }
+            }
+        }
+
+        for (out, [left, right]) in output.chunks_exact_mut(channels).zip(self.mix.iter()) {
+            let (left, right) = (left.clamp(-1.0, 1.0), right.clamp(-1.0, 1.0));
+            match out {
+                [mono] => *mono = (left + right) * 0.5,
+                [out_left, out_right, rest @ ..] => {
+                    *out_left = left;
+                    *out_right = right;
+                    for sample in rest.iter_mut() {
+                        *sample = 0.0;
+                    }
+                }
+                [] => {}
+            }
+        }
+        for sample in output[frames * channels..].iter_mut() {
+            *sample = 0.0;
+        }
+    }
+}
@@ -0,0 +1,14 @@
+mod bus;
+mod mixer;
+mod spatial;
+mod stream;
+mod voice;
+
+pub use bus::BusId;
+pub use spatial::{Listener, Spatial};
+pub use voice::{PlaySettings, VoiceId};
+
+pub(crate) use bus::Bus;
+pub(crate) use mixer::Mixer;
+pub(crate) use stream::Stream;
+pub(crate) use voice::{Voice, VoiceSource};
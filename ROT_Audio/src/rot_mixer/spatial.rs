@@ -0,0 +1,100 @@
+use nalgebra as na;
+
+//Where the sounds are heard from, usually the camera
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Listener {
+    pub position: na::Point3<f32>,
+    pub forward: na::Vector3<f32>,
+    pub up: na::Vector3<f32>,
+}
+
+impl Listener {
+    pub fn look_at(
+        position: na::Point3<f32>,
+        target: na::Point3<f32>,
+        up: na::Vector3<f32>,
+    ) -> Self {
+        Self {
+            position,
+            forward: target - position,
+            up,
+        }
+    }
+
+    //right handed view matrix, looking down -Z like Camera::view
+    pub fn from_view(view: &na::Matrix4<f32>) -> Self {
+        let inverse = view.try_inverse().unwrap_or_else(na::Matrix4::identity);
+
+        Self {
+            position: inverse.transform_point(&na::Point3::origin()),
+            forward: inverse.transform_vector(&-na::Vector3::z()),
+            up: inverse.transform_vector(&na::Vector3::y()),
+        }
+    }
+
+    pub fn right(&self) -> na::Vector3<f32> {
+        self.forward
+            .cross(&self.up)
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(na::Vector3::x)
+    }
+}
+
+impl Default for Listener {
+    fn default() -> Self {
+        Self {
+            position: na::Point3::origin(),
+            forward: -na::Vector3::z(),
+            up: na::Vector3::y(),
+        }
+    }
+}
+
+//A source placed in the world, attenuated with the distance and panned towards its side
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Spatial {
+    pub position: na::Point3<f32>,
+    //full volume up to here
+    pub min_distance: f32,
+    //no quieter past here
+    pub max_distance: f32,
+    //1.0 halves the volume every time the distance doubles
+    pub rolloff: f32,
+}
+
+impl Spatial {
+    pub fn at(position: na::Point3<f32>) -> Self {
+        Self {
+            position,
+            min_distance: 1.0,
+            max_distance: 100.0,
+            rolloff: 1.0,
+        }
+    }
+
+    //inverse distance, clamped between min and max distance
+    pub fn attenuation(&self, listener: &Listener) -> f32 {
+        let min_distance = self.min_distance.max(f32::EPSILON);
+        let distance = na::distance(&self.position, &listener.position)
+            .min(self.max_distance)
+            .max(min_distance);
+
+        min_distance / (min_distance + self.rolloff.max(0.0) * (distance - min_distance))
+    }
+
+    //-1.0 fully left, 1.0 fully right
+    pub fn pan(&self, listener: &Listener) -> f32 {
+        match (self.position - listener.position).try_normalize(f32::EPSILON) {
+            Some(direction) => direction.dot(&listener.right()).clamp(-1.0, 1.0),
+            None => 0.0,
+        }
+    }
+
+    //equal power, so a sound crossing the listener keeps its loudness
+    pub(crate) fn gains(&self, listener: &Listener) -> [f32; 2] {
+        let attenuation = self.attenuation(listener);
+        let angle = (self.pan(listener) + 1.0) * std::f32::consts::FRAC_PI_4;
+
+        [attenuation * angle.cos(), attenuation * angle.sin()]
+    }
+}
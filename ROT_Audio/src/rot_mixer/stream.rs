@@ -0,0 +1,138 @@
+use crate::rot_clip::stereo;
+use crate::rot_decoder::{self, Decoder};
+use log::warn;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, SyncSender, TryRecvError};
+
+//the worker sends the decoded frames in blocks of this size
+const BLOCK_FRAMES: usize = 4096;
+//how many blocks the worker decodes ahead of the mixer before it waits
+const BLOCKS_AHEAD: usize = 8;
+//frames already played are dropped from the buffer in chunks of this size
+const RELEASE_FRAMES: usize = 8192;
+
+//Decoded on its own thread a few blocks ahead, the output only pulls the frames it was sent.
+//The worker rewinds by reopening the file and stops once the voice is dropped
+pub(crate) struct Stream {
+    sample_rate: u32,
+    blocks: Receiver<Vec<[f32; 2]>>,
+    //starting at the frame first
+    frames: Vec<[f32; 2]>,
+    first: usize,
+}
+
+impl Stream {
+    //waits for the first block, so the stream starts when it is played
+    pub fn open(path: &Path, looping: bool) -> Result<Self, Box<dyn Error>> {
+        let decoder = rot_decoder::open(path)?;
+        let sample_rate = decoder.sample_rate();
+        let (sender, blocks) = mpsc::sync_channel(BLOCKS_AHEAD);
+
+        let path = path.to_path_buf();
+        std::thread::Builder::new()
+            .name(format!("Stream {}", path.display()))
+            .spawn(move || Stream::decode(path, decoder, looping, sender))?;
+
+        let frames = blocks.recv().unwrap_or_default();
+
+        Ok(Self {
+            sample_rate,
+            blocks,
+            frames,
+            first: 0,
+        })
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    //Takes what the worker sent until the frame until is there, without waiting for it.
+    //False when the worker is behind, true once it is there or the stream ended
+    pub fn pull(&mut self, until: usize) -> bool {
+        while until >= self.first + self.frames.len() {
+            match self.blocks.try_recv() {
+                Ok(block) => self.frames.extend(block),
+                Err(TryRecvError::Empty) => return false,
+                Err(TryRecvError::Disconnected) => return true,
+            }
+        }
+
+        true
+    }
+
+    //None past the end, or past what was pulled
+    pub fn frame(&self, index: usize) -> Option<[f32; 2]> {
+        self.frames.get(index.checked_sub(self.first)?).copied()
+    }
+
+    //the frames before index won't be asked for again
+    pub fn release(&mut self, index: usize) {
+        let played = index.saturating_sub(self.first);
+        if played >= RELEASE_FRAMES {
+            let played = played.min(self.frames.len());
+            self.frames.drain(..played);
+            self.first += played;
+        }
+    }
+
+    //the frames keep counting up through the loop, so it is seamless
+    fn decode(
+        path: PathBuf,
+        mut decoder: Box<dyn Decoder>,
+        looping: bool,
+        sender: SyncSender<Vec<[f32; 2]>>,
+    ) {
+        let mut samples = Vec::new();
+        let mut block = Vec::with_capacity(BLOCK_FRAMES);
+        //since the file was last opened, a looping empty file would never end otherwise
+        let mut decoded = false;
+
+        loop {
+            let more = match decoder.decode(&mut samples) {
+                Ok(more) => more,
+                Err(error) => {
+                    warn!("Stopping {}: {}", path.display(), error);
+                    break;
+                }
+            };
+
+            let channels = decoder.channels().max(1) as usize;
+            for frame in samples.chunks_exact(channels) {
+                block.push(stereo(frame));
+                decoded = true;
+
+                if block.len() == BLOCK_FRAMES {
+                    let full = std::mem::replace(&mut block, Vec::with_capacity(BLOCK_FRAMES));
+                    //the voice was dropped
+                    if sender.send(full).is_err() {
+                        return;
+                    }
+                }
+            }
+            samples.clear();
+
+            if more {
+                continue;
+            }
+            if !looping || !decoded {
+                break;
+            }
+            match rot_decoder::open(&path) {
+                Ok(reopened) => {
+                    decoder = reopened;
+                    decoded = false;
+                }
+                Err(error) => {
+                    warn!("Can't rewind {}: {}", path.display(), error);
+                    break;
+                }
+            }
+        }
+
+        if !block.is_empty() {
+            let _ = sender.send(block);
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use crate::rot_clip::AudioClip;
+use crate::rot_mixer::{BusId, Listener, Spatial, Stream};
+
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct VoiceId(pub(crate) u64);
+
+#[derive(Debug, Copy, Clone)]
+pub struct PlaySettings {
+    pub bus: BusId,
+    pub volume: f32,
+    //playback speed, 2.0 is an octave up
+    pub pitch: f32,
+    pub looping: bool,
+    //None plays it as is, in stereo
+    pub spatial: Option<Spatial>,
+}
+
+impl Default for PlaySettings {
+    fn default() -> Self {
+        Self {
+            bus: BusId::MASTER,
+            volume: 1.0,
+            pitch: 1.0,
+            looping: false,
+            spatial: None,
+        }
+    }
+}
+
+pub(crate) enum VoiceSource {
+    Clip(AudioClip),
+    Stream(Stream),
+}
+
+impl VoiceSource {
+    fn sample_rate(&self) -> u32 {
+        match self {
+            VoiceSource::Clip(clip) => clip.sample_rate(),
+            VoiceSource::Stream(stream) => stream.sample_rate(),
+        }
+    }
+
+    fn frame(&self, index: usize, looping: bool) -> Option<[f32; 2]> {
+        match self {
+            VoiceSource::Clip(clip) if looping && clip.frames() > 0 => {
+                clip.frame(index % clip.frames())
+            }
+            VoiceSource::Clip(clip) => clip.frame(index),
+            VoiceSource::Stream(stream) => stream.frame(index),
+        }
+    }
+}
+
+pub(crate) struct Voice {
+    pub source: VoiceSource,
+    pub settings: PlaySettings,
+    pub paused: bool,
+
+    //in source frames, between two of them while resampling
+    cursor: f64,
+    //of the last block, the next one ramps from them so volume and pan changes don't click
+    gains: Option<[f32; 2]>,
+}
+
+impl Voice {
+    pub fn new(source: VoiceSource, settings: PlaySettings) -> Self {
+        Self {
+            source,
+            settings,
+            paused: false,
+            cursor: 0.0,
+            gains: None,
+        }
+    }
+
+    //adds into mix, false once it finished playing
+    pub fn render(
+        &mut self,
+        mix: &mut [[f32; 2]],
+        sample_rate: u32,
+        listener: &Listener,
+        bus_gain: f32,
+    ) -> bool {
+        if self.paused {
+            return true;
+        }
+
+        let step = self.source.sample_rate() as f64 / sample_rate as f64
+            * self.settings.pitch.max(0.0) as f64;
+        let looping = self.settings.looping;
+        let loop_frames = match &self.source {
+            VoiceSource::Clip(clip) if looping => clip.frames() as f64,
+            _ => 0.0,
+        };
+
+        //the worker fell behind, waiting a block is better than skipping what it hasn't sent
+        if let VoiceSource::Stream(stream) = &mut self.source {
+            let until = (self.cursor + mix.len() as f64 * step) as usize + 1;
+            if !stream.pull(until) {
+                return true;
+            }
+        }
+
+        let volume = self.settings.volume * bus_gain;
+        let target = match self.settings.spatial {
+            Some(spatial) => {
+                let [left, right] = spatial.gains(listener);
+                [left * volume, right * volume]
+            }
+            None => [volume, volume],
+        };
+        let start = self.gains.unwrap_or(target);
+        self.gains = Some(target);
+
+        let frames = mix.len() as f32;
+        for (index, out) in mix.iter_mut().enumerate() {
+            let frame = self.cursor as usize;
+            let current = match self.source.frame(frame, looping) {
+                Some(current) => current,
+                None => return false,
+            };
+            let next = self.source.frame(frame + 1, looping).unwrap_or(current);
+
+            //linear interpolation, between the source rate and the output one
+            let t = (self.cursor - frame as f64) as f32;
+            let mut sample = [
+                current[0] + (next[0] - current[0]) * t,
+                current[1] + (next[1] - current[1]) * t,
+            ];
+            if self.settings.spatial.is_some() {
+                let mono = (sample[0] + sample[1]) * 0.5;
+                sample = [mono, mono];
+            }
+
+            let ramp = (index + 1) as f32 / frames;
+            out[0] += sample[0] * (start[0] + (target[0] - start[0]) * ramp);
+            out[1] += sample[1] * (start[1] + (target[1] - start[1]) * ramp);
+
+            self.cursor += step;
+            if loop_frames > 0.0 && self.cursor >= loop_frames {
+                self.cursor -= loop_frames;
+            }
+        }
+
+        if let VoiceSource::Stream(stream) = &mut self.source {
+            stream.release(self.cursor as usize);
+        }
+
+        true
+    }
+}
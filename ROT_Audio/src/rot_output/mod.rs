@@ -0,0 +1,490 @@
+#[allow(unused_imports)]
+use log::{debug, error, info, trace, warn};
+
+use crate::rot_clip::AudioClip;
+use crate::rot_mixer::{
+    Bus, BusId, Listener, Mixer, PlaySettings, Stream, Voice, VoiceId, VoiceSource,
+};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use nalgebra as na;
+use std::error::Error;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+//how often the null output renders
+const NULL_PERIOD: Duration = Duration::from_millis(10);
+
+enum Output {
+    //kept alive for as long as it plays
+    Device(cpal::Stream),
+    //renders and drops the mix in real time, until the flag is lowered
+    Null(Arc<AtomicBool>, Option<JoinHandle<()>>),
+    //only renders when asked to
+    Offline,
+}
+
+//Plays clips and streams through the buses into an output:
+//  let step = AudioClip::load("sound/step.ogg")?;
+//  audio.play(&step, PlaySettings { spatial: Some(Spatial::at(position)), ..PlaySettings::default() });
+//  audio.stream("sound/theme.flac", PlaySettings { bus: music, looping: true, ..PlaySettings::default() })?;
+pub struct AudioEngine {
+    mixer: Arc<Mutex<Mixer>>,
+    output: Output,
+}
+
+impl AudioEngine {
+    //the default output device of the system, in its preferred format
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        info!("Opening audio output");
+
+        let host = cpal::default_host();
+        let device = host
+            .default_output_device()
+            .ok_or("No audio output device")?;
+        let supported = device.default_output_config()?;
+        let sample_format = supported.sample_format();
+        let config: cpal::StreamConfig = supported.into();
+
+        trace!(
+            "Audio output at {} Hz, {} channels, {:?}",
+            config.sample_rate.0,
+            config.channels,
+            sample_format
+        );
+        let mixer = Arc::new(Mutex::new(Mixer::new(
+            config.sample_rate.0,
+            config.channels,
+        )));
+
+        let stream = match sample_format {
+            cpal::SampleFormat::F32 => {
+                AudioEngine::build_stream::<f32>(&device, &config, Arc::clone(&mixer))?
+            }
+            cpal::SampleFormat::I16 => {
+                AudioEngine::build_stream::<i16>(&device, &config, Arc::clone(&mixer))?
+            }
+            cpal::SampleFormat::U16 => {
+                AudioEngine::build_stream::<u16>(&device, &config, Arc::clone(&mixer))?
+            }
+        };
+        stream.play()?;
+
+        Ok(Self {
+            mixer,
+            output: Output::Device(stream),
+        })
+    }
+
+    //plays in real time into nothing, for machines without a sound card
+    pub fn null(sample_rate: u32) -> Self {
+        let mixer = Arc::new(Mutex::new(Mixer::new(sample_rate, 2)));
+        let running = Arc::new(AtomicBool::new(true));
+
+        let thread = {
+            let mixer = Arc::clone(&mixer);
+            let running = Arc::clone(&running);
+            let frames = (sample_rate as f64 * NULL_PERIOD.as_secs_f64()).ceil() as usize;
+
+            std::thread::spawn(move || {
+                let mut mix = vec![0.0; frames * 2];
+                let mut next = Instant::now();
+                while running.load(Ordering::Relaxed) {
+                    match mixer.lock() {
+                        Ok(mut mixer) => mixer.render(&mut mix),
+                        Err(_) => return,
+                    }
+
+                    next += NULL_PERIOD;
+                    if let Some(wait) = next.checked_duration_since(Instant::now()) {
+                        std::thread::sleep(wait);
+                    }
+                }
+            })
+        };
+
+        Self {
+            mixer,
+            output: Output::Null(running, Some(thread)),
+        }
+    }
+
+    //nothing plays until render is called, for tests and bouncing sounds to a file
+    pub fn offline(sample_rate: u32, channels: u16) -> Self {
+        Self {
+            mixer: Arc::new(Mutex::new(Mixer::new(sample_rate, channels))),
+            output: Output::Offline,
+        }
+    }
+
+    //Mixes the next frames into output, interleaved in channels(). Meant for offline engines,
+    //the others would skip what is rendered here
+    pub fn render(&self, output: &mut [f32]) {
+        self.mixer().render(output);
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.mixer().sample_rate
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.mixer().channels
+    }
+
+    //the device stops pulling from the mixer, every voice holds its place
+    pub fn suspend(&self) -> bool {
+        match &self.output {
+            Output::Device(stream) => stream.pause().is_ok(),
+            _ => false,
+        }
+    }
+
+    pub fn resume(&self) -> bool {
+        match &self.output {
+            Output::Device(stream) => stream.play().is_ok(),
+            _ => false,
+        }
+    }
+
+    pub fn is_offline(&self) -> bool {
+        matches!(self.output, Output::Offline)
+    }
+
+    // VOICES ------------------------------------------------------
+    pub fn play(&self, clip: &AudioClip, settings: PlaySettings) -> VoiceId {
+        let voice = Voice::new(VoiceSource::Clip(clip.clone()), settings);
+
+        self.mixer().add_voice(voice)
+    }
+
+    //decoded as it plays instead of loaded whole, for music and long ambiences
+    pub fn stream<P: AsRef<Path>>(
+        &self,
+        path: P,
+        settings: PlaySettings,
+    ) -> Result<VoiceId, Box<dyn Error>> {
+        let stream = Stream::open(path.as_ref(), settings.looping)?;
+        let voice = Voice::new(VoiceSource::Stream(stream), settings);
+
+        Ok(self.mixer().add_voice(voice))
+    }
+
+    pub fn stop(&self, voice: VoiceId) -> bool {
+        self.mixer().remove_voice(voice)
+    }
+
+    pub fn stop_all(&self) {
+        self.mixer().voices.clear();
+    }
+
+    //false once it finished or was stopped
+    pub fn is_playing(&self, voice: VoiceId) -> bool {
+        self.mixer().voice_mut(voice).is_some()
+    }
+
+    pub fn voice_count(&self) -> usize {
+        self.mixer().voices.len()
+    }
+
+    pub fn set_paused(&self, voice: VoiceId, paused: bool) -> bool {
+        self.with_voice(voice, |voice| voice.paused = paused)
+    }
+
+    pub fn set_volume(&self, voice: VoiceId, volume: f32) -> bool {
+        self.with_voice(voice, |voice| voice.settings.volume = volume)
+    }
+
+    pub fn set_pitch(&self, voice: VoiceId, pitch: f32) -> bool {
+        self.with_voice(voice, |voice| voice.settings.pitch = pitch)
+    }
+
+    //only moves spatial voices
+    pub fn set_position(&self, voice: VoiceId, position: na::Point3<f32>) -> bool {
+        self.with_voice(voice, |voice| {
+            if let Some(spatial) = voice.settings.spatial.as_mut() {
+                spatial.position = position;
+            }
+        })
+    }
+
+    // LISTENER ----------------------------------------------------
+    pub fn set_listener(&self, listener: Listener) {
+        self.mixer().listener = listener;
+    }
+
+    pub fn listener(&self) -> Listener {
+        self.mixer().listener
+    }
+
+    // BUSES -------------------------------------------------------
+    pub fn add_bus(&self, name: &str) -> BusId {
+        let mut mixer = self.mixer();
+        mixer.buses.push(Bus::new(name));
+
+        BusId(mixer.buses.len() as u32 - 1)
+    }
+
+    pub fn bus(&self, name: &str) -> Option<BusId> {
+        self.mixer()
+            .buses
+            .iter()
+            .position(|bus| bus.name == name)
+            .map(|index| BusId(index as u32))
+    }
+
+    pub fn set_bus_volume(&self, bus: BusId, volume: f32) -> bool {
+        match self.mixer().bus_mut(bus) {
+            Some(bus) => {
+                bus.volume = volume;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn set_bus_muted(&self, bus: BusId, muted: bool) -> bool {
+        match self.mixer().bus_mut(bus) {
+            Some(bus) => {
+                bus.muted = muted;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn with_voice<F: FnOnce(&mut Voice)>(&self, voice: VoiceId, f: F) -> bool {
+        match self.mixer().voice_mut(voice) {
+            Some(voice) => {
+                f(voice);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn mixer(&self) -> MutexGuard<'_, Mixer> {
+        self.mixer.lock().unwrap()
+    }
+
+    fn build_stream<T: cpal::Sample>(
+        device: &cpal::Device,
+        config: &cpal::StreamConfig,
+        mixer: Arc<Mutex<Mixer>>,
+    ) -> Result<cpal::Stream, cpal::BuildStreamError> {
+        let mut mix = Vec::new();
+
+        device.build_output_stream(
+            config,
+            move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+                mix.resize(data.len(), 0.0);
+                match mixer.lock() {
+                    Ok(mut mixer) => mixer.render(&mut mix),
+                    Err(_) => mix.iter_mut().for_each(|sample| *sample = 0.0),
+                }
+
+                for (out, sample) in data.iter_mut().zip(mix.iter()) {
+                    *out = T::from(sample);
+                }
+            },
+            |error| warn!("Audio output error: {}", error),
+        )
+    }
+}
+
+impl Drop for AudioEngine {
+    fn drop(&mut self) {
+        if let Output::Null(running, thread) = &mut self.output {
+            running.store(false, Ordering::Relaxed);
+            if let Some(thread) = thread.take() {
+                let _ = thread.join();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rot_mixer::Spatial;
+
+    const SAMPLE_RATE: u32 = 100;
+
+    //mono, at the engine rate so every output frame is a clip frame
+    fn clip(samples: Vec<f32>) -> AudioClip {
+        AudioClip::from_samples(samples, 1, SAMPLE_RATE, "test")
+    }
+
+    fn render(audio: &AudioEngine, frames: usize) -> Vec<[f32; 2]> {
+        let mut output = vec![0.0; frames * 2];
+        audio.render(&mut output);
+
+        output
+            .chunks_exact(2)
+            .map(|frame| [frame[0], frame[1]])
+            .collect()
+    }
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-5
+    }
+
+    #[test]
+    fn buses_scale_and_mute_their_voices() {
+        let audio = AudioEngine::offline(SAMPLE_RATE, 2);
+        let music = audio.add_bus("music");
+        let settings = PlaySettings {
+            bus: music,
+            looping: true,
+            ..PlaySettings::default()
+        };
+        audio.play(&clip(vec![0.5; 10]), settings);
+
+        assert!(render(&audio, 10)
+            .iter()
+            .all(|[left, right]| close(*left, 0.5) && close(*right, 0.5)));
+
+        //every change ramps over one render
+        assert!(audio.set_bus_volume(music, 0.5));
+        render(&audio, 10);
+        assert!(render(&audio, 10)
+            .iter()
+            .all(|[left, _]| close(*left, 0.25)));
+
+        assert!(audio.set_bus_volume(BusId::MASTER, 0.5));
+        render(&audio, 10);
+        assert!(render(&audio, 10)
+            .iter()
+            .all(|[left, _]| close(*left, 0.125)));
+
+        assert!(audio.set_bus_muted(music, true));
+        render(&audio, 10);
+        assert!(render(&audio, 10)
+            .iter()
+            .all(|[left, right]| *left == 0.0 && *right == 0.0));
+        //muted voices keep playing
+        assert_eq!(audio.voice_count(), 1);
+
+        assert!(!audio.set_bus_volume(BusId(7), 1.0));
+    }
+
+    #[test]
+    fn spatial_voices_fade_with_distance_and_pan_to_their_side() {
+        let listener = Listener::default();
+        let near = Spatial::at(na::Point3::new(0.0, 0.0, -1.0));
+        let far = Spatial::at(na::Point3::new(0.0, 0.0, -4.0));
+        assert!(close(near.attenuation(&listener), 1.0));
+        assert!(close(far.attenuation(&listener), 0.25));
+        assert!(far.attenuation(&listener) < near.attenuation(&listener));
+
+        let right = Spatial::at(na::Point3::new(5.0, 0.0, 0.0));
+        let left = Spatial::at(na::Point3::new(-5.0, 0.0, 0.0));
+        assert!(close(right.pan(&listener), 1.0));
+        assert!(close(left.pan(&listener), -1.0));
+        assert!(close(near.pan(&listener), 0.0));
+
+        //turned around, the sides swap
+        let behind = Listener::look_at(
+            na::Point3::origin(),
+            na::Point3::new(0.0, 0.0, 1.0),
+            na::Vector3::y(),
+        );
+        assert!(close(right.pan(&behind), -1.0));
+
+        let audio = AudioEngine::offline(SAMPLE_RATE, 2);
+        let voice = audio.play(
+            &clip(vec![0.5; 10]),
+            PlaySettings {
+                looping: true,
+                spatial: Some(Spatial::at(na::Point3::new(2.0, 0.0, 0.0))),
+                ..PlaySettings::default()
+            },
+        );
+        let [left, right] = render(&audio, 10)[5];
+        assert!(right > left);
+
+        assert!(audio.set_position(voice, na::Point3::new(-2.0, 0.0, 0.0)));
+        render(&audio, 10);
+        let [left, right] = render(&audio, 10)[5];
+        assert!(left > right);
+    }
+
+    #[test]
+    fn looping_clips_start_over() {
+        let audio = AudioEngine::offline(SAMPLE_RATE, 2);
+        let voice = audio.play(
+            &clip(vec![0.1, 0.2, 0.3, 0.4]),
+            PlaySettings {
+                looping: true,
+                ..PlaySettings::default()
+            },
+        );
+
+        let played = render(&audio, 10)
+            .iter()
+            .map(|[left, _]| *left)
+            .collect::<Vec<_>>();
+        let expected = [0.1, 0.2, 0.3, 0.4, 0.1, 0.2, 0.3, 0.4, 0.1, 0.2];
+        assert!(played
+            .iter()
+            .zip(expected.iter())
+            .all(|(a, b)| close(*a, *b)));
+
+        render(&audio, 1000);
+        assert!(audio.is_playing(voice));
+    }
+
+    #[test]
+    fn voices_are_removed_when_their_clip_ends() {
+        let audio = AudioEngine::offline(SAMPLE_RATE, 1);
+        let voice = audio.play(&clip(vec![0.5; 5]), PlaySettings::default());
+        assert!(audio.is_playing(voice));
+
+        let mut output = vec![0.0; 10];
+        audio.render(&mut output);
+        assert!(output[..5].iter().all(|sample| close(*sample, 0.5)));
+        assert!(output[5..].iter().all(|sample| *sample == 0.0));
+
+        assert!(!audio.is_playing(voice));
+        assert_eq!(audio.voice_count(), 0);
+        assert!(!audio.stop(voice));
+    }
+
+    #[test]
+    fn streams_play_every_frame_and_end() {
+        let path =
+            std::env::temp_dir().join(format!("rot_audio_stream_{}.wav", std::process::id()));
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: SAMPLE_RATE,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let frames = 10_000;
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for _ in 0..frames {
+            writer.write_sample(0.5f32).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let audio = AudioEngine::offline(SAMPLE_RATE, 1);
+        let voice = audio.stream(&path, PlaySettings::default()).unwrap();
+
+        //the worker may fall behind, the voice waits for it instead of skipping
+        let mut played = 0;
+        let mut output = vec![0.0; 256];
+        for _ in 0..100_000 {
+            if !audio.is_playing(voice) {
+                break;
+            }
+            audio.render(&mut output);
+            played += output.iter().filter(|sample| **sample != 0.0).count();
+            std::thread::yield_now();
+        }
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(!audio.is_playing(voice));
+        assert_eq!(played, frames);
+    }
+}
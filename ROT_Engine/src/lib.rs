@@ -10,12 +10,15 @@ mod log_rot;
 use log::{debug, error, info, trace, warn};
 
 use rot_layer;
-use rot_layer::{Layer, LayerStack};
+use rot_layer::{Layer, LayerStack, Systems};
 
-use rot_events::{event::Event as RotEvent, EventTranslator};
+use rot_events::{event::Event as RotEvent, EventTranslator, Gamepads};
 
 use rot_gui::Gui;
 
+use rot_audio::{AudioEngine, Listener};
+use rot_physics::PhysicsWorld;
+
 use std::sync::mpsc::{channel, Receiver, Sender};
 use std::sync::Arc;
 
@@ -29,6 +32,11 @@ pub struct Engine {
     //Renderer
     renderer: Renderer,
 
+    //Systems, lent to the layers next to the renderer
+    physics: PhysicsWorld,
+    audio: AudioEngine,
+    gamepads: Gamepads,
+
     //Event
     event_receiver: Option<Receiver<Arc<RotEvent>>>,
     event_buffer: Vec<RotEvent>,
@@ -50,9 +58,17 @@ impl Engine {
 
         let event_buffer = Vec::new();
 
+        let audio = AudioEngine::new().unwrap_or_else(|error| {
+            warn!("No audio output, playing into the void: {}", error);
+            AudioEngine::null(48_000)
+        });
+
         Self {
             layer_stack,
             renderer,
+            physics: PhysicsWorld::new(),
+            audio,
+            gamepads: Gamepads::new(),
 
             event_receiver: None,
             event_buffer,
@@ -77,8 +93,25 @@ impl Engine {
 
     #[optick_attr::profile]
     fn update(&mut self, delta_time: f64) {
+        self.renderer.debug_draw().advance(delta_time as f32);
+
+        //registered objects are synced around the steps, the layers see where the bodies are.
+        //The collisions reach the layers with the next frame events
+        self.physics.step(delta_time);
+        self.event_buffer.extend(self.physics.drain_events());
+
+        let mut systems = Systems {
+            physics: &mut self.physics,
+            audio: &self.audio,
+            gamepads: &mut self.gamepads,
+        };
         for layer in self.layer_stack.stack() {
-            layer.on_update(&mut self.renderer, delta_time)
+            layer.on_update(&mut self.renderer, &mut systems, delta_time)
+        }
+
+        //the cameras were set while the layers updated
+        if let Some(view) = self.renderer.window_camera_view() {
+            self.audio.set_listener(Listener::from_view(&view));
         }
     }
 }
@@ -127,12 +160,19 @@ impl ROT_Engine {
 
     pub fn run(mut self) {
         let mut engine = self.engine.take().unwrap();
+        let mut systems = Systems {
+            physics: &mut engine.physics,
+            audio: &engine.audio,
+            gamepads: &mut engine.gamepads,
+        };
         for layer in engine.layer_stack.stack() {
-            layer.on_attach(&mut engine.renderer)
+            layer.on_attach(&mut engine.renderer, &mut systems)
         }
 
         let mut event_loop = self.event_loop.take().unwrap();
         let window = self.window.take().unwrap();
+        let mut last_frame = std::time::Instant::now();
+        let mut cursor_grabbed = false;
 
         event_loop.run_return(move |event, _, control_flow| match event {
             Event::WindowEvent {
@@ -176,12 +216,32 @@ impl ROT_Engine {
                 }
                 _ => {}
             },
+            Event::DeviceEvent { ref event, .. } => {
+                let rot_event = EventTranslator::mouse_motion(event);
+                match rot_event {
+                    None => {}
+                    Some(ev) => engine.event_buffer.push(RotEvent::MouseMotion(ev)),
+                }
+            }
             Event::RedrawRequested(_) => {
+                let now = std::time::Instant::now();
+                let delta_time = now.duration_since(last_frame).as_secs_f64();
+                last_frame = now;
+
                 engine.dispatch_events();
-                engine.update(0.5);
+                engine.update(delta_time);
                 engine.renderer.render();
+
+                let cursor_grab = engine.renderer.cursor_grab();
+                if cursor_grab != cursor_grabbed {
+                    //not every platform can grab, the cursor is still hidden then
+                    let _ = window.set_cursor_grab(cursor_grab);
+                    window.set_cursor_visible(!cursor_grab);
+                    cursor_grabbed = cursor_grab;
+                }
             }
             Event::MainEventsCleared => {
+                engine.gamepads.poll(&mut engine.event_buffer);
                 window.request_redraw();
             }
             _ => {}
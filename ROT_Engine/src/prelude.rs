@@ -1,13 +1,35 @@
 pub use crate::ROT_Engine;
 
+pub use rot_audio::{AudioClip, AudioEngine, BusId, Listener, PlaySettings, Spatial, VoiceId};
+
+pub use rot_wgpu::rot_animation::{
+    AnimationClip, AnimationPlayer, Animator, Easing, LoopMode, Skeleton, SkinnedObject, Track,
+    Tween, TweenId,
+};
+pub use rot_wgpu::rot_debug_draw::{DebugDraw, DebugOptions, RenderDebugMode};
+pub use rot_wgpu::rot_particles::{Curve, EmitterSettings, ParticleBlend, ParticleEmitter};
+pub use rot_wgpu::rot_picking::{Bvh, ObjectHit, PickResult, Picker, Ray, RayHit};
 pub use rot_wgpu::rot_pipeline::Pipeline;
 pub use rot_wgpu::rot_primitives::{
-    Camera, Instance, Light, Material, Mesh, Object, Primitive, Vertex,
+    Aabb, AlphaMode, BoundingSphere, Camera, CameraController, CameraStatus, ClearPolicy,
+    FirstPersonController, FlyController, Frustum, Instance, InstanceData, InstanceId,
+    InstanceLayout, InstanceUniform, KeyBindings, KeyboardController, Light, LodMetric, Material,
+    Mesh, Object, ObjectId, OrbitController, Primitive, Projection, RenderTarget, Texture, Vertex,
+    Viewport, ViewportRect,
 };
+pub use rot_wgpu::rot_sprite::{
+    Camera2D, Sprite, SpriteAnimation, SpriteBatch, TextureAtlas, UvRect,
+};
+pub use rot_wgpu::rot_text::{SdfFont, TextAlign, TextBatch, TextPlacement, TextShadow, TextStyle};
 pub use rot_wgpu::Renderer;
 
+pub use rot_physics::{BodyKind, BodySettings, ColliderShape, PhysicsWorld};
+
 pub use rot_events::event::{Event, State};
+pub use rot_events::physics::{BodyId, Collision, ContactState};
+pub use rot_events::GamepadInput::{GamepadId, PadAxis, PadButton};
 pub use rot_events::KeyboardInput::KeyCode;
 pub use rot_events::MouseInput::Button;
+pub use rot_events::{DeadZones, Gamepads};
 
-pub use rot_layer::Layer;
+pub use rot_layer::{Layer, Systems};
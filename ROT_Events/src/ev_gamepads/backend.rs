@@ -0,0 +1,48 @@
+use crate::rot_events::event::State;
+use crate::rot_events::GamepadInput::{PadAxis, PadButton};
+use std::time::Duration;
+
+/// What a backend reports, the gamepad is named by the backend's own index
+#[derive(Debug, Clone, PartialEq)]
+pub enum RawGamepadEvent {
+    Connected {
+        device: usize,
+        /// Tells a reconnecting gamepad apart from a new one
+        uuid: [u8; 16],
+        name: String,
+        rumble: bool,
+    },
+    Disconnected {
+        device: usize,
+    },
+    Button {
+        device: usize,
+        button: PadButton,
+        state: State,
+    },
+    /// Before the dead zone
+    Axis {
+        device: usize,
+        axis: PadAxis,
+        value: f32,
+    },
+}
+
+pub trait GamepadBackend {
+    /// Appends what happened since the last call
+    fn poll(&mut self, events: &mut Vec<RawGamepadEvent>);
+
+    /// Strong and weak motors in 0.0..1.0, a zero duration stops them. False when it can't
+    fn rumble(&mut self, device: usize, strong: f32, weak: f32, duration: Duration) -> bool;
+}
+
+/// For platforms without gamepad support, nothing ever connects
+pub(crate) struct NoGamepads;
+
+impl GamepadBackend for NoGamepads {
+    fn poll(&mut self, _events: &mut Vec<RawGamepadEvent>) {}
+
+    fn rumble(&mut self, _device: usize, _strong: f32, _weak: f32, _duration: Duration) -> bool {
+        false
+    }
+}
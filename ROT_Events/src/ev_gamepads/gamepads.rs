@@ -0,0 +1,514 @@
+#[allow(unused_imports)]
+use log::{debug, error, info, trace, warn};
+
+use crate::ev_gamepads::{GamepadBackend, NoGamepads, RawGamepadEvent};
+use crate::ev_translator_gilrs::GilrsBackend;
+use crate::rot_events::event::{Event, State};
+use crate::rot_events::GamepadInput::{
+    GamepadAxis, GamepadButton, GamepadConnection, GamepadId, PadAxis, PadButton,
+};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+/// Below these the sticks and triggers read 0.0, the rest of the range is stretched back to 1.0
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DeadZones {
+    /// Radial, so diagonals aren't cut
+    pub sticks: f32,
+    pub triggers: f32,
+}
+
+impl Default for DeadZones {
+    fn default() -> Self {
+        Self {
+            sticks: 0.15,
+            triggers: 0.05,
+        }
+    }
+}
+
+struct Pad {
+    uuid: [u8; 16],
+    name: String,
+    //None while disconnected
+    device: Option<usize>,
+    rumble: bool,
+
+    pressed: HashSet<PadButton>,
+    //indexed like PadAxis::ALL, before and after the dead zones
+    raw: [f32; 6],
+    values: [f32; 6],
+}
+
+/// Turns what the backend reports into rot events, and keeps the state of every gamepad
+pub struct Gamepads {
+    backend: Box<dyn GamepadBackend>,
+    pub dead_zones: DeadZones,
+
+    //indexed by GamepadId
+    pads: Vec<Pad>,
+    devices: HashMap<usize, GamepadId>,
+    raw_events: Vec<RawGamepadEvent>,
+}
+
+impl Gamepads {
+    /// Through gilrs, without gamepads where it isn't supported
+    pub fn new() -> Self {
+        match GilrsBackend::new() {
+            Ok(backend) => Gamepads::with_backend(Box::new(backend)),
+            Err(error) => {
+                warn!("Gamepads unavailable: {}", error);
+                Gamepads::with_backend(Box::new(NoGamepads))
+            }
+        }
+    }
+
+    /// A MockGamepadBackend in tests
+    pub fn with_backend(backend: Box<dyn GamepadBackend>) -> Self {
+        Self {
+            backend,
+            dead_zones: DeadZones::default(),
+            pads: Vec::new(),
+            devices: HashMap::new(),
+            raw_events: Vec::new(),
+        }
+    }
+
+    /// Appends the events since the last poll, call it once per loop
+    pub fn poll(&mut self, events: &mut Vec<Event>) {
+        let mut raw_events = std::mem::take(&mut self.raw_events);
+        self.backend.poll(&mut raw_events);
+
+        for raw_event in raw_events.drain(..) {
+            match raw_event {
+                RawGamepadEvent::Connected {
+                    device,
+                    uuid,
+                    name,
+                    rumble,
+                } => self.connect(device, uuid, name, rumble, events),
+                RawGamepadEvent::Disconnected { device } => self.disconnect(device, events),
+                RawGamepadEvent::Button {
+                    device,
+                    button,
+                    state,
+                } => self.button(device, button, state, events),
+                RawGamepadEvent::Axis {
+                    device,
+                    axis,
+                    value,
+                } => self.axis(device, axis, value, events),
+            }
+        }
+
+        self.raw_events = raw_events;
+    }
+
+    /// Connected ones only, in id order
+    pub fn gamepads(&self) -> Vec<GamepadId> {
+        self.pads
+            .iter()
+            .enumerate()
+            .filter(|(_, pad)| pad.device.is_some())
+            .map(|(index, _)| GamepadId(index as u32))
+            .collect()
+    }
+
+    pub fn is_connected(&self, gamepad: GamepadId) -> bool {
+        matches!(self.pad(gamepad), Some(pad) if pad.device.is_some())
+    }
+
+    pub fn name(&self, gamepad: GamepadId) -> Option<&str> {
+        self.pad(gamepad).map(|pad| pad.name.as_str())
+    }
+
+    pub fn is_pressed(&self, gamepad: GamepadId, button: PadButton) -> bool {
+        matches!(self.pad(gamepad), Some(pad) if pad.pressed.contains(&button))
+    }
+
+    /// Past the dead zone, 0.0 while disconnected
+    pub fn axis_value(&self, gamepad: GamepadId, axis: PadAxis) -> f32 {
+        self.pad(gamepad)
+            .map_or(0.0, |pad| pad.values[Gamepads::axis_index(axis)])
+    }
+
+    pub fn supports_rumble(&self, gamepad: GamepadId) -> bool {
+        matches!(self.pad(gamepad), Some(pad) if pad.device.is_some() && pad.rumble)
+    }
+
+    /// Strong and weak motors in 0.0..1.0. False when the gamepad is gone or can't rumble
+    pub fn rumble(
+        &mut self,
+        gamepad: GamepadId,
+        strong: f32,
+        weak: f32,
+        duration: Duration,
+    ) -> bool {
+        let device = match self.pad(gamepad) {
+            Some(Pad {
+                device: Some(device),
+                rumble: true,
+                ..
+            }) => *device,
+            _ => return false,
+        };
+
+        self.backend.rumble(
+            device,
+            strong.clamp(0.0, 1.0),
+            weak.clamp(0.0, 1.0),
+            duration,
+        )
+    }
+
+    pub fn stop_rumble(&mut self, gamepad: GamepadId) -> bool {
+        self.rumble(gamepad, 0.0, 0.0, Duration::from_secs(0))
+    }
+
+    fn pad(&self, gamepad: GamepadId) -> Option<&Pad> {
+        self.pads.get(gamepad.0 as usize)
+    }
+
+    fn connect(
+        &mut self,
+        device: usize,
+        uuid: [u8; 16],
+        name: String,
+        rumble: bool,
+        events: &mut Vec<Event>,
+    ) {
+        if self.devices.contains_key(&device) {
+            self.disconnect(device, events);
+        }
+
+        //the same model plugged back takes the first id it left free
+        let index = match self
+            .pads
+            .iter()
+            .position(|pad| pad.device.is_none() && pad.uuid == uuid)
+        {
+            Some(index) => index,
+            None => {
+                self.pads.push(Pad {
+                    uuid,
+                    name: String::new(),
+                    device: None,
+                    rumble: false,
+                    pressed: HashSet::new(),
+                    raw: [0.0; 6],
+                    values: [0.0; 6],
+                });
+                self.pads.len() - 1
+            }
+        };
+
+        let gamepad = GamepadId(index as u32);
+        let pad = &mut self.pads[index];
+        pad.name = name.clone();
+        pad.device = Some(device);
+        pad.rumble = rumble;
+        self.devices.insert(device, gamepad);
+
+        info!("Gamepad {} connected: {}", gamepad.0, name);
+        events.push(Event::GamepadConnection(GamepadConnection {
+            gamepad,
+            connected: true,
+            name,
+        }));
+    }
+
+    //what was held is forgotten without release events, the connection event says it all
+    fn disconnect(&mut self, device: usize, events: &mut Vec<Event>) {
+        let gamepad = match self.devices.remove(&device) {
+            Some(gamepad) => gamepad,
+            None => return,
+        };
+
+        let pad = &mut self.pads[gamepad.0 as usize];
+        pad.device = None;
+        pad.pressed.clear();
+        pad.raw = [0.0; 6];
+        pad.values = [0.0; 6];
+
+        info!("Gamepad {} disconnected", gamepad.0);
+        events.push(Event::GamepadConnection(GamepadConnection {
+            gamepad,
+            connected: false,
+            name: pad.name.clone(),
+        }));
+    }
+
+    fn button(&mut self, device: usize, button: PadButton, state: State, events: &mut Vec<Event>) {
+        let gamepad = match self.devices.get(&device) {
+            Some(&gamepad) => gamepad,
+            None => return,
+        };
+
+        let pad = &mut self.pads[gamepad.0 as usize];
+        //backends repeat held buttons
+        let changed = match state {
+            State::Pressed => pad.pressed.insert(button),
+            State::Released => pad.pressed.remove(&button),
+        };
+
+        if changed {
+            events.push(Event::GamepadButton(GamepadButton {
+                gamepad,
+                state,
+                button,
+            }));
+        }
+    }
+
+    fn axis(&mut self, device: usize, axis: PadAxis, value: f32, events: &mut Vec<Event>) {
+        let gamepad = match self.devices.get(&device) {
+            Some(&gamepad) => gamepad,
+            None => return,
+        };
+
+        let dead_zones = self.dead_zones;
+        let pad = &mut self.pads[gamepad.0 as usize];
+        pad.raw[Gamepads::axis_index(axis)] = value;
+
+        //moving one axis of a stick can move the other out of the radial dead zone
+        let (axes, values) = match axis {
+            PadAxis::LeftStickX | PadAxis::LeftStickY => {
+                let (x, y) = Gamepads::stick(dead_zones.sticks, pad.raw[0], pad.raw[1]);
+                ([PadAxis::LeftStickX, PadAxis::LeftStickY], [x, y])
+            }
+            PadAxis::RightStickX | PadAxis::RightStickY => {
+                let (x, y) = Gamepads::stick(dead_zones.sticks, pad.raw[2], pad.raw[3]);
+                ([PadAxis::RightStickX, PadAxis::RightStickY], [x, y])
+            }
+            PadAxis::LeftTrigger | PadAxis::RightTrigger => {
+                let value = Gamepads::trigger(dead_zones.triggers, value);
+                ([axis, axis], [value, value])
+            }
+        };
+
+        for (&axis, &value) in axes.iter().zip(values.iter()) {
+            let index = Gamepads::axis_index(axis);
+            if pad.values[index] != value {
+                pad.values[index] = value;
+                events.push(Event::GamepadAxis(GamepadAxis {
+                    gamepad,
+                    axis,
+                    value,
+                }));
+            }
+        }
+    }
+
+    fn stick(dead_zone: f32, x: f32, y: f32) -> (f32, f32) {
+        let dead_zone = dead_zone.clamp(0.0, 0.99);
+        let magnitude = (x * x + y * y).sqrt();
+        if magnitude <= dead_zone {
+            return (0.0, 0.0);
+        }
+
+        let scale = ((magnitude - dead_zone) / (1.0 - dead_zone)).min(1.0) / magnitude;
+        (x * scale, y * scale)
+    }
+
+    fn trigger(dead_zone: f32, value: f32) -> f32 {
+        let dead_zone = dead_zone.clamp(0.0, 0.99);
+        if value <= dead_zone {
+            return 0.0;
+        }
+
+        ((value - dead_zone) / (1.0 - dead_zone)).min(1.0)
+    }
+
+    fn axis_index(axis: PadAxis) -> usize {
+        PadAxis::ALL.iter().position(|&a| a == axis).unwrap()
+    }
+}
+
+impl Default for Gamepads {
+    fn default() -> Self {
+        Gamepads::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ev_gamepads::MockGamepadBackend;
+
+    fn connect(mock: &MockGamepadBackend, device: usize, model: u8, rumble: bool) {
+        mock.push(RawGamepadEvent::Connected {
+            device,
+            uuid: [model; 16],
+            name: format!("Pad {}", model),
+            rumble,
+        });
+    }
+
+    fn setup() -> (MockGamepadBackend, Gamepads) {
+        let mock = MockGamepadBackend::default();
+        let gamepads = Gamepads::with_backend(Box::new(mock.clone()));
+        (mock, gamepads)
+    }
+
+    #[test]
+    fn reconnecting_gets_the_same_id() {
+        let (mock, mut gamepads) = setup();
+        let mut events = Vec::new();
+
+        connect(&mock, 4, 1, false);
+        connect(&mock, 5, 2, false);
+        gamepads.poll(&mut events);
+        assert_eq!(gamepads.gamepads(), vec![GamepadId(0), GamepadId(1)]);
+
+        mock.push(RawGamepadEvent::Disconnected { device: 4 });
+        gamepads.poll(&mut events);
+        assert!(!gamepads.is_connected(GamepadId(0)));
+        assert_eq!(gamepads.gamepads(), vec![GamepadId(1)]);
+
+        //the backend hands out a new index, the model is what matters
+        connect(&mock, 9, 1, false);
+        gamepads.poll(&mut events);
+        assert_eq!(gamepads.gamepads(), vec![GamepadId(0), GamepadId(1)]);
+
+        let connections: Vec<(GamepadId, bool)> = events
+            .iter()
+            .map(|event| match event {
+                Event::GamepadConnection(connection) => (connection.gamepad, connection.connected),
+                _ => panic!("only connections were sent"),
+            })
+            .collect();
+        assert_eq!(
+            connections,
+            vec![
+                (GamepadId(0), true),
+                (GamepadId(1), true),
+                (GamepadId(0), false),
+                (GamepadId(0), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn repeated_presses_are_sent_once() {
+        let (mock, mut gamepads) = setup();
+        let mut events = Vec::new();
+        connect(&mock, 0, 1, false);
+        gamepads.poll(&mut events);
+        events.clear();
+
+        for &state in &[
+            State::Pressed,
+            State::Pressed,
+            State::Released,
+            State::Released,
+        ] {
+            mock.push(RawGamepadEvent::Button {
+                device: 0,
+                button: PadButton::South,
+                state,
+            });
+        }
+        gamepads.poll(&mut events);
+
+        let states: Vec<State> = events
+            .iter()
+            .map(|event| match event {
+                Event::GamepadButton(button) => button.state,
+                _ => panic!("only buttons were sent"),
+            })
+            .collect();
+        assert_eq!(states, vec![State::Pressed, State::Released]);
+        assert!(!gamepads.is_pressed(GamepadId(0), PadButton::South));
+    }
+
+    #[test]
+    fn sticks_are_rescaled_radially() {
+        let close =
+            |a: (f32, f32), b: (f32, f32)| (a.0 - b.0).abs() < 1e-5 && (a.1 - b.1).abs() < 1e-5;
+
+        //inside the circle, even though each axis alone is past it
+        assert_eq!(Gamepads::stick(0.2, 0.13, 0.13), (0.0, 0.0));
+        //halfway between the dead zone and the rim reads half, the direction is kept
+        assert!(close(Gamepads::stick(0.2, 0.6, 0.0), (0.5, 0.0)));
+        let (x, y) = Gamepads::stick(0.2, 0.6 * 0.8, -0.6 * 0.6);
+        assert!(close((x, y), (0.5 * 0.8, -0.5 * 0.6)));
+        //corners of square gates don't go past the rim
+        let (x, y) = Gamepads::stick(0.2, 1.0, 1.0);
+        assert!(((x * x + y * y).sqrt() - 1.0).abs() < 1e-5);
+        assert!((x - y).abs() < 1e-5);
+    }
+
+    #[test]
+    fn triggers_are_rescaled() {
+        assert_eq!(Gamepads::trigger(0.2, 0.1), 0.0);
+        assert_eq!(Gamepads::trigger(0.2, 0.2), 0.0);
+        assert!((Gamepads::trigger(0.2, 0.6) - 0.5).abs() < 1e-5);
+        assert_eq!(Gamepads::trigger(0.2, 1.0), 1.0);
+    }
+
+    #[test]
+    fn axis_events_use_the_dead_zones() {
+        let (mock, mut gamepads) = setup();
+        let mut events = Vec::new();
+        gamepads.dead_zones = DeadZones {
+            sticks: 0.2,
+            triggers: 0.2,
+        };
+        connect(&mock, 0, 1, false);
+        gamepads.poll(&mut events);
+        events.clear();
+
+        mock.push(RawGamepadEvent::Axis {
+            device: 0,
+            axis: PadAxis::LeftStickX,
+            value: 0.1,
+        });
+        mock.push(RawGamepadEvent::Axis {
+            device: 0,
+            axis: PadAxis::RightTrigger,
+            value: 0.6,
+        });
+        gamepads.poll(&mut events);
+
+        //the stick stayed in its dead zone
+        assert_eq!(events.len(), 1);
+        assert_eq!(gamepads.axis_value(GamepadId(0), PadAxis::LeftStickX), 0.0);
+        assert!((gamepads.axis_value(GamepadId(0), PadAxis::RightTrigger) - 0.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn rumble_goes_to_the_gamepads_device() {
+        let (mock, mut gamepads) = setup();
+        connect(&mock, 3, 1, true);
+        connect(&mock, 8, 2, true);
+        gamepads.poll(&mut Vec::new());
+
+        assert!(gamepads.rumble(GamepadId(1), 2.0, 0.25, Duration::from_millis(100)));
+        assert!(gamepads.stop_rumble(GamepadId(0)));
+
+        let rumbles = mock.rumbles();
+        assert_eq!(rumbles.len(), 2);
+        assert_eq!(rumbles[0].device, 8);
+        assert_eq!((rumbles[0].strong, rumbles[0].weak), (1.0, 0.25));
+        assert_eq!(rumbles[1].device, 3);
+        assert_eq!(rumbles[1].duration, Duration::from_secs(0));
+    }
+
+    #[test]
+    fn rumble_is_refused_without_support() {
+        let (mock, mut gamepads) = setup();
+        connect(&mock, 0, 1, false);
+        connect(&mock, 1, 2, true);
+        gamepads.poll(&mut Vec::new());
+
+        assert!(!gamepads.supports_rumble(GamepadId(0)));
+        assert!(!gamepads.rumble(GamepadId(0), 1.0, 1.0, Duration::from_millis(100)));
+        //gone or never connected
+        mock.push(RawGamepadEvent::Disconnected { device: 1 });
+        gamepads.poll(&mut Vec::new());
+        assert!(!gamepads.rumble(GamepadId(1), 1.0, 1.0, Duration::from_millis(100)));
+        assert!(!gamepads.rumble(GamepadId(7), 1.0, 1.0, Duration::from_millis(100)));
+
+        assert!(mock.rumbles().is_empty());
+    }
+}
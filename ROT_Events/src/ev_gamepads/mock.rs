@@ -0,0 +1,50 @@
+use crate::ev_gamepads::{GamepadBackend, RawGamepadEvent};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MockRumble {
+    pub device: usize,
+    pub strong: f32,
+    pub weak: f32,
+    pub duration: Duration,
+}
+
+/// Hands out the events pushed into it, for tests. Clones share them:
+///   let mock = MockGamepadBackend::default();
+///   let mut gamepads = Gamepads::with_backend(Box::new(mock.clone()));
+///   mock.push(RawGamepadEvent::Disconnected { device: 0 });
+#[derive(Debug, Clone, Default)]
+pub struct MockGamepadBackend {
+    events: Rc<RefCell<Vec<RawGamepadEvent>>>,
+    rumbles: Rc<RefCell<Vec<MockRumble>>>,
+}
+
+impl MockGamepadBackend {
+    pub fn push(&self, event: RawGamepadEvent) {
+        self.events.borrow_mut().push(event);
+    }
+
+    /// Every rumble asked for so far
+    pub fn rumbles(&self) -> Vec<MockRumble> {
+        self.rumbles.borrow().clone()
+    }
+}
+
+impl GamepadBackend for MockGamepadBackend {
+    fn poll(&mut self, events: &mut Vec<RawGamepadEvent>) {
+        events.append(&mut self.events.borrow_mut());
+    }
+
+    fn rumble(&mut self, device: usize, strong: f32, weak: f32, duration: Duration) -> bool {
+        self.rumbles.borrow_mut().push(MockRumble {
+            device,
+            strong,
+            weak,
+            duration,
+        });
+
+        true
+    }
+}
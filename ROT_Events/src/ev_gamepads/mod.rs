@@ -0,0 +1,9 @@
+mod backend;
+mod gamepads;
+mod mock;
+
+pub use backend::{GamepadBackend, RawGamepadEvent};
+pub use gamepads::{DeadZones, Gamepads};
+pub use mock::{MockGamepadBackend, MockRumble};
+
+pub(crate) use backend::NoGamepads;
@@ -0,0 +1,190 @@
+#[allow(unused_imports)]
+use log::{debug, error, info, trace, warn};
+
+use crate::ev_gamepads::{GamepadBackend, RawGamepadEvent};
+use crate::rot_events::event::State;
+use crate::rot_events::GamepadInput::{PadAxis, PadButton};
+use std::collections::HashMap;
+use std::error::Error;
+use std::time::Duration;
+
+use gilrs::ff::{BaseEffect, BaseEffectType, Effect, EffectBuilder, Repeat, Replay, Ticks};
+use gilrs::{Axis, Button, EventType, Gilrs};
+
+/// Gamepads of the system, the devices are gilrs' own ids
+pub struct GilrsBackend {
+    gilrs: Gilrs,
+    //connections the first poll reports, gilrs doesn't send events for what was already plugged
+    pending: Vec<RawGamepadEvent>,
+    //one per gamepad, replaced by every rumble
+    effects: HashMap<usize, Effect>,
+}
+
+impl GilrsBackend {
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        let gilrs = Gilrs::new()?;
+        let pending = gilrs
+            .gamepads()
+            .map(|(id, gamepad)| RawGamepadEvent::Connected {
+                device: id.into(),
+                uuid: gamepad.uuid(),
+                name: gamepad.name().to_string(),
+                rumble: gamepad.is_ff_supported(),
+            })
+            .collect();
+
+        Ok(Self {
+            gilrs,
+            pending,
+            effects: HashMap::new(),
+        })
+    }
+}
+
+impl GamepadBackend for GilrsBackend {
+    fn poll(&mut self, events: &mut Vec<RawGamepadEvent>) {
+        events.append(&mut self.pending);
+
+        while let Some(gilrs::Event { id, event, .. }) = self.gilrs.next_event() {
+            let device: usize = id.into();
+
+            let raw_event = match event {
+                EventType::Connected => {
+                    let gamepad = self.gilrs.gamepad(id);
+                    Some(RawGamepadEvent::Connected {
+                        device,
+                        uuid: gamepad.uuid(),
+                        name: gamepad.name().to_string(),
+                        rumble: gamepad.is_ff_supported(),
+                    })
+                }
+                EventType::Disconnected => {
+                    self.effects.remove(&device);
+                    Some(RawGamepadEvent::Disconnected { device })
+                }
+                EventType::ButtonPressed(button, _) => {
+                    pad_button(button).map(|button| RawGamepadEvent::Button {
+                        device,
+                        button,
+                        state: State::Pressed,
+                    })
+                }
+                EventType::ButtonReleased(button, _) => {
+                    pad_button(button).map(|button| RawGamepadEvent::Button {
+                        device,
+                        button,
+                        state: State::Released,
+                    })
+                }
+                //the analog triggers come as buttons on most pads, and as the z axes on some
+                EventType::ButtonChanged(Button::LeftTrigger2, value, _)
+                | EventType::AxisChanged(Axis::LeftZ, value, _) => Some(RawGamepadEvent::Axis {
+                    device,
+                    axis: PadAxis::LeftTrigger,
+                    value,
+                }),
+                EventType::ButtonChanged(Button::RightTrigger2, value, _)
+                | EventType::AxisChanged(Axis::RightZ, value, _) => Some(RawGamepadEvent::Axis {
+                    device,
+                    axis: PadAxis::RightTrigger,
+                    value,
+                }),
+                EventType::AxisChanged(axis, value, _) => {
+                    pad_axis(axis).map(|axis| RawGamepadEvent::Axis {
+                        device,
+                        axis,
+                        value,
+                    })
+                }
+                _ => None,
+            };
+
+            if let Some(raw_event) = raw_event {
+                events.push(raw_event);
+            }
+        }
+    }
+
+    fn rumble(&mut self, device: usize, strong: f32, weak: f32, duration: Duration) -> bool {
+        //dropping the effect stops it
+        self.effects.remove(&device);
+        if duration.as_millis() == 0 || (strong <= 0.0 && weak <= 0.0) {
+            return true;
+        }
+
+        let id = match self.gilrs.gamepads().find(|(id, _)| device == (*id).into()) {
+            Some((id, gamepad)) if gamepad.is_ff_supported() => id,
+            _ => return false,
+        };
+
+        let ticks = Ticks::from_ms(duration.as_millis().min(u32::MAX as u128) as u32);
+        let scheduling = Replay {
+            play_for: ticks,
+            ..Replay::default()
+        };
+        let magnitude = |value: f32| (value * u16::MAX as f32) as u16;
+
+        let effect = EffectBuilder::new()
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Strong {
+                    magnitude: magnitude(strong),
+                },
+                scheduling,
+                ..BaseEffect::default()
+            })
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Weak {
+                    magnitude: magnitude(weak),
+                },
+                scheduling,
+                ..BaseEffect::default()
+            })
+            .repeat(Repeat::For(ticks))
+            .gamepads(&[id])
+            .finish(&mut self.gilrs);
+
+        match effect.and_then(|effect| effect.play().map(|_| effect)) {
+            Ok(effect) => {
+                self.effects.insert(device, effect);
+                true
+            }
+            Err(error) => {
+                warn!("Can't rumble gamepad {}: {}", device, error);
+                false
+            }
+        }
+    }
+}
+
+fn pad_button(button: Button) -> Option<PadButton> {
+    match button {
+        Button::South => Some(PadButton::South),
+        Button::East => Some(PadButton::East),
+        Button::North => Some(PadButton::North),
+        Button::West => Some(PadButton::West),
+        Button::LeftTrigger => Some(PadButton::LeftBumper),
+        Button::RightTrigger => Some(PadButton::RightBumper),
+        Button::LeftTrigger2 => Some(PadButton::LeftTrigger),
+        Button::RightTrigger2 => Some(PadButton::RightTrigger),
+        Button::Select => Some(PadButton::Select),
+        Button::Start => Some(PadButton::Start),
+        Button::Mode => Some(PadButton::Mode),
+        Button::LeftThumb => Some(PadButton::LeftStick),
+        Button::RightThumb => Some(PadButton::RightStick),
+        Button::DPadUp => Some(PadButton::DPadUp),
+        Button::DPadDown => Some(PadButton::DPadDown),
+        Button::DPadLeft => Some(PadButton::DPadLeft),
+        Button::DPadRight => Some(PadButton::DPadRight),
+        _ => None,
+    }
+}
+
+fn pad_axis(axis: Axis) -> Option<PadAxis> {
+    match axis {
+        Axis::LeftStickX => Some(PadAxis::LeftStickX),
+        Axis::LeftStickY => Some(PadAxis::LeftStickY),
+        Axis::RightStickX => Some(PadAxis::RightStickX),
+        Axis::RightStickY => Some(PadAxis::RightStickY),
+        _ => None,
+    }
+}
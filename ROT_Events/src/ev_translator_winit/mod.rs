@@ -3,10 +3,12 @@ use log::{debug, error, info, trace, warn};
 
 use crate::rot_events::event::State;
 use crate::rot_events::KeyboardInput::{KeyCode, KeyboardInputEvent};
-use crate::rot_events::MouseInput::{Button, Coord, MouseButton, MouseMovement, MouseWheel};
+use crate::rot_events::MouseInput::{
+    Button, Coord, MouseButton, MouseMotion, MouseMovement, MouseWheel,
+};
 
 use winit::event::{
-    ElementState, MouseButton as mb, MouseScrollDelta, VirtualKeyCode, WindowEvent,
+    DeviceEvent, ElementState, MouseButton as mb, MouseScrollDelta, VirtualKeyCode, WindowEvent,
 };
 
 pub fn mouse_button(event: &WindowEvent) -> Option<MouseButton> {
@@ -46,6 +48,18 @@ pub fn mouse_movement(event: &WindowEvent) -> Option<MouseMovement> {
     }
 }
 
+pub fn mouse_motion(event: &DeviceEvent) -> Option<MouseMotion> {
+    match event {
+        DeviceEvent::MouseMotion { delta } => Some(MouseMotion {
+            delta: Coord {
+                x: delta.0,
+                y: delta.1,
+            },
+        }),
+        _ => None,
+    }
+}
+
 pub fn mouse_wheel(event: &WindowEvent) -> Option<MouseWheel> {
     match event {
         WindowEvent::MouseWheel { delta, .. } => {
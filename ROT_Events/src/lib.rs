@@ -1,7 +1,15 @@
+mod ev_gamepads;
+pub mod ev_translator_gilrs;
 pub mod ev_translator_winit;
 mod rot_events;
 
+pub use ev_gamepads::{
+    DeadZones, GamepadBackend, Gamepads, MockGamepadBackend, MockRumble, RawGamepadEvent,
+};
+pub use ev_translator_gilrs::GilrsBackend;
 pub use ev_translator_winit as EventTranslator;
 pub use rot_events::event;
 pub use rot_events::KeyboardInput;
 pub use rot_events::MouseInput;
+pub use rot_events::physics;
+pub use rot_events::GamepadInput;
@@ -1,7 +1,9 @@
 #[allow(unused_imports)]
 #[allow(non_camel_case_types)]
 use crate::rot_events::KeyboardInput::KeyboardInputEvent;
-use crate::rot_events::MouseInput::{MouseWheel, MouseButton, MouseMovement};
+use crate::rot_events::GamepadInput::{GamepadAxis, GamepadButton, GamepadConnection};
+use crate::rot_events::MouseInput::{MouseWheel, MouseButton, MouseMotion, MouseMovement};
+use crate::rot_events::physics::Collision;
 use std::fmt::{Debug, Formatter, Pointer};
 
 #[derive(Debug)]
@@ -9,11 +11,16 @@ pub enum Event {
     MouseButton(MouseButton),
     MouseWheel(MouseWheel),
     MouseMovement(MouseMovement),
+    MouseMotion(MouseMotion),
     KeyboardInput(KeyboardInputEvent),
+    Collision(Collision),
+    GamepadConnection(GamepadConnection),
+    GamepadButton(GamepadButton),
+    GamepadAxis(GamepadAxis),
 
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub enum State {
     Pressed,
     Released,
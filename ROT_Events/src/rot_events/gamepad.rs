@@ -0,0 +1,77 @@
+use crate::rot_events::commom::State;
+
+/// Stays the same while the program runs, a reconnecting gamepad of the same model gets its id back.
+/// Told apart by model only, two identical gamepads unplugged together can swap ids
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct GamepadId(pub u32);
+
+#[derive(Debug)]
+pub struct GamepadConnection {
+    pub gamepad: GamepadId,
+    pub connected: bool,
+    pub name: String,
+}
+
+#[derive(Debug)]
+pub struct GamepadButton {
+    pub gamepad: GamepadId,
+    pub state: State,
+    pub button: PadButton,
+}
+
+/// Sent when the value past the dead zone changes
+#[derive(Debug)]
+pub struct GamepadAxis {
+    pub gamepad: GamepadId,
+    pub axis: PadAxis,
+    /// -1.0..1.0 for sticks, up and right positive. 0.0..1.0 for triggers
+    pub value: f32,
+}
+
+/// Named by position, South is A on Xbox pads and Cross on PlayStation ones
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum PadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftBumper,
+    RightBumper,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    /// The logo button in the middle
+    Mode,
+    LeftStick,
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+pub enum PadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+impl PadAxis {
+    pub const ALL: [PadAxis; 6] = [
+        PadAxis::LeftStickX,
+        PadAxis::LeftStickY,
+        PadAxis::RightStickX,
+        PadAxis::RightStickY,
+        PadAxis::LeftTrigger,
+        PadAxis::RightTrigger,
+    ];
+
+    pub fn is_trigger(&self) -> bool {
+        matches!(self, PadAxis::LeftTrigger | PadAxis::RightTrigger)
+    }
+}
@@ -1,9 +1,12 @@
 #[allow(unused_imports)]
 #[allow(non_camel_case_types)]
 pub mod commom;
+pub mod gamepad;
 pub mod keyboard;
 pub mod mouse;
+pub mod physics;
 
 pub use commom as event;
+pub use gamepad as GamepadInput;
 pub use keyboard as KeyboardInput;
 pub use mouse as MouseInput;
@@ -17,7 +17,13 @@ pub struct MouseMovement {
     pub position: Coord<f64>,
 }
 
+/// Raw device motion, keeps coming when the cursor is grabbed or at the edge of the screen
 #[derive(Debug)]
+pub struct MouseMotion {
+    pub delta: Coord<f64>,
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub enum Button {
     Left,
     Right,
@@ -0,0 +1,17 @@
+/// Handed out by the physics world for each body, collisions name the bodies with it
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct BodyId(pub u64);
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum ContactState {
+    Started,
+    Stopped,
+}
+
+#[derive(Debug)]
+pub struct Collision {
+    pub state: ContactState,
+    pub bodies: [BodyId; 2],
+    /// One of them is a sensor, they overlap but nothing was pushed apart
+    pub sensor: bool,
+}
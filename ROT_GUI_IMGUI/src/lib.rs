@@ -5,7 +5,7 @@ use rot_events::{
     KeyboardInput::KeyCode,
     MouseInput::{Button, TypeOfMouseEvent},
 };
-use rot_layer::Layer;
+use rot_layer::{Layer, Systems};
 
 use rot_wgpu::Renderer;
 use winit::dpi::LogicalSize;
@@ -46,7 +46,7 @@ impl Gui {
 }
 
 impl Layer for Gui {
-    fn on_attach(&mut self, renderer: &mut Renderer) {}
+    fn on_attach(&mut self, renderer: &mut Renderer, systems: &mut Systems) {}
 
     fn on_event(&mut self, event: &Event) {
         let io = self.context.io_mut();
@@ -93,7 +93,7 @@ impl Layer for Gui {
         }
     }
 
-    fn on_update(&mut self, renderer: &mut Renderer, delta_time: f64) {}
+    fn on_update(&mut self, renderer: &mut Renderer, systems: &mut Systems, delta_time: f64) {}
 
     fn get_name(&self) -> &String {
         &self.name
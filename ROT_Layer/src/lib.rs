@@ -1,5 +1,7 @@
 mod rot_layerbase;
 mod rot_layerstack;
+mod rot_systems;
 
 pub use rot_layerbase::Layer;
 pub use rot_layerstack::LayerStack;
+pub use rot_systems::Systems;
@@ -0,0 +1,13 @@
+use rot_audio::AudioEngine;
+use rot_events::Gamepads;
+use rot_physics::PhysicsWorld;
+
+//What the engine owns next to the renderer, lent to the layers while they attach and update
+pub struct Systems<'a> {
+    //stepped before the layers update
+    pub physics: &'a mut PhysicsWorld,
+    //heard from the last camera drawing into the window, set after the layers update
+    pub audio: &'a AudioEngine,
+    //polled once per loop, the state matches the events dispatched
+    pub gamepads: &'a mut Gamepads,
+}
@@ -0,0 +1,5 @@
+mod rot_collider;
+mod rot_world;
+
+pub use rot_collider::ColliderShape;
+pub use rot_world::{BodyKind, BodySettings, PhysicsWorld};
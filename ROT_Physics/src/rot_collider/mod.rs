@@ -0,0 +1,234 @@
+use nalgebra as na;
+use rapier3d::geometry::ColliderBuilder;
+use rot_wgpu::rot_primitives::Mesh;
+
+//In the model space of the instance it is attached to, its scale is applied when attached
+#[derive(Debug, Clone)]
+pub enum ColliderShape {
+    //half the size on each axis
+    Box(na::Vector3<f32>),
+    Sphere(f32),
+    //along the Y axis, the half height doesn't count the caps
+    Capsule {
+        half_height: f32,
+        radius: f32,
+    },
+    ConvexHull(Vec<na::Point3<f32>>),
+    //hollow, meant for static and kinematic bodies like terrain and level geometry
+    TriMesh {
+        vertices: Vec<na::Point3<f32>>,
+        indices: Vec<[u32; 3]>,
+    },
+}
+
+impl ColliderShape {
+    pub fn convex_hull(mesh: &Mesh) -> Self {
+        ColliderShape::ConvexHull(ColliderShape::points(mesh))
+    }
+
+    pub fn trimesh(mesh: &Mesh) -> Self {
+        ColliderShape::TriMesh {
+            vertices: ColliderShape::points(mesh),
+            indices: mesh
+                .indices
+                .chunks_exact(3)
+                .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+                .collect(),
+        }
+    }
+
+    //None when the shape is degenerate once scaled, like a flat convex hull or an empty box
+    pub(crate) fn builder(&self, scale: &na::Vector3<f32>) -> Option<ColliderBuilder> {
+        let scale = scale.abs();
+        let scale_point =
+            |point: &na::Point3<f32>| na::Point3::from(point.coords.component_mul(&scale));
+
+        match self {
+            ColliderShape::Box(half_extents) => {
+                let half_extents = half_extents.component_mul(&scale);
+                if half_extents.min() <= 0.0 {
+                    return None;
+                }
+
+                Some(ColliderBuilder::cuboid(
+                    half_extents.x,
+                    half_extents.y,
+                    half_extents.z,
+                ))
+            }
+            //non uniform scales are rounded up, spheres and capsules can't be squashed
+            ColliderShape::Sphere(radius) => {
+                let radius = radius * scale.max();
+                match radius > 0.0 {
+                    true => Some(ColliderBuilder::ball(radius)),
+                    false => None,
+                }
+            }
+            ColliderShape::Capsule {
+                half_height,
+                radius,
+            } => {
+                let radius = radius * scale.x.max(scale.z);
+                match radius > 0.0 {
+                    true => Some(ColliderBuilder::capsule_y(
+                        half_height.max(0.0) * scale.y,
+                        radius,
+                    )),
+                    false => None,
+                }
+            }
+            //parry builds hulls of flat points too, the solver can't use them
+            ColliderShape::ConvexHull(points) => {
+                let points = points.iter().map(scale_point).collect::<Vec<_>>();
+                match ColliderShape::has_volume(&points) {
+                    true => ColliderBuilder::convex_hull(&points),
+                    false => None,
+                }
+            }
+            ColliderShape::TriMesh { vertices, indices } => {
+                if indices.is_empty() {
+                    return None;
+                }
+
+                let vertices = vertices.iter().map(scale_point).collect();
+                Some(ColliderBuilder::trimesh(vertices, indices.clone()))
+            }
+        }
+    }
+
+    //whether some point is off the plane of the three most spread out ones
+    fn has_volume(points: &[na::Point3<f32>]) -> bool {
+        let first = match points.first() {
+            Some(first) => *first,
+            None => return false,
+        };
+        let farthest = |distance: &dyn Fn(&na::Point3<f32>) -> f32| {
+            points.iter().map(|point| (distance(point), *point)).fold(
+                (0.0, first),
+                |farthest, point| match point.0 > farthest.0 {
+                    true => point,
+                    false => farthest,
+                },
+            )
+        };
+
+        let (length, second) = farthest(&|point| (point - first).magnitude());
+        if length <= f32::EPSILON {
+            return false;
+        }
+        let epsilon = length * 1e-4;
+
+        let axis = (second - first) / length;
+        let (height, third) = farthest(&|point| (point - first).cross(&axis).magnitude());
+        if height <= epsilon {
+            return false;
+        }
+
+        let normal = (third - first).cross(&axis).normalize();
+        let (depth, _) = farthest(&|point| (point - first).dot(&normal).abs());
+        depth > epsilon
+    }
+
+    fn points(mesh: &Mesh) -> Vec<na::Point3<f32>> {
+        mesh.vertices
+            .iter()
+            .map(|vertex| na::Point3::from(vertex.position))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit() -> na::Vector3<f32> {
+        na::Vector3::new(1.0, 1.0, 1.0)
+    }
+
+    #[test]
+    fn builds_solid_shapes() {
+        assert!(ColliderShape::Box(na::Vector3::new(1.0, 2.0, 3.0))
+            .builder(&unit())
+            .is_some());
+        assert!(ColliderShape::Sphere(0.5).builder(&unit()).is_some());
+        assert!(ColliderShape::Capsule {
+            half_height: 1.0,
+            radius: 0.5
+        }
+        .builder(&unit())
+        .is_some());
+
+        let tetrahedron = vec![
+            na::Point3::new(0.0, 0.0, 0.0),
+            na::Point3::new(1.0, 0.0, 0.0),
+            na::Point3::new(0.0, 1.0, 0.0),
+            na::Point3::new(0.0, 0.0, 1.0),
+        ];
+        assert!(ColliderShape::ConvexHull(tetrahedron)
+            .builder(&unit())
+            .is_some());
+    }
+
+    #[test]
+    fn degenerate_shapes_build_nothing() {
+        //every point on the y = 0 plane
+        let flat = vec![
+            na::Point3::new(0.0, 0.0, 0.0),
+            na::Point3::new(1.0, 0.0, 0.0),
+            na::Point3::new(0.0, 0.0, 1.0),
+            na::Point3::new(1.0, 0.0, 1.0),
+        ];
+        assert!(ColliderShape::ConvexHull(flat).builder(&unit()).is_none());
+        let collinear = vec![
+            na::Point3::new(0.0, 0.0, 0.0),
+            na::Point3::new(1.0, 1.0, 1.0),
+            na::Point3::new(2.0, 2.0, 2.0),
+        ];
+        assert!(ColliderShape::ConvexHull(collinear)
+            .builder(&unit())
+            .is_none());
+        assert!(ColliderShape::ConvexHull(Vec::new())
+            .builder(&unit())
+            .is_none());
+
+        let empty = ColliderShape::TriMesh {
+            vertices: vec![na::Point3::origin()],
+            indices: Vec::new(),
+        };
+        assert!(empty.builder(&unit()).is_none());
+
+        assert!(ColliderShape::Box(na::Vector3::new(1.0, 0.0, 1.0))
+            .builder(&unit())
+            .is_none());
+        assert!(ColliderShape::Sphere(0.0).builder(&unit()).is_none());
+    }
+
+    #[test]
+    fn scaled_flat_shapes_build_nothing() {
+        let flatten = na::Vector3::new(1.0, 0.0, 1.0);
+        let tetrahedron = vec![
+            na::Point3::new(0.0, 0.0, 0.0),
+            na::Point3::new(1.0, 0.0, 0.0),
+            na::Point3::new(0.0, 1.0, 0.0),
+            na::Point3::new(0.0, 0.0, 1.0),
+        ];
+
+        assert!(ColliderShape::ConvexHull(tetrahedron)
+            .builder(&flatten)
+            .is_none());
+        assert!(ColliderShape::Box(unit()).builder(&flatten).is_none());
+        //rounded up, still round
+        assert!(ColliderShape::Sphere(1.0).builder(&flatten).is_some());
+    }
+
+    #[test]
+    fn scale_is_applied() {
+        let collider = ColliderShape::Sphere(1.0)
+            .builder(&na::Vector3::new(1.0, -3.0, 2.0))
+            .unwrap()
+            .build();
+
+        let ball = collider.shape().as_ball().unwrap();
+        assert!((ball.radius - 3.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,698 @@
+use crate::ColliderShape;
+use crossbeam::channel::{self, Receiver};
+use log::warn;
+use nalgebra as na;
+use rapier3d::dynamics::{
+    BodyStatus, CCDSolver, IntegrationParameters, JointSet, RigidBodyBuilder, RigidBodyHandle,
+    RigidBodySet,
+};
+use rapier3d::geometry::{
+    BroadPhase, ColliderHandle, ColliderSet, ContactEvent, IntersectionEvent, NarrowPhase,
+};
+use rapier3d::pipeline::{ChannelEventCollector, PhysicsPipeline};
+use rot_events::event::Event;
+use rot_events::physics::{BodyId, Collision, ContactState};
+use rot_wgpu::rot_primitives::{InstanceId, Object, ObjectId};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BodyKind {
+    //moved by the simulation and written back to the instance after stepping
+    Dynamic,
+    //follows the instance isometry set before the step, pushes the dynamic bodies out of its way
+    Kinematic,
+    //never moves by itself, set_isometry teleports it
+    Static,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct BodySettings {
+    pub kind: BodyKind,
+    //the mass comes from the collider volume
+    pub density: f32,
+    pub friction: f32,
+    pub restitution: f32,
+    //only reports collisions, nothing is pushed
+    pub sensor: bool,
+    pub linear_damping: f32,
+    pub angular_damping: f32,
+    pub linear_velocity: na::Vector3<f32>,
+    pub angular_velocity: na::Vector3<f32>,
+}
+
+impl Default for BodySettings {
+    fn default() -> Self {
+        Self {
+            kind: BodyKind::Dynamic,
+            density: 1.0,
+            friction: 0.5,
+            restitution: 0.0,
+            sensor: false,
+            linear_damping: 0.0,
+            angular_damping: 0.0,
+            linear_velocity: na::Vector3::zeros(),
+            angular_velocity: na::Vector3::zeros(),
+        }
+    }
+}
+
+struct Body {
+    object: ObjectId,
+    instance: InstanceId,
+    kind: BodyKind,
+    handle: RigidBodyHandle,
+    collider: ColliderHandle,
+}
+
+//Rigid bodies attached to object instances, owned and stepped on a fixed timestep by the engine
+//before the layers update:
+//  let crates = Rc::new(RefCell::new(crates));
+//  systems.physics.add_body(&crates.borrow(), id, &ColliderShape::Box(half), BodySettings::default());
+//  systems.physics.register(&crates);
+//Registered objects feed their kinematic bodies before every step and get their dynamic bodies
+//back after stepping. Objects that aren't registered call sync themselves, a step late.
+//Collisions come back to the layers as Event::Collision
+pub struct PhysicsWorld {
+    pub gravity: na::Vector3<f32>,
+    //seconds simulated per step, whatever the frame rate
+    pub fixed_timestep: f32,
+    //a long frame runs at most this many steps, the rest of it is dropped
+    pub max_steps: u32,
+    accumulator: f32,
+
+    pipeline: PhysicsPipeline,
+    integration_parameters: IntegrationParameters,
+    broad_phase: BroadPhase,
+    narrow_phase: NarrowPhase,
+    rigid_bodies: RigidBodySet,
+    colliders: ColliderSet,
+    joints: JointSet,
+    ccd_solver: CCDSolver,
+    event_collector: ChannelEventCollector,
+    contact_events: Receiver<ContactEvent>,
+    intersection_events: Receiver<IntersectionEvent>,
+
+    next_body_id: u64,
+    bodies: HashMap<BodyId, Body>,
+    instance_bodies: HashMap<(ObjectId, InstanceId), BodyId>,
+    collider_bodies: HashMap<ColliderHandle, BodyId>,
+    //synced around every step, until they are dropped
+    objects: Vec<(ObjectId, Weak<RefCell<Object>>)>,
+    //drained by the engine every frame
+    events: Vec<Event>,
+}
+
+impl PhysicsWorld {
+    pub fn new() -> Self {
+        let (contact_sender, contact_events) = channel::unbounded();
+        let (intersection_sender, intersection_events) = channel::unbounded();
+
+        Self {
+            gravity: na::Vector3::new(0.0, -9.81, 0.0),
+            fixed_timestep: 1.0 / 60.0,
+            max_steps: 5,
+            accumulator: 0.0,
+
+            pipeline: PhysicsPipeline::new(),
+            integration_parameters: IntegrationParameters::default(),
+            broad_phase: BroadPhase::new(),
+            narrow_phase: NarrowPhase::new(),
+            rigid_bodies: RigidBodySet::new(),
+            colliders: ColliderSet::new(),
+            joints: JointSet::new(),
+            ccd_solver: CCDSolver::new(),
+            event_collector: ChannelEventCollector::new(intersection_sender, contact_sender),
+            contact_events,
+            intersection_events,
+
+            next_body_id: 0,
+            bodies: HashMap::new(),
+            instance_bodies: HashMap::new(),
+            collider_bodies: HashMap::new(),
+            objects: Vec::new(),
+            events: Vec::new(),
+        }
+    }
+
+    //starts where the instance is, with its scale applied to the shape.
+    //None when the instance doesn't exist, already has a body or the shape is degenerate
+    pub fn add_body(
+        &mut self,
+        object: &Object,
+        instance: InstanceId,
+        shape: &ColliderShape,
+        settings: BodySettings,
+    ) -> Option<BodyId> {
+        let key = (object.id(), instance);
+        if self.instance_bodies.contains_key(&key) {
+            warn!(
+                "{} instance {} already has a body",
+                object.name,
+                instance.raw()
+            );
+            return None;
+        }
+
+        let transform = object.instance(instance)?;
+        let (handle, collider) =
+            match self.insert_rigid_body(transform.isometry, &transform.scale, shape, &settings) {
+                Some(handles) => handles,
+                None => {
+                    warn!(
+                        "No collider can be built for {} instance {}",
+                        object.name,
+                        instance.raw()
+                    );
+                    return None;
+                }
+            };
+
+        let id = BodyId(self.next_body_id);
+        self.next_body_id += 1;
+
+        self.bodies.insert(
+            id,
+            Body {
+                object: key.0,
+                instance,
+                kind: settings.kind,
+                handle,
+                collider,
+            },
+        );
+        self.instance_bodies.insert(key, id);
+        self.collider_bodies.insert(collider, id);
+
+        Some(id)
+    }
+
+    pub fn remove_body(&mut self, id: BodyId) -> bool {
+        let body = match self.bodies.remove(&id) {
+            Some(body) => body,
+            None => return false,
+        };
+
+        self.instance_bodies.remove(&(body.object, body.instance));
+        self.collider_bodies.remove(&body.collider);
+        self.rigid_bodies
+            .remove(body.handle, &mut self.colliders, &mut self.joints);
+
+        true
+    }
+
+    //synced by step from now on, its bodies are removed once it is dropped
+    pub fn register(&mut self, object: &Rc<RefCell<Object>>) {
+        let id = object.borrow().id();
+        if self.objects.iter().all(|(registered, _)| *registered != id) {
+            self.objects.push((id, Rc::downgrade(object)));
+        }
+    }
+
+    pub fn unregister(&mut self, object: &Object) {
+        let id = object.id();
+        self.objects.retain(|(registered, _)| *registered != id);
+    }
+
+    pub fn body(&self, object: &Object, instance: InstanceId) -> Option<BodyId> {
+        self.instance_bodies.get(&(object.id(), instance)).copied()
+    }
+
+    //the instance a collision is about
+    pub fn instance(&self, id: BodyId) -> Option<(ObjectId, InstanceId)> {
+        self.bodies
+            .get(&id)
+            .map(|body| (body.object, body.instance))
+    }
+
+    pub fn body_count(&self) -> usize {
+        self.bodies.len()
+    }
+
+    //runs as many fixed steps as fit in the time accumulated so far, returns how many ran
+    pub fn step(&mut self, delta_time: f64) -> u32 {
+        self.integration_parameters.dt = self.fixed_timestep;
+        self.accumulator += delta_time as f32;
+
+        let objects = self.registered_objects();
+        let mut steps = 0;
+        while self.accumulator >= self.fixed_timestep && steps < self.max_steps {
+            for object in objects.iter() {
+                match object.try_borrow() {
+                    Ok(object) => self.push_kinematic(&object),
+                    Err(_) => warn!("Skipping a registered object borrowed while stepping"),
+                }
+            }
+
+            self.pipeline.step(
+                &self.gravity,
+                &self.integration_parameters,
+                &mut self.broad_phase,
+                &mut self.narrow_phase,
+                &mut self.rigid_bodies,
+                &mut self.colliders,
+                &mut self.joints,
+                &mut self.ccd_solver,
+                &(),
+                &self.event_collector,
+            );
+
+            self.accumulator -= self.fixed_timestep;
+            steps += 1;
+        }
+        //too far behind, catching up would only make the next frame longer
+        if self.accumulator >= self.fixed_timestep {
+            self.accumulator %= self.fixed_timestep;
+        }
+
+        if steps > 0 {
+            for object in objects.iter() {
+                if let Ok(mut object) = object.try_borrow_mut() {
+                    self.pull_dynamic(&mut object);
+                }
+            }
+        }
+
+        self.collect_events();
+
+        steps
+    }
+
+    //For objects that aren't registered: writes the dynamic bodies of this object to its
+    //instances and moves its kinematic bodies to where the instances are now, reached on the
+    //next step. Bodies whose instance was removed are removed too
+    pub fn sync(&mut self, object: &mut Object) {
+        self.pull_dynamic(object);
+        self.push_kinematic(object);
+    }
+
+    //teleports the body, the instance follows on the next sync
+    pub fn set_isometry(&mut self, id: BodyId, isometry: na::Isometry3<f32>) -> bool {
+        match self.rigid_body_mut(id) {
+            Some(rigid_body) => {
+                rigid_body.set_position(isometry, true);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn linear_velocity(&self, id: BodyId) -> Option<na::Vector3<f32>> {
+        let body = self.bodies.get(&id)?;
+        self.rigid_bodies
+            .get(body.handle)
+            .map(|rigid_body| *rigid_body.linvel())
+    }
+
+    pub fn set_linear_velocity(&mut self, id: BodyId, velocity: na::Vector3<f32>) -> bool {
+        match self.rigid_body_mut(id) {
+            Some(rigid_body) => {
+                rigid_body.set_linvel(velocity, true);
+                true
+            }
+            None => false,
+        }
+    }
+
+    //an instant change of momentum, like a hit or a jump
+    pub fn apply_impulse(&mut self, id: BodyId, impulse: na::Vector3<f32>) -> bool {
+        match self.rigid_body_mut(id) {
+            Some(rigid_body) => {
+                rigid_body.apply_impulse(impulse, true);
+                true
+            }
+            None => false,
+        }
+    }
+
+    //applied during the next step only, call it every frame for a continuous push
+    pub fn apply_force(&mut self, id: BodyId, force: na::Vector3<f32>) -> bool {
+        match self.rigid_body_mut(id) {
+            Some(rigid_body) => {
+                rigid_body.apply_force(force, true);
+                true
+            }
+            None => false,
+        }
+    }
+
+    //Event::Collision for every contact started or stopped since the last drain
+    pub fn drain_events(&mut self) -> std::vec::Drain<Event> {
+        self.events.drain(..)
+    }
+
+    //the live registered objects, the bodies of the dropped ones are removed
+    fn registered_objects(&mut self) -> Vec<Rc<RefCell<Object>>> {
+        let mut dropped = Vec::new();
+        let mut objects = Vec::new();
+        for (id, object) in self.objects.iter() {
+            match object.upgrade() {
+                Some(object) => objects.push(object),
+                None => dropped.push(*id),
+            }
+        }
+
+        if !dropped.is_empty() {
+            self.objects.retain(|(id, _)| !dropped.contains(id));
+            let bodies = self
+                .bodies
+                .iter()
+                .filter(|(_, body)| dropped.contains(&body.object))
+                .map(|(&id, _)| id)
+                .collect::<Vec<_>>();
+            for id in bodies {
+                self.remove_body(id);
+            }
+        }
+
+        objects
+    }
+
+    fn push_kinematic(&mut self, object: &Object) {
+        let object_id = object.id();
+        let mut removed = Vec::new();
+
+        for (&id, body) in self
+            .bodies
+            .iter()
+            .filter(|(_, body)| body.object == object_id)
+        {
+            let instance = match object.instance(body.instance) {
+                Some(instance) => instance,
+                None => {
+                    removed.push(id);
+                    continue;
+                }
+            };
+
+            if body.kind == BodyKind::Kinematic {
+                Self::move_kinematic(&mut self.rigid_bodies, body.handle, instance.isometry);
+            }
+        }
+
+        for id in removed {
+            self.remove_body(id);
+        }
+    }
+
+    fn pull_dynamic(&mut self, object: &mut Object) {
+        let object_id = object.id();
+        let mut removed = Vec::new();
+
+        for (&id, body) in self
+            .bodies
+            .iter()
+            .filter(|(_, body)| body.object == object_id && body.kind == BodyKind::Dynamic)
+        {
+            if let Some(isometry) = self.awake_isometry(body.handle) {
+                if !object.set_isometry(body.instance, isometry) {
+                    removed.push(id);
+                }
+            }
+        }
+
+        for id in removed {
+            self.remove_body(id);
+        }
+    }
+
+    //None when the shape is degenerate once scaled
+    fn insert_rigid_body(
+        &mut self,
+        isometry: na::Isometry3<f32>,
+        scale: &na::Vector3<f32>,
+        shape: &ColliderShape,
+        settings: &BodySettings,
+    ) -> Option<(RigidBodyHandle, ColliderHandle)> {
+        let collider = shape
+            .builder(scale)?
+            .density(settings.density)
+            .friction(settings.friction)
+            .restitution(settings.restitution)
+            .sensor(settings.sensor)
+            .build();
+
+        let status = match settings.kind {
+            BodyKind::Dynamic => BodyStatus::Dynamic,
+            BodyKind::Kinematic => BodyStatus::Kinematic,
+            BodyKind::Static => BodyStatus::Static,
+        };
+        let mut rigid_body = RigidBodyBuilder::new(status)
+            .position(isometry)
+            .linear_damping(settings.linear_damping)
+            .angular_damping(settings.angular_damping)
+            .build();
+        rigid_body.set_linvel(settings.linear_velocity, true);
+        rigid_body.set_angvel(settings.angular_velocity, true);
+
+        let handle = self.rigid_bodies.insert(rigid_body);
+        let collider = self
+            .colliders
+            .insert(collider, handle, &mut self.rigid_bodies);
+
+        Some((handle, collider))
+    }
+
+    //reached on the next step
+    fn move_kinematic(
+        rigid_bodies: &mut RigidBodySet,
+        handle: RigidBodyHandle,
+        isometry: na::Isometry3<f32>,
+    ) {
+        if let Some(rigid_body) = rigid_bodies.get_mut(handle) {
+            if isometry != *rigid_body.position() {
+                rigid_body.set_next_kinematic_position(isometry);
+            }
+        }
+    }
+
+    //None for sleeping bodies, nothing moved since they fell asleep
+    fn awake_isometry(&self, handle: RigidBodyHandle) -> Option<na::Isometry3<f32>> {
+        self.rigid_bodies
+            .get(handle)
+            .filter(|rigid_body| !rigid_body.is_sleeping())
+            .map(|rigid_body| *rigid_body.position())
+    }
+
+    fn rigid_body_mut(&mut self, id: BodyId) -> Option<&mut rapier3d::dynamics::RigidBody> {
+        let body = self.bodies.get(&id)?;
+        self.rigid_bodies.get_mut(body.handle)
+    }
+
+    fn collect_events(&mut self) {
+        while let Ok(event) = self.contact_events.try_recv() {
+            let (state, first, second) = match event {
+                ContactEvent::Started(first, second) => (ContactState::Started, first, second),
+                ContactEvent::Stopped(first, second) => (ContactState::Stopped, first, second),
+            };
+            self.push_collision(state, first, second, false);
+        }
+
+        while let Ok(event) = self.intersection_events.try_recv() {
+            let state = match event.intersecting {
+                true => ContactState::Started,
+                false => ContactState::Stopped,
+            };
+            self.push_collision(state, event.collider1, event.collider2, true);
+        }
+    }
+
+    fn push_collision(
+        &mut self,
+        state: ContactState,
+        first: ColliderHandle,
+        second: ColliderHandle,
+        sensor: bool,
+    ) {
+        //the body may have been removed since
+        let bodies = match (
+            self.collider_bodies.get(&first),
+            self.collider_bodies.get(&second),
+        ) {
+            (Some(&first), Some(&second)) => [first, second],
+            _ => return,
+        };
+
+        self.events.push(Event::Collision(Collision {
+            state,
+            bodies,
+            sensor,
+        }));
+    }
+}
+
+impl Default for PhysicsWorld {
+    fn default() -> Self {
+        PhysicsWorld::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //a body without an object, known to the events by its collider
+    fn insert(
+        world: &mut PhysicsWorld,
+        id: u64,
+        translation: na::Vector3<f32>,
+        shape: ColliderShape,
+        settings: BodySettings,
+    ) -> RigidBodyHandle {
+        let isometry = na::Isometry3::translation(translation.x, translation.y, translation.z);
+        let (handle, collider) = world
+            .insert_rigid_body(isometry, &na::Vector3::repeat(1.0), &shape, &settings)
+            .unwrap();
+        world.collider_bodies.insert(collider, BodyId(id));
+        handle
+    }
+
+    fn collisions(world: &mut PhysicsWorld) -> Vec<(ContactState, [BodyId; 2], bool)> {
+        world
+            .drain_events()
+            .filter_map(|event| match event {
+                Event::Collision(mut collision) => {
+                    collision.bodies.sort();
+                    Some((collision.state, collision.bodies, collision.sensor))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn steps_fit_in_the_accumulated_time() {
+        let mut world = PhysicsWorld::new();
+        world.fixed_timestep = 0.01;
+
+        assert_eq!(world.step(0.004), 0);
+        assert_eq!(world.step(0.004), 0);
+        //0.012 accumulated, one step and 0.002 left
+        assert_eq!(world.step(0.004), 1);
+        assert!((world.accumulator - 0.002).abs() < 1e-6);
+
+        assert_eq!(world.step(0.025), 2);
+        assert!((world.accumulator - 0.007).abs() < 1e-6);
+    }
+
+    #[test]
+    fn long_frames_drop_what_max_steps_leaves() {
+        let mut world = PhysicsWorld::new();
+        world.fixed_timestep = 0.01;
+        world.max_steps = 3;
+
+        assert_eq!(world.step(1.0), 3);
+        assert!(world.accumulator < world.fixed_timestep);
+        //the rest of the frame isn't owed to the next one
+        assert_eq!(world.step(0.0), 0);
+    }
+
+    #[test]
+    fn dynamic_bodies_fall_and_report_their_pose() {
+        let mut world = PhysicsWorld::new();
+        world.max_steps = 60;
+        let handle = insert(
+            &mut world,
+            0,
+            na::Vector3::new(1.0, 10.0, -2.0),
+            ColliderShape::Sphere(0.5),
+            BodySettings::default(),
+        );
+
+        assert_eq!(world.step(0.5 + 1e-4), 30);
+        let isometry = world.awake_isometry(handle).unwrap();
+        //half a second of free fall, about 1.2 down
+        let fallen = 10.0 - isometry.translation.y;
+        assert!((fallen - 0.5 * 9.81 * 0.25).abs() < 0.1, "{}", fallen);
+        assert!((isometry.translation.x - 1.0).abs() < 1e-5);
+        assert!((isometry.translation.z + 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn kinematic_bodies_reach_their_target_in_one_step() {
+        let mut world = PhysicsWorld::new();
+        let handle = insert(
+            &mut world,
+            0,
+            na::Vector3::zeros(),
+            ColliderShape::Box(na::Vector3::repeat(0.5)),
+            BodySettings {
+                kind: BodyKind::Kinematic,
+                ..BodySettings::default()
+            },
+        );
+
+        let target = na::Isometry3::new(
+            na::Vector3::new(1.0, 2.0, 3.0),
+            na::Vector3::new(0.0, 0.5, 0.0),
+        );
+        PhysicsWorld::move_kinematic(&mut world.rigid_bodies, handle, target);
+        assert_eq!(world.step(world.fixed_timestep as f64), 1);
+
+        let position = world.rigid_bodies.get(handle).unwrap().position();
+        assert!((position.translation.vector - target.translation.vector).norm() < 1e-5);
+        assert!(position.rotation.angle_to(&target.rotation) < 1e-5);
+    }
+
+    #[test]
+    fn contacts_become_collision_events() {
+        let mut world = PhysicsWorld::new();
+        world.max_steps = 60;
+        insert(
+            &mut world,
+            7,
+            na::Vector3::zeros(),
+            ColliderShape::Box(na::Vector3::new(10.0, 0.5, 10.0)),
+            BodySettings {
+                kind: BodyKind::Static,
+                ..BodySettings::default()
+            },
+        );
+        insert(
+            &mut world,
+            3,
+            na::Vector3::new(0.0, 1.5, 0.0),
+            ColliderShape::Sphere(0.5),
+            BodySettings::default(),
+        );
+
+        world.step(1.0);
+        assert_eq!(
+            collisions(&mut world),
+            vec![(ContactState::Started, [BodyId(3), BodyId(7)], false)]
+        );
+        //already drained
+        assert!(collisions(&mut world).is_empty());
+    }
+
+    #[test]
+    fn sensors_report_without_pushing() {
+        let mut world = PhysicsWorld::new();
+        world.max_steps = 60;
+        insert(
+            &mut world,
+            1,
+            na::Vector3::zeros(),
+            ColliderShape::Box(na::Vector3::repeat(1.0)),
+            BodySettings {
+                kind: BodyKind::Static,
+                sensor: true,
+                ..BodySettings::default()
+            },
+        );
+        let handle = insert(
+            &mut world,
+            2,
+            na::Vector3::new(0.0, 2.0, 0.0),
+            ColliderShape::Sphere(0.5),
+            BodySettings::default(),
+        );
+
+        world.step(1.0);
+        let events = collisions(&mut world);
+        assert!(events.contains(&(ContactState::Started, [BodyId(1), BodyId(2)], true)));
+        //fell through
+        assert!(world.awake_isometry(handle).unwrap().translation.y < -1.0);
+    }
+}
@@ -5,38 +5,87 @@ use wgpu::util::DeviceExt;
 
 use winit::window::Window;
 
+//re-exported for instance_data!
+pub use bytemuck;
+pub use wgpu;
+
 pub mod rot_primitives;
 use crate::rot_primitives::Object;
-use rot_primitives::{Camera, DepthBufferTexture, Material, Mesh, Vertex};
+use rot_primitives::{
+    Camera, DepthBufferTexture, Frustum, Material, Mesh, RenderTarget, Vertex, Viewport,
+};
 
 pub mod rot_pipeline;
+use rot_pipeline::PipelineCache;
+
+pub mod rot_render_queue;
+use rot_render_queue::{ComputeCommand, DrawCommand, RenderQueue, RenderView, ViewClearPipelines};
+
+pub mod rot_debug_draw;
+use rot_debug_draw::{DebugDraw, Overdraw, RenderDebugMode};
+
+pub mod rot_sprite;
+
+pub mod rot_text;
+
+pub mod rot_particles;
+
+pub mod rot_animation;
+
+pub mod rot_picking;
 
 use nalgebra as na;
+use std::cell::RefCell;
+use std::rc::Rc;
 
-pub struct Renderer<'a> {
+pub struct Renderer {
     //DepthBuffer
     depth_buffer: DepthBufferTexture,
+    //multisampled color the window views draw into, resolved into the frame. None without MSAA
+    msaa_buffer: Option<wgpu::TextureView>,
 
     //Command Buffer
     command_buffer: Option<Vec<wgpu::CommandBuffer>>,
-    frame: Option<wgpu::SwapChainTexture>,
+    //compute and overlay, the rest goes to the view of the camera set when submitted
+    render_queue: RenderQueue,
+    views: Vec<RenderView>,
+    debug_draw: DebugDraw,
+    debug_mode: RenderDebugMode,
+    //only while debug_mode is Overdraw
+    overdraw: Option<Overdraw>,
 
     //Present Stuff
-    pub render_pass: Option<wgpu::RenderPass<'a>>,
     surface: wgpu::Surface,
+    //kept to pick the swapchain format again when the window changes
+    adapter: wgpu::Adapter,
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
+    //what the device was actually created with
+    pub features: wgpu::Features,
     pub swapchain_descriptor: wgpu::SwapChainDescriptor,
     swapchain: wgpu::SwapChain,
 
+    //Pipelines
+    pub(crate) pipeline_cache: RefCell<PipelineCache>,
+    pub sample_count: u32,
+    //depth cleared to 0.0 and tested with Greater, for Projection::InfinitePerspective
+    reverse_z: bool,
+
     //Window & EventLoop
     pub size: winit::dpi::PhysicalSize<u32>,
 
     clear_color: na::Vector3<f64>,
-    camera: Option<&'a Camera>,
+    camera_bind_group: Option<Rc<wgpu::BindGroup>>,
+    camera_position: na::Point3<f32>,
+    camera_frustum: Option<Frustum>,
+    camera_projection: na::Matrix4<f32>,
+    camera_viewport: Viewport,
+    camera_render_target: Option<Rc<RenderTarget>>,
+    window_camera_view: Option<na::Matrix4<f32>>,
+    cursor_grab: bool,
 }
 
-impl<'a> Renderer<'a> {
+impl Renderer {
     pub async fn build(window: &Window) -> Self {
         info!("Building WGPU Renderer");
 
@@ -54,13 +103,15 @@ impl<'a> Renderer<'a> {
 
         trace!("Creating Device and Queue");
         let (device, queue) = Renderer::create_device_queue(&adapter).await;
+        let features = device.features();
 
         trace!("Creating Swapchain");
         let (swapchain_descriptor, swapchain) =
             Renderer::create_swapchain(&device, &adapter, &surface, &size);
 
         trace!("Creating DepthBuffer");
-        let depth_buffer = DepthBufferTexture::new(&device, &swapchain_descriptor, "depth_buffer");
+        let depth_buffer =
+            DepthBufferTexture::new(&device, &swapchain_descriptor, 1, "depth_buffer");
 
         info!("Renderer Built");
 
@@ -68,17 +119,33 @@ impl<'a> Renderer<'a> {
 
         Renderer {
             depth_buffer,
+            msaa_buffer: None,
             command_buffer: Some(Vec::new()),
-            frame: None,
-            render_pass: None,
+            render_queue: RenderQueue::new(),
+            views: Vec::new(),
+            debug_draw: DebugDraw::new(),
+            debug_mode: RenderDebugMode::Shaded,
+            overdraw: None,
             surface,
+            adapter,
             device,
             queue,
+            features,
             swapchain_descriptor,
             swapchain,
+            pipeline_cache: RefCell::new(PipelineCache::new()),
+            sample_count: 1,
+            reverse_z: false,
             size,
             clear_color,
-            camera: None,
+            camera_bind_group: None,
+            camera_position: na::Point3::origin(),
+            camera_frustum: None,
+            camera_projection: na::Matrix4::identity(),
+            camera_viewport: Viewport::default(),
+            camera_render_target: None,
+            window_camera_view: None,
+            cursor_grab: false,
         }
     }
 
@@ -88,86 +155,538 @@ impl<'a> Renderer<'a> {
         self.clear_color = na::Vector3::new(color_rgb[0], color_rgb[1], color_rgb[2]);
     }
 
+    //starts a view: what is submitted until the next set_camera is drawn by this camera, in its viewport.
+    //For split-screen set each camera and draw the scene after it
     pub fn set_camera(&mut self, camera: &Camera) {
-        self.camera = Some(camera);
+        self.camera_bind_group = Some(Rc::clone(&camera.bind_group));
+        self.camera_position = camera.eye();
+        self.camera_frustum = Some(camera.frustum());
+        self.camera_projection = camera.projection_matrix();
+        self.camera_viewport = camera.viewport;
+        self.camera_render_target = camera.render_target().cloned();
+        self.cursor_grab = camera.cursor_grab();
+        //the window camera set last is the one heard, not the ones drawing into textures
+        if camera.render_target().is_none() {
+            self.window_camera_view = Some(camera.view());
+        }
+
+        self.views.push(RenderView::new(
+            camera.viewport,
+            Some(Rc::clone(&camera.bind_group)),
+            camera.render_target().cloned(),
+        ));
     }
-}
 
-impl Renderer {
-    pub(crate) fn get_render_pass(&mut self) -> &mut wgpu::RenderPass {
-        self.render_pass.as_mut().unwrap()
-    }
-
-    pub(crate) fn draw_frame(
-        &mut self,
-        object: &Object,
-        render_pipeline: &rot_pipeline::Pipeline,
-    ) -> Result<(), wgpu::SwapChainError> {
-        let frame = self.swapchain.get_current_frame()?.output;
-
-        let mut cmd_encoder =
-            vec![self
-                .device
-                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                    label: Some("Render Encoder"),
-                })];
-
-        let mut render_pass = cmd_encoder[0].begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("Render pass"),
-            color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
-                attachment: &frame.view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color {
-                        r: self.clear_color[0],
-                        g: self.clear_color[1],
-                        b: self.clear_color[2],
-                        a: 1.0,
+    fn current_view(&mut self) -> &mut RenderView {
+        //nothing set this frame, the last camera keeps drawing
+        if self.views.is_empty() {
+            self.views.push(RenderView::new(
+                self.camera_viewport,
+                self.camera_bind_group.clone(),
+                self.camera_render_target.clone(),
+            ));
+        }
+
+        self.views.last_mut().unwrap()
+    }
+
+    //index of the view the next draws go to, one per set_camera this frame
+    pub fn view_index(&self) -> usize {
+        self.views.len().max(1) - 1
+    }
+
+    //whether the active camera wants the cursor hidden and kept in the window
+    pub fn cursor_grab(&self) -> bool {
+        self.cursor_grab
+    }
+
+    pub fn camera_bind_group(&self) -> Option<Rc<wgpu::BindGroup>> {
+        self.camera_bind_group.clone()
+    }
+
+    pub fn camera_position(&self) -> na::Point3<f32> {
+        self.camera_position
+    }
+
+    pub fn camera_frustum(&self) -> Option<&Frustum> {
+        self.camera_frustum.as_ref()
+    }
+
+    //wgpu clip space, of the camera the next draws go to
+    pub fn camera_projection(&self) -> &na::Matrix4<f32> {
+        &self.camera_projection
+    }
+
+    pub fn submit_compute(&mut self, command: ComputeCommand) {
+        self.render_queue.push_compute(command);
+    }
+
+    pub fn submit_opaque(&mut self, command: DrawCommand) {
+        self.current_view().queue.push_opaque(command);
+    }
+
+    pub fn submit_transparent(&mut self, command: DrawCommand) {
+        self.current_view().queue.push_transparent(command);
+    }
+
+    pub fn debug_draw(&mut self) -> &mut DebugDraw {
+        &mut self.debug_draw
+    }
+
+    //view of the last camera drawing into the window, the engine puts the listener there
+    pub fn window_camera_view(&self) -> Option<na::Matrix4<f32>> {
+        self.window_camera_view
+    }
+
+    pub fn set_debug_mode(&mut self, debug_mode: RenderDebugMode) {
+        self.debug_mode = debug_mode;
+    }
+
+    pub fn debug_mode(&self) -> RenderDebugMode {
+        self.debug_mode
+    }
+
+    //drawn after the transparent pass, over the finished scene
+    pub fn submit_debug(&mut self, command: DrawCommand) {
+        self.current_view().queue.push_debug(command);
+    }
+
+    //drawn last without a depth buffer, in submission order. Used by sprites and HUDs
+    pub fn submit_overlay(&mut self, command: DrawCommand) {
+        self.render_queue.push_overlay(command);
+    }
+
+    //MSAA of the window views, 1 turns it off. Render targets and the overlay stay single
+    //sampled, the pipelines drawn into each are rebuilt for it
+    pub fn set_sample_count(&mut self, sample_count: u32) {
+        self.sample_count = sample_count.max(1);
+        self.create_window_attachments();
+        self.pipeline_cache
+            .borrow_mut()
+            .invalidate(self.swapchain_descriptor.format, self.sample_count);
+    }
+
+    //set it before building objects, the depth test is baked into their pipelines
+    pub fn set_reverse_z(&mut self, reverse_z: bool) {
+        self.reverse_z = reverse_z;
+    }
+
+    pub fn reverse_z(&self) -> bool {
+        self.reverse_z
+    }
+
+    pub fn render(&mut self) {
+        let frame = match self.swapchain.get_current_frame() {
+            Ok(frame) => frame.output,
+            Err(error) => {
+                warn!("Dropping frame: {:?}", error);
+                self.render_queue.clear();
+                self.views.clear();
+                return;
+            }
+        };
+
+        let mut cmd_encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Render Encoder"),
+            });
+
+        for view in self.views.iter_mut() {
+            view.queue.sort_transparent();
+        }
+        self.submit_debug_draw();
+        //render targets first, so the window views can show them this frame.
+        //Stable, cameras of the same priority draw in the order they were set
+        self.views
+            .sort_by_key(|view| (view.render_target.is_none(), view.viewport.priority));
+
+        // TARGETS ---------------------------------------------------
+        //every draw gets a pipeline for the attachment it is recorded into
+        let mut views = std::mem::take(&mut self.views);
+        let mut overdraw = match self.debug_mode {
+            RenderDebugMode::Overdraw => {
+                Some(self.overdraw.take().unwrap_or_else(|| Overdraw::new(self)))
+            }
+            _ => None,
+        };
+        for view in views.iter_mut() {
+            let (format, sample_count, dimensions) = match view.render_target.as_ref() {
+                Some(render_target) => (render_target.format(), 1, render_target.dimensions()),
+                None => (
+                    self.swapchain_descriptor.format,
+                    self.sample_count,
+                    (
+                        self.swapchain_descriptor.width,
+                        self.swapchain_descriptor.height,
+                    ),
+                ),
+            };
+            view.queue.retarget(self, format, sample_count);
+
+            if let Some(overdraw) = overdraw.as_mut() {
+                overdraw.prepare(self, dimensions);
+                view.queue
+                    .retarget_opaque(self, rot_debug_draw::COUNTER_FORMAT, 1);
+            }
+        }
+        self.views = views;
+        self.overdraw = overdraw;
+
+        let mut render_queue = std::mem::take(&mut self.render_queue);
+        render_queue.retarget(self, self.swapchain_descriptor.format, 1);
+        self.render_queue = render_queue;
+
+        //with MSAA the window views draw into the multisampled buffer, resolved at each pass
+        let (window_view, window_resolve) = match self.msaa_buffer.as_ref() {
+            Some(msaa_buffer) => (msaa_buffer, Some(&frame.view)),
+            None => (&frame.view, None),
+        };
+
+        let background = [
+            self.clear_color[0],
+            self.clear_color[1],
+            self.clear_color[2],
+        ];
+        let (width, height) = (
+            self.swapchain_descriptor.width,
+            self.swapchain_descriptor.height,
+        );
+
+        // COMPUTE ---------------------------------------------------
+        if !self.render_queue.compute().is_empty() {
+            let mut compute_pass = cmd_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("Compute pass"),
+            });
+
+            for command in self.render_queue.compute() {
+                command.record(&mut compute_pass);
+            }
+        }
+
+        // CLEAR -----------------------------------------------------
+        //the whole frame, what no camera covers keeps the clear color
+        {
+            cmd_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Clear pass"),
+                color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                    attachment: window_view,
+                    resolve_target: window_resolve,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color {
+                            r: background[0],
+                            g: background[1],
+                            b: background[2],
+                            a: 1.0,
+                        }),
+                        store: true,
+                    },
+                }],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachmentDescriptor {
+                    attachment: &self.depth_buffer.view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(match self.reverse_z {
+                            true => 0.0,
+                            false => 1.0,
+                        }),
+                        store: true,
                     }),
-                    store: true,
-                },
-            }],
-            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachmentDescriptor {
-                attachment: &self.depth_buffer.view,
-                depth_ops: Some(wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(1.0),
-                    store: true,
+                    stencil_ops: None,
                 }),
-                stencil_ops: None,
-            }),
-        });
+            });
+        }
 
-        self.render_pass = Some(render_pass);
+        for view in self.views.iter() {
+            let (color_view, resolve_target, depth_view, format, sample_count, (width, height)) =
+                match view.render_target.as_ref() {
+                    Some(render_target) => (
+                        &render_target.color().view,
+                        None,
+                        &render_target.depth().view,
+                        render_target.format(),
+                        1,
+                        render_target.dimensions(),
+                    ),
+                    None => (
+                        window_view,
+                        window_resolve,
+                        &self.depth_buffer.view,
+                        self.swapchain_descriptor.format,
+                        self.sample_count,
+                        (width, height),
+                    ),
+                };
+            if view.pixel_rect(width, height).is_none() || view.samples_own_target() {
+                continue;
+            }
+            let clear_pipelines = ViewClearPipelines::get(self, format, sample_count);
+
+            // OVERDRAW ----------------------------------------------
+            //the opaque draws only count their fragments, the ramp paints the counts instead
+            let overdraw = self.overdraw.as_ref().and_then(|overdraw| {
+                let counter = overdraw.counter((width, height))?;
+                let ramp = overdraw.ramp().for_target(self, format, sample_count);
+                Some((counter, ramp))
+            });
+            if let Some((counter, _)) = overdraw.as_ref() {
+                let mut render_pass = cmd_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Overdraw pass"),
+                    color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                        attachment: &counter.counter.view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                            store: true,
+                        },
+                    }],
+                    depth_stencil_attachment: Some(
+                        wgpu::RenderPassDepthStencilAttachmentDescriptor {
+                            attachment: &counter.depth.view,
+                            depth_ops: Some(wgpu::Operations {
+                                load: wgpu::LoadOp::Clear(1.0),
+                                store: false,
+                            }),
+                            stencil_ops: None,
+                        },
+                    ),
+                });
+
+                view.begin(&mut render_pass, width, height);
+                for command in view.queue.opaque() {
+                    command.record(&mut render_pass);
+                }
+            }
+
+            // OPAQUE ------------------------------------------------
+            {
+                let mut render_pass = cmd_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Opaque pass"),
+                    color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                        attachment: color_view,
+                        resolve_target,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: true,
+                        },
+                    }],
+                    depth_stencil_attachment: Some(
+                        wgpu::RenderPassDepthStencilAttachmentDescriptor {
+                            attachment: depth_view,
+                            depth_ops: Some(wgpu::Operations {
+                                load: wgpu::LoadOp::Load,
+                                store: true,
+                            }),
+                            stencil_ops: None,
+                        },
+                    ),
+                });
+
+                view.begin(&mut render_pass, width, height);
+                view.clear(
+                    &mut render_pass,
+                    &clear_pipelines,
+                    background,
+                    self.reverse_z,
+                );
+
+                match overdraw.as_ref() {
+                    Some((counter, ramp)) => {
+                        render_pass.set_pipeline(&ramp.render_pipeline);
+                        render_pass.set_bind_group(0, &counter.bind_group, &[]);
+                        render_pass.draw(0..3, 0..1);
+                    }
+                    None => {
+                        for command in view.queue.opaque() {
+                            command.record(&mut render_pass);
+                        }
+                    }
+                }
+            }
+
+            // TRANSPARENT -------------------------------------------
+            //reads the opaque depth but the pipelines don't write it
+            if !view.queue.transparent().is_empty() {
+                let mut render_pass = cmd_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Transparent pass"),
+                    color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                        attachment: color_view,
+                        resolve_target,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: true,
+                        },
+                    }],
+                    depth_stencil_attachment: Some(
+                        wgpu::RenderPassDepthStencilAttachmentDescriptor {
+                            attachment: depth_view,
+                            depth_ops: Some(wgpu::Operations {
+                                load: wgpu::LoadOp::Load,
+                                store: true,
+                            }),
+                            stencil_ops: None,
+                        },
+                    ),
+                });
+
+                view.begin(&mut render_pass, width, height);
+                for command in view.queue.transparent() {
+                    command.record(&mut render_pass);
+                }
+            }
+
+            // DEBUG -------------------------------------------------
+            if !view.queue.debug().is_empty() {
+                let mut render_pass = cmd_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Debug pass"),
+                    color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                        attachment: color_view,
+                        resolve_target,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Load,
+                            store: true,
+                        },
+                    }],
+                    depth_stencil_attachment: Some(
+                        wgpu::RenderPassDepthStencilAttachmentDescriptor {
+                            attachment: depth_view,
+                            depth_ops: Some(wgpu::Operations {
+                                load: wgpu::LoadOp::Load,
+                                store: true,
+                            }),
+                            stencil_ops: None,
+                        },
+                    ),
+                });
+
+                view.begin(&mut render_pass, width, height);
+                for command in view.queue.debug() {
+                    command.record(&mut render_pass);
+                }
+            }
+        }
 
-        let mut cmd_encoder_finished = cmd_encoder
-            .into_iter()
-            .map(|encoder| encoder.finish())
-            .collect::<Vec<_>>();
+        // OVERLAY ---------------------------------------------------
+        //over the whole window, after every camera
+        if !self.render_queue.overlay().is_empty() {
+            let mut render_pass = cmd_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Overlay pass"),
+                color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                    attachment: &frame.view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: true,
+                    },
+                }],
+                depth_stencil_attachment: None,
+            });
+
+            for command in self.render_queue.overlay() {
+                command.record(&mut render_pass);
+            }
+        }
 
         self.command_buffer
             .as_mut()
             .unwrap()
-            .append(&mut cmd_encoder_finished);
-        self.frame = Some(frame);
-        Ok(())
-    }
+            .push(cmd_encoder.finish());
 
-    pub fn render(&mut self) {
-        let frame = self.frame.take().unwrap();
         self.queue.submit(self.command_buffer.take().unwrap());
 
         self.command_buffer = Some(Vec::new());
+        self.render_queue.clear();
+        self.views.clear();
+    }
+
+    //the same lines in every view, each seen by its camera
+    fn submit_debug_draw(&mut self) {
+        self.current_view();
+        if self
+            .views
+            .iter()
+            .all(|view| view.camera_bind_group.is_none())
+        {
+            return;
+        }
+
+        let (vertex_buffer, tested, on_top) =
+            match self.debug_draw.upload(&self.device, &self.queue) {
+                Some(upload) => upload,
+                None => return,
+            };
+        let (tested_pipeline, on_top_pipeline) = DebugDraw::pipelines(self);
+
+        for view in self.views.iter_mut() {
+            let camera_bind_group = match view.camera_bind_group.as_ref() {
+                Some(bind_group) => Rc::clone(bind_group),
+                None => continue,
+            };
+
+            for (pipeline, vertices) in
+                [(&tested_pipeline, &tested), (&on_top_pipeline, &on_top)].iter()
+            {
+                if vertices.start == vertices.end {
+                    continue;
+                }
+
+                view.queue.push_debug(DrawCommand {
+                    pipeline: (*pipeline).clone(),
+                    bind_groups: vec![Rc::clone(&camera_bind_group)],
+                    vertex_buffers: vec![Rc::clone(&vertex_buffer)],
+                    index_buffer: None,
+                    indices: (*vertices).clone(),
+                    instances: 0..1,
+                    indirect: None,
+                    depth: 0.0,
+                });
+            }
+        }
     }
 
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
         self.size = new_size;
         self.swapchain_descriptor.width = new_size.width;
         self.swapchain_descriptor.height = new_size.height;
+        //a window moved to another display can prefer another format
+        self.swapchain_descriptor.format =
+            self.adapter.get_swap_chain_preferred_format(&self.surface);
         self.swapchain = self
             .device
             .create_swap_chain(&self.surface, &self.swapchain_descriptor);
-        self.depth_buffer =
-            DepthBufferTexture::new(&self.device, &self.swapchain_descriptor, "depth_buffer");
+        self.create_window_attachments();
+        self.pipeline_cache
+            .borrow_mut()
+            .invalidate(self.swapchain_descriptor.format, self.sample_count);
+    }
+
+    fn create_window_attachments(&mut self) {
+        self.depth_buffer = DepthBufferTexture::new(
+            &self.device,
+            &self.swapchain_descriptor,
+            self.sample_count,
+            "depth_buffer",
+        );
+
+        self.msaa_buffer = match self.sample_count {
+            1 => None,
+            sample_count => {
+                let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+                    label: Some("msaa_buffer"),
+                    size: wgpu::Extent3d {
+                        width: self.swapchain_descriptor.width,
+                        height: self.swapchain_descriptor.height,
+                        depth: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: self.swapchain_descriptor.format,
+                    usage: wgpu::TextureUsage::RENDER_ATTACHMENT,
+                });
+
+                Some(texture.create_view(&wgpu::TextureViewDescriptor::default()))
+            }
+        };
     }
 
     fn create_swapchain(
@@ -194,7 +713,8 @@ impl Renderer {
             .request_device(
                 &wgpu::DeviceDescriptor {
                     label: None,
-                    features: wgpu::Features::default(),
+                    //optional, the wireframe view falls back to a shader without it
+                    features: adapter.features() & wgpu::Features::NON_FILL_POLYGON_MODE,
                     limits: wgpu::Limits::default(),
                 },
                 None,
@@ -0,0 +1,113 @@
+use crate::rot_animation::skeleton::slerp;
+use crate::rot_animation::{JointTransform, Skeleton};
+use nalgebra as na;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Interpolation {
+    //holds every key until the next one
+    Step,
+    //lerp, slerp for rotations
+    Linear,
+}
+
+#[derive(Debug, Clone)]
+pub enum ChannelValues {
+    Translation(Vec<na::Vector3<f32>>),
+    Rotation(Vec<na::UnitQuaternion<f32>>),
+    Scale(Vec<na::Vector3<f32>>),
+}
+
+//Keyframes of one part of one joint transform, a value per time
+#[derive(Debug, Clone)]
+pub struct Channel {
+    pub joint: usize,
+    pub interpolation: Interpolation,
+    //seconds, ascending
+    pub times: Vec<f32>,
+    pub values: ChannelValues,
+}
+
+impl Channel {
+    //the keys around time and how far time is from the first towards the second
+    fn locate(&self, time: f32) -> (usize, usize, f32) {
+        let next = self.times.iter().position(|&key| key > time);
+
+        match next {
+            Some(0) => (0, 0, 0.0),
+            Some(next) => {
+                let (start, end) = (self.times[next - 1], self.times[next]);
+                let t = match self.interpolation {
+                    Interpolation::Step => 0.0,
+                    Interpolation::Linear => (time - start) / (end - start),
+                };
+
+                (next - 1, next, t)
+            }
+            None => {
+                let last = self.times.len().saturating_sub(1);
+                (last, last, 0.0)
+            }
+        }
+    }
+
+    fn apply(&self, time: f32, transform: &mut JointTransform) {
+        if self.times.is_empty() {
+            return;
+        }
+
+        let (start, end, t) = self.locate(time);
+        match &self.values {
+            ChannelValues::Translation(values) => {
+                transform.translation = values[start].lerp(&values[end], t)
+            }
+            ChannelValues::Rotation(values) => {
+                transform.rotation = slerp(&values[start], &values[end], t)
+            }
+            ChannelValues::Scale(values) => transform.scale = values[start].lerp(&values[end], t),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AnimationClip {
+    pub name: String,
+    //seconds, the last key of any channel
+    pub duration: f32,
+    channels: Vec<Channel>,
+}
+
+impl AnimationClip {
+    pub fn new(name: &str, channels: Vec<Channel>) -> Self {
+        let duration = channels
+            .iter()
+            .filter_map(|channel| channel.times.last())
+            .fold(0.0, |duration: f32, &time| duration.max(time));
+
+        Self {
+            name: name.to_string(),
+            duration,
+            channels,
+        }
+    }
+
+    pub fn channels(&self) -> &[Channel] {
+        self.channels.as_slice()
+    }
+
+    //only overwrites what the clip animates, the rest of the pose is kept
+    pub fn sample_into(&self, time: f32, pose: &mut [JointTransform]) {
+        for channel in self.channels.iter() {
+            if let Some(transform) = pose.get_mut(channel.joint) {
+                channel.apply(time, transform);
+            }
+        }
+    }
+
+    //joints the clip doesn't animate stay at rest
+    pub fn sample(&self, skeleton: &Skeleton, time: f32) -> Vec<JointTransform> {
+        let mut pose = skeleton.rest_pose();
+        self.sample_into(time, pose.as_mut_slice());
+
+        pose
+    }
+}
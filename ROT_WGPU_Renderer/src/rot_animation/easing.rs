@@ -0,0 +1,154 @@
+use std::f32::consts::PI;
+
+//Remaps the 0.0..=1.0 progress of a tween, in the usual easings.net naming
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    //holds the start value until the very end
+    Step,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    SineIn,
+    SineOut,
+    SineInOut,
+    ExpoIn,
+    ExpoOut,
+    //overshoot a little past the ends
+    BackIn,
+    BackOut,
+    ElasticOut,
+    BounceOut,
+}
+
+impl Default for Easing {
+    fn default() -> Self {
+        Easing::Linear
+    }
+}
+
+impl Easing {
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.max(0.0).min(1.0);
+        const BACK: f32 = 1.70158;
+
+        match self {
+            Easing::Linear => t,
+            Easing::Step => match t < 1.0 {
+                true => 0.0,
+                false => 1.0,
+            },
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::QuadInOut => match t < 0.5 {
+                true => 2.0 * t * t,
+                false => 1.0 - (-2.0 * t + 2.0).powi(2) / 2.0,
+            },
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Easing::CubicInOut => match t < 0.5 {
+                true => 4.0 * t * t * t,
+                false => 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0,
+            },
+            Easing::SineIn => 1.0 - (t * PI / 2.0).cos(),
+            Easing::SineOut => (t * PI / 2.0).sin(),
+            Easing::SineInOut => -((t * PI).cos() - 1.0) / 2.0,
+            Easing::ExpoIn => match t <= 0.0 {
+                true => 0.0,
+                false => 2.0f32.powf(10.0 * t - 10.0),
+            },
+            Easing::ExpoOut => match t >= 1.0 {
+                true => 1.0,
+                false => 1.0 - 2.0f32.powf(-10.0 * t),
+            },
+            Easing::BackIn => (BACK + 1.0) * t * t * t - BACK * t * t,
+            Easing::BackOut => 1.0 + (BACK + 1.0) * (t - 1.0).powi(3) + BACK * (t - 1.0).powi(2),
+            Easing::ElasticOut => match t <= 0.0 || t >= 1.0 {
+                true => t,
+                false => {
+                    2.0f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * (2.0 * PI / 3.0)).sin() + 1.0
+                }
+            },
+            Easing::BounceOut => bounce_out(t),
+        }
+    }
+}
+
+fn bounce_out(t: f32) -> f32 {
+    const N: f32 = 7.5625;
+    const D: f32 = 2.75;
+
+    if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Easing; 17] = [
+        Easing::Linear,
+        Easing::Step,
+        Easing::QuadIn,
+        Easing::QuadOut,
+        Easing::QuadInOut,
+        Easing::CubicIn,
+        Easing::CubicOut,
+        Easing::CubicInOut,
+        Easing::SineIn,
+        Easing::SineOut,
+        Easing::SineInOut,
+        Easing::ExpoIn,
+        Easing::ExpoOut,
+        Easing::BackIn,
+        Easing::BackOut,
+        Easing::ElasticOut,
+        Easing::BounceOut,
+    ];
+
+    #[test]
+    fn every_easing_starts_at_0_and_ends_at_1() {
+        for easing in ALL.iter() {
+            assert!(easing.apply(0.0).abs() < 1e-5, "{:?}", easing);
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-5, "{:?}", easing);
+        }
+    }
+
+    #[test]
+    fn progress_outside_0_to_1_is_clamped() {
+        for easing in ALL.iter() {
+            assert_eq!(easing.apply(-0.5), easing.apply(0.0), "{:?}", easing);
+            assert_eq!(easing.apply(1.5), easing.apply(1.0), "{:?}", easing);
+        }
+    }
+
+    #[test]
+    fn in_out_easings_meet_halfway() {
+        for easing in [Easing::QuadInOut, Easing::CubicInOut, Easing::SineInOut].iter() {
+            assert!((easing.apply(0.5) - 0.5).abs() < 1e-5, "{:?}", easing);
+        }
+
+        assert_eq!(Easing::Step.apply(0.99), 0.0);
+        assert!(Easing::QuadIn.apply(0.5) < 0.5 && Easing::QuadOut.apply(0.5) > 0.5);
+    }
+
+    #[test]
+    fn back_overshoots_the_ends() {
+        assert!(Easing::BackIn.apply(0.2) < 0.0);
+        assert!(Easing::BackOut.apply(0.8) > 1.0);
+        assert!(Easing::ElasticOut.apply(0.1) > 1.0);
+    }
+}
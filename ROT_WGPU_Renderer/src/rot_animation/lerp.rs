@@ -0,0 +1,63 @@
+use crate::rot_animation::skeleton::slerp;
+use nalgebra as na;
+
+//Anything a curve, track or tween can blend between, t is in 0.0..=1.0
+pub trait Lerp: Copy {
+    fn lerp(a: Self, b: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        a + (b - a) * t
+    }
+}
+
+impl Lerp for [f32; 3] {
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        [
+            f32::lerp(a[0], b[0], t),
+            f32::lerp(a[1], b[1], t),
+            f32::lerp(a[2], b[2], t),
+        ]
+    }
+}
+
+//colors
+impl Lerp for [f32; 4] {
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        [
+            f32::lerp(a[0], b[0], t),
+            f32::lerp(a[1], b[1], t),
+            f32::lerp(a[2], b[2], t),
+            f32::lerp(a[3], b[3], t),
+        ]
+    }
+}
+
+impl Lerp for na::Vector3<f32> {
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        a.lerp(&b, t)
+    }
+}
+
+impl Lerp for na::Point3<f32> {
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        a.coords.lerp(&b.coords, t).into()
+    }
+}
+
+//shortest path slerp
+impl Lerp for na::UnitQuaternion<f32> {
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        slerp(&a, &b, t)
+    }
+}
+
+impl Lerp for na::Isometry3<f32> {
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        na::Isometry3::from_parts(
+            a.translation.vector.lerp(&b.translation.vector, t).into(),
+            slerp(&a.rotation, &b.rotation, t),
+        )
+    }
+}
@@ -0,0 +1,15 @@
+mod clip;
+mod easing;
+mod lerp;
+mod player;
+mod skeleton;
+mod skinned_object;
+mod tween;
+
+pub use clip::{AnimationClip, Channel, ChannelValues, Interpolation};
+pub use easing::Easing;
+pub use lerp::Lerp;
+pub use player::AnimationPlayer;
+pub use skeleton::{Joint, JointTransform, Skeleton};
+pub use skinned_object::{SkinnedMesh, SkinnedObject};
+pub use tween::{Animator, Keyframe, LoopMode, Track, Tween, TweenId};
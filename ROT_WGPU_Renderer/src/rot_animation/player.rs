@@ -0,0 +1,165 @@
+use crate::rot_animation::{AnimationClip, JointTransform, Skeleton};
+use std::rc::Rc;
+
+#[derive(Debug, Clone)]
+struct Playback {
+    clip: Rc<AnimationClip>,
+    time: f32,
+    looping: bool,
+}
+
+impl Playback {
+    fn new(clip: &Rc<AnimationClip>, looping: bool) -> Self {
+        Self {
+            clip: Rc::clone(clip),
+            time: 0.0,
+            looping,
+        }
+    }
+
+    fn advance(&mut self, delta_time: f32) {
+        let duration = self.clip.duration;
+        self.time += delta_time;
+
+        self.time = match (self.looping, duration > 0.0) {
+            (true, true) => self.time.rem_euclid(duration),
+            _ => self.time.max(0.0).min(duration),
+        };
+    }
+
+    fn is_finished(&self) -> bool {
+        !self.looping && self.time >= self.clip.duration
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Fade {
+    from: Playback,
+    elapsed: f32,
+    duration: f32,
+}
+
+//Plays one clip at a time on a skeleton, crossfading from the previous one when asked to
+#[derive(Debug, Clone)]
+pub struct AnimationPlayer {
+    //negative plays backwards
+    pub speed: f32,
+    pub paused: bool,
+
+    current: Option<Playback>,
+    fade: Option<Fade>,
+}
+
+impl Default for AnimationPlayer {
+    fn default() -> Self {
+        Self {
+            speed: 1.0,
+            paused: false,
+            current: None,
+            fade: None,
+        }
+    }
+}
+
+impl AnimationPlayer {
+    pub fn new() -> Self {
+        AnimationPlayer::default()
+    }
+
+    //cuts straight to the clip, from its start
+    pub fn play(&mut self, clip: &Rc<AnimationClip>, looping: bool) {
+        self.current = Some(Playback::new(clip, looping));
+        self.fade = None;
+    }
+
+    //blends from whatever plays now over duration seconds.
+    //A fade already running is dropped, its target becomes the clip faded out
+    pub fn crossfade(&mut self, clip: &Rc<AnimationClip>, looping: bool, duration: f32) {
+        let from = match self.current.take() {
+            Some(from) if duration > 0.0 => from,
+            _ => return self.play(clip, looping),
+        };
+
+        self.current = Some(Playback::new(clip, looping));
+        self.fade = Some(Fade {
+            from,
+            elapsed: 0.0,
+            duration,
+        });
+    }
+
+    pub fn stop(&mut self) {
+        self.current = None;
+        self.fade = None;
+    }
+
+    pub fn advance(&mut self, delta_time: f32) {
+        if self.paused {
+            return;
+        }
+        let delta_time = delta_time * self.speed;
+
+        if let Some(current) = self.current.as_mut() {
+            current.advance(delta_time);
+        }
+
+        if let Some(fade) = self.fade.as_mut() {
+            fade.from.advance(delta_time);
+            fade.elapsed += delta_time.abs();
+            if fade.elapsed >= fade.duration {
+                self.fade = None;
+            }
+        }
+    }
+
+    pub fn clip(&self) -> Option<&Rc<AnimationClip>> {
+        self.current.as_ref().map(|current| &current.clip)
+    }
+
+    //seconds into the current clip
+    pub fn time(&self) -> f32 {
+        self.current.as_ref().map_or(0.0, |current| current.time)
+    }
+
+    pub fn set_time(&mut self, time: f32) {
+        if let Some(current) = self.current.as_mut() {
+            current.time = 0.0;
+            current.advance(time);
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.current.is_some()
+    }
+
+    pub fn is_fading(&self) -> bool {
+        self.fade.is_some()
+    }
+
+    //a looping clip never finishes, a stopped player always is
+    pub fn is_finished(&self) -> bool {
+        self.current.as_ref().map_or(true, Playback::is_finished)
+    }
+
+    //the rest pose when nothing plays
+    pub fn pose(&self, skeleton: &Skeleton) -> Vec<JointTransform> {
+        let current = match self.current.as_ref() {
+            Some(current) => current,
+            None => return skeleton.rest_pose(),
+        };
+        let pose = current.clip.sample(skeleton, current.time);
+
+        match self.fade.as_ref() {
+            Some(fade) => {
+                let from = fade.from.clip.sample(skeleton, fade.from.time);
+                let t = fade.elapsed / fade.duration;
+
+                from.iter()
+                    .zip(pose.iter())
+                    .map(|(from, to)| from.interpolate(to, t))
+                    .collect()
+            }
+            None => pose,
+        }
+    }
+}
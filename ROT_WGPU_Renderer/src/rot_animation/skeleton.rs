@@ -0,0 +1,127 @@
+use nalgebra as na;
+
+//Transform of a joint relative to its parent
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct JointTransform {
+    pub translation: na::Vector3<f32>,
+    pub rotation: na::UnitQuaternion<f32>,
+    pub scale: na::Vector3<f32>,
+}
+
+impl Default for JointTransform {
+    fn default() -> Self {
+        Self {
+            translation: na::Vector3::zeros(),
+            rotation: na::UnitQuaternion::identity(),
+            scale: na::Vector3::new(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+impl JointTransform {
+    pub fn to_homogeneous(&self) -> na::Matrix4<f32> {
+        na::Matrix4::new_translation(&self.translation)
+            * self.rotation.to_homogeneous()
+            * na::Matrix4::new_nonuniform_scaling(&self.scale)
+    }
+
+    //linear on translation and scale, slerp on rotation
+    pub fn interpolate(&self, other: &JointTransform, t: f32) -> Self {
+        Self {
+            translation: self.translation.lerp(&other.translation, t),
+            rotation: slerp(&self.rotation, &other.rotation, t),
+            scale: self.scale.lerp(&other.scale, t),
+        }
+    }
+}
+
+pub(crate) fn slerp(
+    from: &na::UnitQuaternion<f32>,
+    to: &na::UnitQuaternion<f32>,
+    t: f32,
+) -> na::UnitQuaternion<f32> {
+    //q and -q are the same rotation, this keeps the shortest way around
+    let to = match from.coords.dot(&to.coords) < 0.0 {
+        true => na::UnitQuaternion::new_unchecked(-to.into_inner()),
+        false => *to,
+    };
+
+    //slerp is undefined for (almost) equal rotations, where nlerp is just as good
+    from.try_slerp(&to, t, 1.0e-6)
+        .unwrap_or_else(|| from.nlerp(&to, t))
+}
+
+#[derive(Debug, Clone)]
+pub struct Joint {
+    pub name: String,
+    pub parent: Option<usize>,
+    //where the joint sits when nothing animates it
+    pub rest: JointTransform,
+    //model space to joint space in the bind pose
+    pub inverse_bind: na::Matrix4<f32>,
+}
+
+//Joint hierarchy, parents always come before their children
+#[derive(Debug, Clone)]
+pub struct Skeleton {
+    joints: Vec<Joint>,
+    //model space transform of whatever holds the root joints
+    pub root: na::Matrix4<f32>,
+}
+
+impl Skeleton {
+    pub fn new(joints: Vec<Joint>, root: na::Matrix4<f32>) -> Self {
+        for (index, joint) in joints.iter().enumerate() {
+            assert!(
+                joint.parent.map_or(true, |parent| parent < index),
+                "joint {} comes before its parent",
+                joint.name
+            );
+        }
+
+        Self { joints, root }
+    }
+
+    pub fn joints(&self) -> &[Joint] {
+        self.joints.as_slice()
+    }
+
+    pub fn len(&self) -> usize {
+        self.joints.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.joints.is_empty()
+    }
+
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.joints.iter().position(|joint| joint.name == name)
+    }
+
+    pub fn rest_pose(&self) -> Vec<JointTransform> {
+        self.joints.iter().map(|joint| joint.rest).collect()
+    }
+
+    //model space transform of every joint, to attach things to a hand for example
+    pub fn global_transforms(&self, pose: &[JointTransform]) -> Vec<na::Matrix4<f32>> {
+        let mut globals: Vec<na::Matrix4<f32>> = Vec::with_capacity(self.joints.len());
+        for (joint, local) in self.joints.iter().zip(pose.iter()) {
+            let parent = match joint.parent {
+                Some(parent) => globals[parent],
+                None => self.root,
+            };
+            globals.push(parent * local.to_homogeneous());
+        }
+
+        globals
+    }
+
+    //what the skinning shader moves the bind pose vertices with
+    pub fn joint_matrices(&self, pose: &[JointTransform]) -> Vec<na::Matrix4<f32>> {
+        self.global_transforms(pose)
+            .into_iter()
+            .zip(self.joints.iter())
+            .map(|(global, joint)| global * joint.inverse_bind)
+            .collect()
+    }
+}
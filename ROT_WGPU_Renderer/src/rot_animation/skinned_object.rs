@@ -0,0 +1,617 @@
+use crate::rot_animation::{
+    AnimationClip, AnimationPlayer, Channel, ChannelValues, Interpolation, Joint, JointTransform,
+    Skeleton,
+};
+use crate::rot_pipeline::{BindGroupLayout, Pipeline, PipelineBuilder};
+use crate::rot_primitives::{AlphaMode, Instance, Material, SkinnedVertex};
+use crate::rot_render_queue::DrawCommand;
+use crate::Renderer;
+use log::warn;
+use nalgebra as na;
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+use std::rc::Rc;
+use wgpu::util::DeviceExt;
+
+pub struct SkinnedMesh {
+    pub name: String,
+
+    pub vertex_buffer: Rc<wgpu::Buffer>,
+    pub index_buffer: Rc<wgpu::Buffer>,
+    pub size: usize,
+
+    //index into the owner SkinnedObject materials
+    pub material: usize,
+}
+
+//A rigged model with its own pose. Every character is one SkinnedObject,
+//clips can be shared between the ones loaded from the same file
+pub struct SkinnedObject {
+    pub name: String,
+
+    pub meshes: Vec<SkinnedMesh>,
+    pub materials: Vec<Material>,
+    pub skeleton: Skeleton,
+    pub clips: Vec<Rc<AnimationClip>>,
+
+    pub player: AnimationPlayer,
+    //world transform, picked up on the next update
+    pub instance: Instance,
+
+    instance_buffer: Rc<wgpu::Buffer>,
+    joint_buffer: wgpu::Buffer,
+    joint_bind_group: Rc<wgpu::BindGroup>,
+    joint_bind_group_layout: BindGroupLayout,
+}
+
+impl SkinnedObject {
+    //the first skin of a glTF/GLB file, the meshes bound to it and every animation
+    pub fn load<P: AsRef<Path>>(
+        renderer: &Renderer,
+        path: P,
+        name: &str,
+    ) -> Result<Self, Box<dyn Error>> {
+        let (document, buffers, images) = gltf::import(path.as_ref())?;
+        let skin = document
+            .skins()
+            .next()
+            .ok_or_else(|| format!("{} has no skin", path.as_ref().display()))?;
+        let nodes = document.nodes().collect::<Vec<_>>();
+
+        // SKELETON ----
+        let mut parents = vec![None; nodes.len()];
+        for node in nodes.iter() {
+            for child in node.children() {
+                parents[child.index()] = Some(node.index());
+            }
+        }
+        let depth = |mut node: usize| {
+            let mut depth = 0;
+            while let Some(parent) = parents[node] {
+                depth += 1;
+                node = parent;
+            }
+            depth
+        };
+
+        let skin_nodes = skin.joints().map(|node| node.index()).collect::<Vec<_>>();
+        let inverse_binds = match skin
+            .reader(|buffer| Some(&buffers[buffer.index()]))
+            .read_inverse_bind_matrices()
+        {
+            Some(matrices) => matrices.map(na::Matrix4::from).collect::<Vec<_>>(),
+            None => vec![na::Matrix4::identity(); skin_nodes.len()],
+        };
+
+        //the vertices index joints in skin order, the skeleton wants parents first
+        let mut order = (0..skin_nodes.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&joint| depth(skin_nodes[joint]));
+        let mut remap = vec![0; order.len()];
+        for (index, &joint) in order.iter().enumerate() {
+            remap[joint] = index;
+        }
+        let joint_of_node = skin_nodes
+            .iter()
+            .enumerate()
+            .map(|(joint, &node)| (node, remap[joint]))
+            .collect::<HashMap<_, _>>();
+
+        let joints = order
+            .iter()
+            .map(|&joint| {
+                let node = &nodes[skin_nodes[joint]];
+
+                //nodes in between that aren't joints are skipped
+                let mut parent = parents[node.index()];
+                while let Some(index) = parent {
+                    if joint_of_node.contains_key(&index) {
+                        break;
+                    }
+                    parent = parents[index];
+                }
+
+                Joint {
+                    name: node.name().unwrap_or_default().to_string(),
+                    parent: parent.map(|index| joint_of_node[&index]),
+                    rest: joint_transform(node),
+                    inverse_bind: inverse_binds[joint],
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut root = na::Matrix4::identity();
+        let mut ancestor = order.first().and_then(|&joint| parents[skin_nodes[joint]]);
+        while let Some(index) = ancestor {
+            root = na::Matrix4::from(nodes[index].transform().matrix()) * root;
+            ancestor = parents[index];
+        }
+
+        let skeleton = Skeleton::new(joints, root);
+
+        // MATERIALS ----
+        let mut materials = document
+            .materials()
+            .map(|material| load_material(renderer, &material, images.as_slice(), name))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut default_material = None;
+
+        // MESHES ----
+        let mut meshes = Vec::new();
+        for node in nodes.iter() {
+            let mesh = match (node.mesh(), node.skin()) {
+                (Some(mesh), Some(node_skin)) if node_skin.index() == skin.index() => mesh,
+                _ => continue,
+            };
+
+            for (index, primitive) in mesh.primitives().enumerate() {
+                //points and lines can't be skinned into anything useful
+                if primitive.mode() != gltf::mesh::Mode::Triangles {
+                    continue;
+                }
+
+                let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+                let positions = reader
+                    .read_positions()
+                    .ok_or("skinned primitive without positions")?
+                    .collect::<Vec<_>>();
+                let count = positions.len();
+                let normals = match reader.read_normals() {
+                    Some(normals) => normals.collect(),
+                    None => vec![[0.0, 1.0, 0.0]; count],
+                };
+                let tex_coords = match reader.read_tex_coords(0) {
+                    Some(tex_coords) => tex_coords.into_f32().collect(),
+                    None => vec![[0.0; 2]; count],
+                };
+                let vertex_joints = reader
+                    .read_joints(0)
+                    .ok_or("skinned primitive without JOINTS_0")?
+                    .into_u16()
+                    .collect::<Vec<_>>();
+                let weights = reader
+                    .read_weights(0)
+                    .ok_or("skinned primitive without WEIGHTS_0")?
+                    .into_f32()
+                    .collect::<Vec<_>>();
+                let indices = match reader.read_indices() {
+                    Some(indices) => indices.into_u32().collect(),
+                    None => (0..count as u32).collect::<Vec<_>>(),
+                };
+
+                //broken files index past the skin, those vertices follow the first joint
+                let mut unknown_joints = 0;
+                let vertices = (0..count)
+                    .map(|vertex| {
+                        let mut joints = [0; 4];
+                        for (joint, &skin_joint) in
+                            joints.iter_mut().zip(vertex_joints[vertex].iter())
+                        {
+                            *joint = match remap.get(skin_joint as usize) {
+                                Some(&remapped) => remapped as u32,
+                                None => {
+                                    unknown_joints += 1;
+                                    0
+                                }
+                            };
+                        }
+
+                        //exporters don't always normalize them
+                        let mut weights = weights[vertex];
+                        let total = weights.iter().sum::<f32>();
+                        match total > 0.0 {
+                            true => weights.iter_mut().for_each(|weight| *weight /= total),
+                            false => weights = [1.0, 0.0, 0.0, 0.0],
+                        }
+
+                        SkinnedVertex {
+                            position: positions[vertex],
+                            tex_coords: tex_coords[vertex],
+                            normal: normals[vertex],
+                            joints,
+                            weights,
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                if unknown_joints > 0 {
+                    warn!(
+                        "{}: {} joint references past the {} joints of the skin, bound to joint 0",
+                        name,
+                        unknown_joints,
+                        remap.len()
+                    );
+                }
+
+                let material = match primitive.material().index() {
+                    Some(material) => material,
+                    None => *default_material.get_or_insert_with(|| {
+                        materials.push(Material::from_color([255; 4], renderer, name));
+                        materials.len() - 1
+                    }),
+                };
+
+                let mesh_name = format!("{} {}", mesh.name().unwrap_or(name), index);
+                meshes.push(SkinnedMesh::new(
+                    renderer,
+                    vertices.as_slice(),
+                    indices.as_slice(),
+                    material,
+                    &mesh_name,
+                ));
+            }
+        }
+
+        // CLIPS ----
+        let clips = document
+            .animations()
+            .enumerate()
+            .map(|(index, animation)| {
+                let mut channels = Vec::new();
+                for channel in animation.channels() {
+                    let joint = match joint_of_node.get(&channel.target().node().index()) {
+                        Some(&joint) => joint,
+                        None => continue,
+                    };
+
+                    //cubic spline tangents are dropped, the keys are sampled linearly
+                    let (interpolation, cubic) = match channel.sampler().interpolation() {
+                        gltf::animation::Interpolation::Step => (Interpolation::Step, false),
+                        gltf::animation::Interpolation::Linear => (Interpolation::Linear, false),
+                        gltf::animation::Interpolation::CubicSpline => {
+                            (Interpolation::Linear, true)
+                        }
+                    };
+
+                    let reader = channel.reader(|buffer| Some(&buffers[buffer.index()]));
+                    let times = match reader.read_inputs() {
+                        Some(times) => times.collect::<Vec<_>>(),
+                        None => continue,
+                    };
+                    let values = match reader.read_outputs() {
+                        Some(gltf::animation::util::ReadOutputs::Translations(values)) => {
+                            ChannelValues::Translation(keys(values.map(na::Vector3::from), cubic))
+                        }
+                        Some(gltf::animation::util::ReadOutputs::Rotations(values)) => {
+                            ChannelValues::Rotation(keys(values.into_f32().map(quaternion), cubic))
+                        }
+                        Some(gltf::animation::util::ReadOutputs::Scales(values)) => {
+                            ChannelValues::Scale(keys(values.map(na::Vector3::from), cubic))
+                        }
+                        _ => continue,
+                    };
+
+                    channels.push(Channel {
+                        joint,
+                        interpolation,
+                        times,
+                        values,
+                    });
+                }
+
+                let clip_name = match animation.name() {
+                    Some(clip_name) => clip_name.to_string(),
+                    None => format!("{} {}", name, index),
+                };
+                Rc::new(AnimationClip::new(&clip_name, channels))
+            })
+            .collect::<Vec<_>>();
+
+        Ok(SkinnedObject::new(
+            renderer, meshes, materials, skeleton, clips, name,
+        ))
+    }
+
+    pub fn new(
+        renderer: &Renderer,
+        meshes: Vec<SkinnedMesh>,
+        materials: Vec<Material>,
+        skeleton: Skeleton,
+        clips: Vec<Rc<AnimationClip>>,
+        name: &str,
+    ) -> Self {
+        let instance = Instance::default();
+        let instance_buffer =
+            renderer
+                .device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some(&format!("{} Instance Buffer", name)),
+                    contents: bytemuck::cast_slice(&[instance.uniform]),
+                    usage: wgpu::BufferUsage::VERTEX | wgpu::BufferUsage::COPY_DST,
+                });
+
+        let mut joint_matrices = matrices_to_raw(&skeleton.joint_matrices(&skeleton.rest_pose()));
+        //an empty storage buffer can't be bound
+        if joint_matrices.is_empty() {
+            joint_matrices.push(na::Matrix4::identity().into());
+        }
+        let joint_buffer = renderer
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(&format!("{} Joint Buffer", name)),
+                contents: bytemuck::cast_slice(joint_matrices.as_slice()),
+                usage: wgpu::BufferUsage::STORAGE | wgpu::BufferUsage::COPY_DST,
+            });
+
+        let joint_bind_group_layout = SkinnedObject::get_bind_group_layout(renderer);
+        let joint_bind_group = renderer
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(&format!("{} Joint Bind Group", name)),
+                layout: &joint_bind_group_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: joint_buffer.as_entire_binding(),
+                }],
+            });
+
+        Self {
+            name: name.to_string(),
+            meshes,
+            materials,
+            skeleton,
+            clips,
+            player: AnimationPlayer::new(),
+            instance,
+            instance_buffer: Rc::new(instance_buffer),
+            joint_buffer,
+            joint_bind_group: Rc::new(joint_bind_group),
+            joint_bind_group_layout,
+        }
+    }
+
+    pub fn clip(&self, name: &str) -> Option<Rc<AnimationClip>> {
+        self.clips.iter().find(|clip| clip.name == name).cloned()
+    }
+
+    //false when there is no clip with that name
+    pub fn play(&mut self, clip: &str, looping: bool) -> bool {
+        match self.clip(clip) {
+            Some(clip) => {
+                self.player.play(&clip, looping);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn crossfade(&mut self, clip: &str, looping: bool, duration: f32) -> bool {
+        match self.clip(clip) {
+            Some(clip) => {
+                self.player.crossfade(&clip, looping, duration);
+                true
+            }
+            None => false,
+        }
+    }
+
+    //advances the player and uploads the new pose and instance
+    pub fn update(&mut self, renderer: &Renderer, delta_time: f32) {
+        self.player.advance(delta_time);
+
+        let pose = self.player.pose(&self.skeleton);
+        let joint_matrices = matrices_to_raw(&self.skeleton.joint_matrices(&pose));
+        renderer.queue.write_buffer(
+            &self.joint_buffer,
+            0,
+            bytemuck::cast_slice(joint_matrices.as_slice()),
+        );
+
+        self.instance.update();
+        renderer.queue.write_buffer(
+            &self.instance_buffer,
+            0,
+            bytemuck::cast_slice(&[self.instance.uniform]),
+        );
+    }
+
+    //world space, of the pose the player is at
+    pub fn joint_transform(&self, joint: usize) -> Option<na::Matrix4<f32>> {
+        let pose = self.player.pose(&self.skeleton);
+        let model: na::Matrix4<f32> = self.instance.uniform.model.into();
+
+        self.skeleton
+            .global_transforms(&pose)
+            .get(joint)
+            .map(|global| model * global)
+    }
+
+    pub fn draw(&self, renderer: &mut Renderer) {
+        let camera_bind_group = match renderer.camera_bind_group() {
+            Some(bind_group) => bind_group,
+            None => return,
+        };
+        let position = na::Point3::from(self.instance.isometry.translation.vector);
+        let depth = na::distance(&renderer.camera_position(), &position);
+
+        for mesh in self.meshes.iter() {
+            let material = &self.materials[mesh.material];
+            let pipeline = self.pipeline(renderer, material.alpha_mode);
+
+            let command = DrawCommand {
+                pipeline: pipeline.clone(),
+                bind_groups: vec![
+                    Rc::clone(&material.bind_group),
+                    Rc::clone(&camera_bind_group),
+                    Rc::clone(&self.joint_bind_group),
+                ],
+                vertex_buffers: vec![
+                    Rc::clone(&mesh.vertex_buffer),
+                    Rc::clone(&self.instance_buffer),
+                ],
+                index_buffer: Some(Rc::clone(&mesh.index_buffer)),
+                indices: 0..mesh.size as u32,
+                instances: 0..1,
+                indirect: None,
+                depth,
+            };
+
+            if material.is_transparent() {
+                renderer.submit_transparent(command);
+            } else {
+                renderer.submit_opaque(command);
+            }
+        }
+    }
+
+    fn pipeline(&self, renderer: &Renderer, alpha_mode: AlphaMode) -> Pipeline {
+        let instance_layout = Instance::desc();
+        let pipeline_builder = PipelineBuilder {
+            name: "skinned pipeline",
+            uniform_material: true,
+            uniform_camera: true,
+            bind_group_layouts: vec![&self.joint_bind_group_layout],
+            vertex_shader_path: "shaders/skinned.vert.spv",
+            fragment_shader_path: "shaders/cube.frag.spv",
+            vertex_buffer_layout: vec![SkinnedVertex::desc(), instance_layout.desc()],
+            ..PipelineBuilder::default()
+        };
+
+        match alpha_mode {
+            AlphaMode::Opaque | AlphaMode::Mask(_) => Pipeline::new(renderer, &pipeline_builder),
+            AlphaMode::Blend => Pipeline::new(
+                renderer,
+                &pipeline_builder
+                    .with_alpha_blending()
+                    .with_depth(true, false),
+            ),
+        }
+    }
+
+    pub fn get_bind_group_layout(renderer: &Renderer) -> BindGroupLayout {
+        BindGroupLayout::new(
+            renderer,
+            "Joint Bind Group Layout",
+            &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStage::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        )
+    }
+}
+
+impl SkinnedMesh {
+    pub fn new(
+        renderer: &Renderer,
+        vertices: &[SkinnedVertex],
+        indices: &[u32],
+        material: usize,
+        name: &str,
+    ) -> Self {
+        let vertex_buffer = renderer
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(&format!("{} Vertex Buffer", name)),
+                contents: bytemuck::cast_slice(vertices),
+                usage: wgpu::BufferUsage::VERTEX,
+            });
+        let index_buffer = renderer
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(&format!("{} Index Buffer", name)),
+                contents: bytemuck::cast_slice(indices),
+                usage: wgpu::BufferUsage::INDEX,
+            });
+
+        Self {
+            name: name.to_string(),
+            vertex_buffer: Rc::new(vertex_buffer),
+            index_buffer: Rc::new(index_buffer),
+            size: indices.len(),
+            material,
+        }
+    }
+}
+
+fn joint_transform(node: &gltf::Node) -> JointTransform {
+    let (translation, rotation, scale) = node.transform().decomposed();
+
+    JointTransform {
+        translation: na::Vector3::from(translation),
+        rotation: quaternion(rotation),
+        scale: na::Vector3::from(scale),
+    }
+}
+
+//glTF stores them as x, y, z, w
+fn quaternion(rotation: [f32; 4]) -> na::UnitQuaternion<f32> {
+    na::UnitQuaternion::from_quaternion(na::Quaternion::new(
+        rotation[3],
+        rotation[0],
+        rotation[1],
+        rotation[2],
+    ))
+}
+
+//cubic spline outputs are in tangent, value, out tangent for every key
+fn keys<T>(values: impl Iterator<Item = T>, cubic: bool) -> Vec<T> {
+    match cubic {
+        true => values.skip(1).step_by(3).collect(),
+        false => values.collect(),
+    }
+}
+
+fn matrices_to_raw(matrices: &[na::Matrix4<f32>]) -> Vec<[[f32; 4]; 4]> {
+    matrices.iter().map(|&matrix| matrix.into()).collect()
+}
+
+//the base color factor only tints untextured materials
+fn load_material(
+    renderer: &Renderer,
+    material: &gltf::Material,
+    images: &[gltf::image::Data],
+    name: &str,
+) -> Result<Material, Box<dyn Error>> {
+    let label = format!("{} {}", name, material.name().unwrap_or("material"));
+    let pbr = material.pbr_metallic_roughness();
+
+    let mut result = match pbr.base_color_texture() {
+        Some(info) => {
+            let rgba = to_rgba(&images[info.texture().source().index()])?;
+            Material::from_rgba(&rgba, renderer, &label)
+        }
+        None => {
+            let factor = pbr.base_color_factor();
+            let mut color = [0; 4];
+            for (channel, value) in color.iter_mut().zip(factor.iter()) {
+                *channel = (value.max(0.0).min(1.0) * 255.0).round() as u8;
+            }
+            Material::from_color(color, renderer, &label)
+        }
+    };
+
+    match material.alpha_mode() {
+        gltf::material::AlphaMode::Opaque => {}
+        gltf::material::AlphaMode::Mask => {
+            result.set_alpha_mode(renderer, AlphaMode::Mask(material.alpha_cutoff()))
+        }
+        gltf::material::AlphaMode::Blend => result.set_alpha_mode(renderer, AlphaMode::Blend),
+    }
+
+    Ok(result)
+}
+
+fn to_rgba(data: &gltf::image::Data) -> Result<image::RgbaImage, Box<dyn Error>> {
+    let pixels = match data.format {
+        gltf::image::Format::R8G8B8A8 => data.pixels.clone(),
+        gltf::image::Format::R8G8B8 => data
+            .pixels
+            .chunks(3)
+            .flat_map(|pixel| vec![pixel[0], pixel[1], pixel[2], 255])
+            .collect(),
+        gltf::image::Format::R8 => data
+            .pixels
+            .iter()
+            .flat_map(|&value| vec![value, value, value, 255])
+            .collect(),
+        format => return Err(format!("unsupported glTF image format {:?}", format).into()),
+    };
+
+    image::RgbaImage::from_raw(data.width, data.height, pixels)
+        .ok_or_else(|| "glTF image smaller than its dimensions".into())
+}
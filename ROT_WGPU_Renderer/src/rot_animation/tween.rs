@@ -0,0 +1,410 @@
+use crate::rot_animation::{Easing, Lerp};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Keyframe<T: Lerp> {
+    //seconds from the start of the track
+    pub time: f32,
+    pub value: T,
+    //how the value eases in from the previous key
+    pub easing: Easing,
+}
+
+//Values over time, eased between keys and held past the last one
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track<T: Lerp> {
+    keys: Vec<Keyframe<T>>,
+}
+
+impl<T: Lerp> Track<T> {
+    pub fn new(start: T) -> Self {
+        Self {
+            keys: vec![Keyframe {
+                time: 0.0,
+                value: start,
+                easing: Easing::Linear,
+            }],
+        }
+    }
+
+    pub fn with_key(mut self, time: f32, value: T, easing: Easing) -> Self {
+        let index = self.keys.iter().take_while(|key| key.time <= time).count();
+        self.keys.insert(
+            index,
+            Keyframe {
+                time,
+                value,
+                easing,
+            },
+        );
+        self
+    }
+
+    pub fn keys(&self) -> &[Keyframe<T>] {
+        self.keys.as_slice()
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.keys.last().map_or(0.0, |key| key.time)
+    }
+
+    pub fn sample(&self, time: f32) -> T {
+        let next = self.keys.iter().position(|key| key.time > time);
+
+        match next {
+            Some(0) => self.keys[0].value,
+            Some(next) => {
+                let start = &self.keys[next - 1];
+                let end = &self.keys[next];
+                let t = (time - start.time) / (end.time - start.time);
+
+                T::lerp(start.value, end.value, end.easing.apply(t))
+            }
+            None => self.keys.last().expect("track without keys").value,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LoopMode {
+    Once,
+    //jumps back to the start
+    Loop,
+    //plays forwards then backwards
+    PingPong,
+}
+
+//Plays a track forward with the time it is given, usually the engine delta time
+pub struct Tween<T: Lerp> {
+    track: Track<T>,
+    pub loop_mode: LoopMode,
+    //seconds the start value is held before playing
+    pub delay: f32,
+
+    elapsed: f32,
+    finished: bool,
+    on_complete: Option<Box<dyn FnMut()>>,
+}
+
+impl<T: Lerp> Tween<T> {
+    pub fn new(from: T, to: T, duration: f32, easing: Easing) -> Self {
+        Tween::from_track(Track::new(from).with_key(duration, to, easing))
+    }
+
+    pub fn from_track(track: Track<T>) -> Self {
+        Self {
+            track,
+            loop_mode: LoopMode::Once,
+            delay: 0.0,
+            elapsed: 0.0,
+            finished: false,
+            on_complete: None,
+        }
+    }
+
+    pub fn with_loop_mode(mut self, loop_mode: LoopMode) -> Self {
+        self.loop_mode = loop_mode;
+        self
+    }
+
+    pub fn with_delay(mut self, delay: f32) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    //called when a Once tween ends, and at the end of every cycle of the looping ones
+    pub fn on_complete<F: FnMut() + 'static>(mut self, on_complete: F) -> Self {
+        self.on_complete = Some(Box::new(on_complete));
+        self
+    }
+
+    pub fn track(&self) -> &Track<T> {
+        &self.track
+    }
+
+    pub fn advance(&mut self, delta_time: f32) -> T {
+        if self.finished {
+            return self.value();
+        }
+
+        let duration = self.track.duration();
+        let cycles = self.cycles();
+        self.elapsed += delta_time.max(0.0);
+
+        match (self.loop_mode, duration > 0.0) {
+            (LoopMode::Loop, true) | (LoopMode::PingPong, true) => {
+                for _ in cycles..self.cycles() {
+                    self.complete();
+                }
+            }
+            _ => {
+                if self.elapsed >= self.delay + duration {
+                    self.finished = true;
+                    self.complete();
+                }
+            }
+        }
+
+        self.value()
+    }
+
+    pub fn value(&self) -> T {
+        self.track.sample(self.local_time())
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    //back to the start, delay included
+    pub fn reset(&mut self) {
+        self.elapsed = 0.0;
+        self.finished = false;
+    }
+
+    fn complete(&mut self) {
+        if let Some(on_complete) = self.on_complete.as_mut() {
+            on_complete();
+        }
+    }
+
+    fn cycles(&self) -> u32 {
+        let duration = self.track.duration();
+        match duration > 0.0 {
+            true => ((self.elapsed - self.delay).max(0.0) / duration) as u32,
+            false => 0,
+        }
+    }
+
+    fn local_time(&self) -> f32 {
+        let duration = self.track.duration();
+        let time = (self.elapsed - self.delay).max(0.0);
+        if duration <= 0.0 || self.finished {
+            return duration;
+        }
+
+        match self.loop_mode {
+            LoopMode::Once => time.min(duration),
+            LoopMode::Loop => time % duration,
+            LoopMode::PingPong => match self.cycles() % 2 {
+                0 => time % duration,
+                _ => duration - time % duration,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct TweenId(u32);
+
+trait Animation<O> {
+    //true once finished
+    fn advance(&mut self, target: &mut O, delta_time: f32) -> bool;
+}
+
+struct BoundTween<O, T: Lerp> {
+    tween: Tween<T>,
+    apply: Box<dyn FnMut(&mut O, T)>,
+}
+
+impl<O, T: Lerp> Animation<O> for BoundTween<O, T> {
+    fn advance(&mut self, target: &mut O, delta_time: f32) -> bool {
+        let value = self.tween.advance(delta_time);
+        (self.apply)(target, value);
+
+        self.tween.is_finished()
+    }
+}
+
+//Runs tweens on the properties of one target, a Camera, a Light or an Object, through setters:
+//  animator.add(tween, |camera: &mut Camera, eye| camera.set_eye(eye));
+//Finished tweens are dropped after applying their last value
+pub struct Animator<O> {
+    animations: Vec<(TweenId, Box<dyn Animation<O>>)>,
+    next_id: u32,
+}
+
+impl<O> Default for Animator<O> {
+    fn default() -> Self {
+        Self {
+            animations: Vec::new(),
+            next_id: 0,
+        }
+    }
+}
+
+impl<O: 'static> Animator<O> {
+    pub fn new() -> Self {
+        Animator::default()
+    }
+
+    pub fn add<T, F>(&mut self, tween: Tween<T>, apply: F) -> TweenId
+    where
+        T: Lerp + 'static,
+        F: FnMut(&mut O, T) + 'static,
+    {
+        let id = TweenId(self.next_id);
+        self.next_id += 1;
+
+        self.animations.push((
+            id,
+            Box::new(BoundTween {
+                tween,
+                apply: Box::new(apply),
+            }),
+        ));
+
+        id
+    }
+
+    //the property keeps whatever value it was last given
+    pub fn cancel(&mut self, id: TweenId) -> bool {
+        let count = self.animations.len();
+        self.animations
+            .retain(|(animation_id, _)| *animation_id != id);
+
+        self.animations.len() < count
+    }
+
+    pub fn is_active(&self, id: TweenId) -> bool {
+        self.animations
+            .iter()
+            .any(|(animation_id, _)| *animation_id == id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.animations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.animations.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.animations.clear();
+    }
+
+    //in the order they were added, later tweens win on the same property
+    pub fn update(&mut self, target: &mut O, delta_time: f32) {
+        let mut index = 0;
+        while index < self.animations.len() {
+            match self.animations[index].1.advance(target, delta_time) {
+                true => {
+                    self.animations.remove(index);
+                }
+                false => index += 1,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    fn counter<T: Lerp>(tween: Tween<T>) -> (Tween<T>, Rc<Cell<u32>>) {
+        let count = Rc::new(Cell::new(0));
+        let completions = Rc::clone(&count);
+        let tween = tween.on_complete(move || completions.set(completions.get() + 1));
+
+        (tween, count)
+    }
+
+    #[test]
+    fn tracks_ease_between_keys_and_hold_the_ends() {
+        let track = Track::new(0.0)
+            .with_key(2.0, 10.0, Easing::QuadIn)
+            .with_key(1.0, 4.0, Easing::Linear);
+
+        assert_eq!(
+            track.keys().iter().map(|key| key.time).collect::<Vec<_>>(),
+            vec![0.0, 1.0, 2.0]
+        );
+        assert_eq!(track.duration(), 2.0);
+
+        assert!(close(track.sample(-1.0), 0.0));
+        assert!(close(track.sample(0.5), 2.0));
+        assert!(close(track.sample(1.0), 4.0));
+        //quad in from 4 to 10, a quarter of the way at half the time
+        assert!(close(track.sample(1.5), 5.5));
+        assert!(close(track.sample(5.0), 10.0));
+    }
+
+    #[test]
+    fn once_stops_at_the_end_value() {
+        let (mut tween, completions) = counter(Tween::new(0.0, 1.0, 1.0, Easing::Linear));
+
+        assert!(close(tween.advance(0.25), 0.25));
+        assert!(!tween.is_finished());
+        assert!(close(tween.advance(2.0), 1.0));
+        assert!(tween.is_finished());
+        assert!(close(tween.advance(1.0), 1.0));
+        assert_eq!(completions.get(), 1);
+
+        tween.reset();
+        assert!(close(tween.value(), 0.0) && !tween.is_finished());
+    }
+
+    #[test]
+    fn delay_holds_the_start_value() {
+        let mut tween = Tween::new(2.0, 4.0, 1.0, Easing::Linear).with_delay(0.5);
+
+        assert!(close(tween.advance(0.4), 2.0));
+        assert!(close(tween.advance(0.6), 3.0));
+        assert!(close(tween.advance(0.5), 4.0));
+        assert!(tween.is_finished());
+    }
+
+    #[test]
+    fn loops_wrap_and_complete_every_cycle() {
+        let (mut tween, completions) =
+            counter(Tween::new(0.0, 1.0, 1.0, Easing::Linear).with_loop_mode(LoopMode::Loop));
+
+        assert!(close(tween.advance(0.75), 0.75));
+        assert!(close(tween.advance(0.5), 0.25));
+        assert!(close(tween.advance(2.0), 0.25));
+        assert_eq!(completions.get(), 3);
+        assert!(!tween.is_finished());
+    }
+
+    #[test]
+    fn ping_pong_plays_back_down() {
+        let mut tween =
+            Tween::new(0.0, 1.0, 1.0, Easing::Linear).with_loop_mode(LoopMode::PingPong);
+
+        assert!(close(tween.advance(0.5), 0.5));
+        assert!(close(tween.advance(0.75), 0.75));
+        assert!(close(tween.advance(1.0), 0.25));
+    }
+
+    #[test]
+    fn animator_applies_and_drops_finished_tweens() {
+        let mut animator = Animator::new();
+        let short = animator.add(
+            Tween::new(0.0, 1.0, 1.0, Easing::Linear),
+            |target: &mut [f32; 2], value| target[0] = value,
+        );
+        let long = animator.add(
+            Tween::new(0.0, 10.0, 2.0, Easing::Linear),
+            |target: &mut [f32; 2], value| target[1] = value,
+        );
+        let mut target = [0.0; 2];
+
+        animator.update(&mut target, 0.5);
+        assert!(close(target[0], 0.5) && close(target[1], 2.5));
+
+        animator.update(&mut target, 1.0);
+        assert!(close(target[0], 1.0) && close(target[1], 7.5));
+        assert!(!animator.is_active(short) && animator.is_active(long));
+
+        assert!(animator.cancel(long));
+        animator.update(&mut target, 1.0);
+        assert!(close(target[1], 7.5));
+        assert!(animator.is_empty());
+    }
+}
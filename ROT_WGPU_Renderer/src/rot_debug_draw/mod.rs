@@ -0,0 +1,458 @@
+use crate::rot_pipeline::{Pipeline, PipelineBuilder};
+use crate::rot_primitives::Aabb;
+use crate::Renderer;
+use nalgebra as na;
+use std::ops::Range;
+use std::rc::Rc;
+
+mod overdraw;
+mod render_mode;
+pub(crate) use overdraw::{Overdraw, COUNTER_FORMAT};
+pub use render_mode::RenderDebugMode;
+pub(crate) use render_mode::{vertex_normals_pipeline, view_pipeline, wireframe_pipeline};
+
+pub mod color {
+    pub const WHITE: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+    pub const BLACK: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+    pub const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+    pub const GREEN: [f32; 4] = [0.0, 1.0, 0.0, 1.0];
+    pub const BLUE: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+    pub const YELLOW: [f32; 4] = [1.0, 1.0, 0.0, 1.0];
+    pub const CYAN: [f32; 4] = [0.0, 1.0, 1.0, 1.0];
+    pub const MAGENTA: [f32; 4] = [1.0, 0.0, 1.0, 1.0];
+    pub const GRAY: [f32; 4] = [0.5, 0.5, 0.5, 1.0];
+}
+
+const CIRCLE_SEGMENTS: usize = 32;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct DebugVertex {
+    pub position: [f32; 3],
+    pub color: [f32; 4],
+}
+
+unsafe impl bytemuck::Pod for DebugVertex {}
+unsafe impl bytemuck::Zeroable for DebugVertex {}
+
+impl DebugVertex {
+    pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<DebugVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::InputStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float3,
+                    offset: 0,
+                    shader_location: 0,
+                },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float4,
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                },
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DebugOptions {
+    //seconds the shape stays around, 0.0 draws it for a single frame
+    pub duration: f32,
+    //false draws it on top of everything
+    pub depth_test: bool,
+}
+
+impl Default for DebugOptions {
+    fn default() -> Self {
+        Self {
+            duration: 0.0,
+            depth_test: true,
+        }
+    }
+}
+
+impl DebugOptions {
+    pub fn with_duration(mut self, duration: f32) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    pub fn on_top(mut self) -> Self {
+        self.depth_test = false;
+        self
+    }
+}
+
+struct DebugLine {
+    start: [f32; 3],
+    end: [f32; 3],
+    color: [f32; 4],
+    remaining: f32,
+    depth_test: bool,
+}
+
+//Immediate mode lines, every shape is split into segments that are batched into
+//one dynamic vertex buffer and drawn as a LineList after the scene
+pub struct DebugDraw {
+    pub enabled: bool,
+
+    lines: Vec<DebugLine>,
+
+    vertex_buffer: Option<Rc<wgpu::Buffer>>,
+    //in vertices
+    capacity: usize,
+}
+
+impl DebugDraw {
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            lines: Vec::new(),
+            vertex_buffer: None,
+            capacity: 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+
+    //Ages every shape, the ones already drawn for their whole duration go away
+    pub fn advance(&mut self, delta_time: f32) {
+        for line in self.lines.iter_mut() {
+            line.remaining -= delta_time;
+        }
+        self.lines.retain(|line| line.remaining > 0.0);
+    }
+
+    // SHAPES -------------------------------------------------------
+
+    pub fn line(
+        &mut self,
+        start: na::Point3<f32>,
+        end: na::Point3<f32>,
+        color: [f32; 4],
+        options: DebugOptions,
+    ) {
+        if !self.enabled {
+            return;
+        }
+
+        self.lines.push(DebugLine {
+            start: start.coords.into(),
+            end: end.coords.into(),
+            color,
+            remaining: options.duration,
+            depth_test: options.depth_test,
+        });
+    }
+
+    //closed loop through every point
+    pub fn polyline(&mut self, points: &[na::Point3<f32>], color: [f32; 4], options: DebugOptions) {
+        for (index, start) in points.iter().enumerate() {
+            let end = points[(index + 1) % points.len()];
+            self.line(*start, end, color, options);
+        }
+    }
+
+    pub fn aabb(&mut self, aabb: &Aabb, color: [f32; 4], options: DebugOptions) {
+        self.obb(
+            &na::Isometry3::translation(aabb.center().x, aabb.center().y, aabb.center().z),
+            aabb.half_extents(),
+            color,
+            options,
+        );
+    }
+
+    pub fn obb(
+        &mut self,
+        isometry: &na::Isometry3<f32>,
+        half_extents: na::Vector3<f32>,
+        color: [f32; 4],
+        options: DebugOptions,
+    ) {
+        let corner = |x: f32, y: f32, z: f32| {
+            isometry * na::Point3::from(half_extents.component_mul(&na::Vector3::new(x, y, z)))
+        };
+        let corners = [
+            corner(-1.0, -1.0, -1.0),
+            corner(1.0, -1.0, -1.0),
+            corner(1.0, 1.0, -1.0),
+            corner(-1.0, 1.0, -1.0),
+            corner(-1.0, -1.0, 1.0),
+            corner(1.0, -1.0, 1.0),
+            corner(1.0, 1.0, 1.0),
+            corner(-1.0, 1.0, 1.0),
+        ];
+
+        self.box_edges(&corners, color, options);
+    }
+
+    //three great circles
+    pub fn sphere(
+        &mut self,
+        center: na::Point3<f32>,
+        radius: f32,
+        color: [f32; 4],
+        options: DebugOptions,
+    ) {
+        self.circle(center, na::Vector3::x(), radius, color, options);
+        self.circle(center, na::Vector3::y(), radius, color, options);
+        self.circle(center, na::Vector3::z(), radius, color, options);
+    }
+
+    pub fn circle(
+        &mut self,
+        center: na::Point3<f32>,
+        normal: na::Vector3<f32>,
+        radius: f32,
+        color: [f32; 4],
+        options: DebugOptions,
+    ) {
+        let (tangent, bitangent) = DebugDraw::basis(&normal);
+        let points = (0..CIRCLE_SEGMENTS)
+            .map(|index| {
+                let angle = 2.0 * std::f32::consts::PI * index as f32 / CIRCLE_SEGMENTS as f32;
+                center + (tangent * angle.cos() + bitangent * angle.sin()) * radius
+            })
+            .collect::<Vec<_>>();
+
+        self.polyline(&points, color, options);
+    }
+
+    pub fn arrow(
+        &mut self,
+        start: na::Point3<f32>,
+        end: na::Point3<f32>,
+        color: [f32; 4],
+        options: DebugOptions,
+    ) {
+        self.line(start, end, color, options);
+
+        let direction = end - start;
+        let length = direction.magnitude();
+        if length <= f32::EPSILON {
+            return;
+        }
+
+        let head = length * 0.2;
+        let back = end - direction / length * head;
+        let (tangent, bitangent) = DebugDraw::basis(&direction);
+        for side in [tangent, -tangent, bitangent, -bitangent].iter() {
+            self.line(end, back + side * head * 0.5, color, options);
+        }
+    }
+
+    //on the XZ plane, size is the full width and divisions the cells per side
+    pub fn grid(
+        &mut self,
+        center: na::Point3<f32>,
+        size: f32,
+        divisions: u32,
+        color: [f32; 4],
+        options: DebugOptions,
+    ) {
+        let divisions = divisions.max(1);
+        let half = size * 0.5;
+
+        for index in 0..=divisions {
+            let offset = -half + size * index as f32 / divisions as f32;
+
+            self.line(
+                center + na::Vector3::new(offset, 0.0, -half),
+                center + na::Vector3::new(offset, 0.0, half),
+                color,
+                options,
+            );
+            self.line(
+                center + na::Vector3::new(-half, 0.0, offset),
+                center + na::Vector3::new(half, 0.0, offset),
+                color,
+                options,
+            );
+        }
+    }
+
+    //X red, Y green, Z blue
+    pub fn gizmo(&mut self, isometry: &na::Isometry3<f32>, size: f32, options: DebugOptions) {
+        let origin = isometry * na::Point3::origin();
+        let axes = [
+            (na::Vector3::x(), color::RED),
+            (na::Vector3::y(), color::GREEN),
+            (na::Vector3::z(), color::BLUE),
+        ];
+
+        for (axis, axis_color) in axes.iter() {
+            self.arrow(
+                origin,
+                origin + isometry.transform_vector(axis) * size,
+                *axis_color,
+                options,
+            );
+        }
+    }
+
+    //small cross, for light positions and such
+    pub fn point(
+        &mut self,
+        position: na::Point3<f32>,
+        size: f32,
+        color: [f32; 4],
+        options: DebugOptions,
+    ) {
+        let half = size * 0.5;
+        for axis in [na::Vector3::x(), na::Vector3::y(), na::Vector3::z()].iter() {
+            self.line(
+                position - axis * half,
+                position + axis * half,
+                color,
+                options,
+            );
+        }
+    }
+
+    //outline of a view projection, like Camera::frustum uses (OpenGL -1..1 depth)
+    pub fn frustum(
+        &mut self,
+        view_proj: &na::Matrix4<f32>,
+        color: [f32; 4],
+        options: DebugOptions,
+    ) {
+        let inverse = match view_proj.try_inverse() {
+            Some(inverse) => inverse,
+            None => return,
+        };
+
+        let corner = |x: f32, y: f32, z: f32| {
+            na::Point3::from_homogeneous(inverse * na::Vector4::new(x, y, z, 1.0))
+                .unwrap_or_else(na::Point3::origin)
+        };
+        let corners = [
+            corner(-1.0, -1.0, -1.0),
+            corner(1.0, -1.0, -1.0),
+            corner(1.0, 1.0, -1.0),
+            corner(-1.0, 1.0, -1.0),
+            corner(-1.0, -1.0, 1.0),
+            corner(1.0, -1.0, 1.0),
+            corner(1.0, 1.0, 1.0),
+            corner(-1.0, 1.0, 1.0),
+        ];
+
+        self.box_edges(&corners, color, options);
+    }
+
+    //first four corners are one face, the last four the opposite one in the same order
+    fn box_edges(
+        &mut self,
+        corners: &[na::Point3<f32>; 8],
+        color: [f32; 4],
+        options: DebugOptions,
+    ) {
+        for index in 0..4 {
+            let next = (index + 1) % 4;
+
+            self.line(corners[index], corners[next], color, options);
+            self.line(corners[index + 4], corners[next + 4], color, options);
+            self.line(corners[index], corners[index + 4], color, options);
+        }
+    }
+
+    //two unit vectors perpendicular to the direction and each other
+    fn basis(direction: &na::Vector3<f32>) -> (na::Vector3<f32>, na::Vector3<f32>) {
+        let direction = direction.normalize();
+        let helper = if direction.y.abs() < 0.99 {
+            na::Vector3::y()
+        } else {
+            na::Vector3::x()
+        };
+        let tangent = direction.cross(&helper).normalize();
+        let bitangent = direction.cross(&tangent);
+
+        (tangent, bitangent)
+    }
+
+    // GPU ----------------------------------------------------------
+
+    //Writes every line to the vertex buffer, depth tested ones first.
+    //Returns the buffer and the vertex ranges of both groups
+    pub(crate) fn upload(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Option<(Rc<wgpu::Buffer>, Range<u32>, Range<u32>)> {
+        if !self.enabled || self.lines.is_empty() {
+            return None;
+        }
+
+        let mut vertices = Vec::with_capacity(self.lines.len() * 2);
+        for &depth_test in [true, false].iter() {
+            for line in self
+                .lines
+                .iter()
+                .filter(|line| line.depth_test == depth_test)
+            {
+                vertices.push(DebugVertex {
+                    position: line.start,
+                    color: line.color,
+                });
+                vertices.push(DebugVertex {
+                    position: line.end,
+                    color: line.color,
+                });
+            }
+        }
+        let tested = 2 * self.lines.iter().filter(|line| line.depth_test).count() as u32;
+
+        if vertices.len() > self.capacity || self.vertex_buffer.is_none() {
+            self.capacity = self.capacity.max(64);
+            while self.capacity < vertices.len() {
+                self.capacity *= 2;
+            }
+
+            self.vertex_buffer = Some(Rc::new(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Debug Draw Vertex Buffer"),
+                size: (self.capacity * std::mem::size_of::<DebugVertex>()) as wgpu::BufferAddress,
+                usage: wgpu::BufferUsage::VERTEX | wgpu::BufferUsage::COPY_DST,
+                mapped_at_creation: false,
+            })));
+        }
+
+        let vertex_buffer = Rc::clone(self.vertex_buffer.as_ref().unwrap());
+        queue.write_buffer(&vertex_buffer, 0, bytemuck::cast_slice(vertices.as_slice()));
+
+        Some((vertex_buffer, 0..tested, tested..vertices.len() as u32))
+    }
+
+    //depth tested and on top, neither writes depth
+    pub(crate) fn pipelines(renderer: &Renderer) -> (Pipeline, Pipeline) {
+        let pipeline_builder = PipelineBuilder {
+            name: "debug draw pipeline",
+            uniform_camera: true,
+            vertex_shader_path: "shaders/debug_line.vert.spv",
+            fragment_shader_path: "shaders/debug_line.frag.spv",
+            vertex_buffer_layout: vec![DebugVertex::desc()],
+            topology: wgpu::PrimitiveTopology::LineList,
+            cull_mode: wgpu::CullMode::None,
+            depth_compare: wgpu::CompareFunction::LessEqual,
+            ..PipelineBuilder::default()
+        }
+        .with_alpha_blending();
+
+        (
+            Pipeline::new(renderer, &pipeline_builder.clone().with_depth(true, false)),
+            Pipeline::new(renderer, &pipeline_builder.with_depth(false, false)),
+        )
+    }
+}
+
+impl Default for DebugDraw {
+    fn default() -> Self {
+        DebugDraw::new()
+    }
+}
@@ -0,0 +1,121 @@
+use crate::rot_pipeline::{BindGroupLayout, Pipeline, PipelineBuilder};
+use crate::rot_primitives::Texture;
+use crate::Renderer;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+//blendable, and counts far past what the ramp shows
+pub(crate) const COUNTER_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R16Float;
+
+//What RenderDebugMode::Overdraw draws with. The opaque draws of a view add 1.0 per fragment
+//into a counter, then the ramp maps the counts to colors over the view's own attachment
+pub(crate) struct Overdraw {
+    ramp: Pipeline,
+    bind_group_layout: BindGroupLayout,
+    //one per attachment size, the views of the same size reuse it one after the other
+    counters: HashMap<(u32, u32), OverdrawCounter>,
+}
+
+pub(crate) struct OverdrawCounter {
+    pub counter: Texture,
+    //the window depth may be multisampled, the counter never is
+    pub depth: Texture,
+    pub bind_group: Rc<wgpu::BindGroup>,
+}
+
+impl Overdraw {
+    pub fn new(renderer: &Renderer) -> Self {
+        let bind_group_layout = Overdraw::get_bind_group_layout(renderer);
+        let pipeline_builder = PipelineBuilder {
+            name: "overdraw ramp pipeline",
+            bind_group_layouts: vec![&bind_group_layout],
+            vertex_shader_path: "shaders/clear.vert.spv",
+            fragment_shader_path: "shaders/debug_overdraw_ramp.frag.spv",
+            cull_mode: wgpu::CullMode::None,
+            ..PipelineBuilder::default()
+        }
+        .with_depth(false, false);
+
+        Self {
+            ramp: Pipeline::new(renderer, &pipeline_builder),
+            bind_group_layout,
+            counters: HashMap::new(),
+        }
+    }
+
+    //full screen triangle, the counter bound at set 0 and drawn with 0..3, 0..1
+    pub fn ramp(&self) -> &Pipeline {
+        &self.ramp
+    }
+
+    //creates the counter for an attachment of this size the first time
+    pub fn prepare(&mut self, renderer: &Renderer, dimensions: (u32, u32)) {
+        let bind_group_layout = &self.bind_group_layout;
+        self.counters.entry(dimensions).or_insert_with(|| {
+            let counter =
+                Texture::color_attachment(renderer, dimensions, COUNTER_FORMAT, "overdraw counter");
+            let depth = Texture::depth_attachment(renderer, dimensions, "overdraw depth");
+            //counts are read with texelFetch, never filtered
+            let sampler = renderer.device.create_sampler(&wgpu::SamplerDescriptor {
+                label: Some("overdraw counter sampler"),
+                ..Default::default()
+            });
+
+            let bind_group = renderer
+                .device
+                .create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("Overdraw Bind Group"),
+                    layout: bind_group_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureView(&counter.view),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::Sampler(&sampler),
+                        },
+                    ],
+                });
+
+            OverdrawCounter {
+                counter,
+                depth,
+                bind_group: Rc::new(bind_group),
+            }
+        });
+    }
+
+    //None until prepare was called for this size
+    pub fn counter(&self, dimensions: (u32, u32)) -> Option<&OverdrawCounter> {
+        self.counters.get(&dimensions)
+    }
+
+    fn get_bind_group_layout(renderer: &Renderer) -> BindGroupLayout {
+        BindGroupLayout::new(
+            renderer,
+            "Overdraw Bind Group Layout",
+            &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler {
+                        filtering: false,
+                        comparison: false,
+                    },
+                    count: None,
+                },
+            ],
+        )
+    }
+}
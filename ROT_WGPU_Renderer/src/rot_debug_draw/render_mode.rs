@@ -0,0 +1,162 @@
+use crate::rot_pipeline::{Pipeline, PipelineBuilder};
+use crate::rot_primitives::{Instance, Vertex};
+use crate::Renderer;
+
+//Global view used to inspect the geometry, switched at runtime with Renderer::set_debug_mode
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum RenderDebugMode {
+    Shaded,
+    //shaded plus the triangle edges on top
+    Wireframe,
+    //world space normals mapped to 0..1
+    Normals,
+    UvChecker,
+    //white near the camera fading to black
+    Depth,
+    //fragments counted without depth test, then colored from blue through red to white
+    Overdraw,
+    //shaded plus each vertex normal as a debug line
+    VertexNormals,
+}
+
+impl RenderDebugMode {
+    pub const ALL: [RenderDebugMode; 7] = [
+        RenderDebugMode::Shaded,
+        RenderDebugMode::Wireframe,
+        RenderDebugMode::Normals,
+        RenderDebugMode::UvChecker,
+        RenderDebugMode::Depth,
+        RenderDebugMode::Overdraw,
+        RenderDebugMode::VertexNormals,
+    ];
+
+    //handy to cycle through them from a key binding
+    pub fn next(self) -> Self {
+        let index = RenderDebugMode::ALL
+            .iter()
+            .position(|&mode| mode == self)
+            .unwrap_or(0);
+
+        RenderDebugMode::ALL[(index + 1) % RenderDebugMode::ALL.len()]
+    }
+
+    //the views that replace the material pipeline entirely
+    pub fn replaces_shading(&self) -> bool {
+        match self {
+            RenderDebugMode::Normals
+            | RenderDebugMode::UvChecker
+            | RenderDebugMode::Depth
+            | RenderDebugMode::Overdraw => true,
+            _ => false,
+        }
+    }
+}
+
+impl Default for RenderDebugMode {
+    fn default() -> Self {
+        RenderDebugMode::Shaded
+    }
+}
+
+//Pipeline drawing a mesh + instance buffer pair in place of its material,
+//only the camera is bound at set 0
+pub(crate) fn view_pipeline(renderer: &Renderer, mode: RenderDebugMode) -> Option<Pipeline> {
+    let (vertex_shader_path, fragment_shader_path) = match mode {
+        RenderDebugMode::Normals => (
+            "shaders/pos_as_color.vert.spv",
+            "shaders/pos_as_color.frag.spv",
+        ),
+        RenderDebugMode::UvChecker => ("shaders/debug_view.vert.spv", "shaders/debug_uv.frag.spv"),
+        RenderDebugMode::Depth => (
+            "shaders/debug_view.vert.spv",
+            "shaders/debug_depth.frag.spv",
+        ),
+        RenderDebugMode::Overdraw => (
+            "shaders/debug_view.vert.spv",
+            "shaders/debug_overdraw.frag.spv",
+        ),
+        _ => return None,
+    };
+
+    let instance_layout = Instance::desc();
+    let pipeline_builder = PipelineBuilder {
+        name: "debug view pipeline",
+        uniform_camera: true,
+        vertex_shader_path,
+        fragment_shader_path,
+        vertex_buffer_layout: vec![Vertex::desc(), instance_layout.desc()],
+        ..PipelineBuilder::default()
+    };
+
+    //adds 1.0 into the overdraw counter per fragment
+    let count = wgpu::BlendState {
+        src_factor: wgpu::BlendFactor::One,
+        dst_factor: wgpu::BlendFactor::One,
+        operation: wgpu::BlendOperation::Add,
+    };
+    let pipeline_builder = match mode {
+        RenderDebugMode::Overdraw => pipeline_builder
+            .with_blend(count.clone(), count)
+            .with_cull_mode(wgpu::CullMode::None)
+            .with_depth(false, false),
+        _ => pipeline_builder,
+    };
+
+    Some(Pipeline::new(renderer, &pipeline_builder))
+}
+
+//A line along each vertex normal, for Mesh::normal_lines_buffer + instance buffer pairs
+pub(crate) fn vertex_normals_pipeline(renderer: &Renderer) -> Pipeline {
+    let instance_layout = Instance::desc();
+    let pipeline_builder = PipelineBuilder {
+        name: "vertex normals pipeline",
+        uniform_camera: true,
+        vertex_shader_path: "shaders/debug_view.vert.spv",
+        fragment_shader_path: "shaders/debug_normal_line.frag.spv",
+        vertex_buffer_layout: vec![Vertex::desc(), instance_layout.desc()],
+        topology: wgpu::PrimitiveTopology::LineList,
+        cull_mode: wgpu::CullMode::None,
+        depth_compare: wgpu::CompareFunction::LessEqual,
+        ..PipelineBuilder::default()
+    }
+    .with_depth(true, false);
+
+    Pipeline::new(renderer, &pipeline_builder)
+}
+
+//Edges drawn over the shaded meshes. With NON_FILL_POLYGON_MODE it rasterizes the
+//indexed mesh as lines, otherwise it is a fill pipeline for Mesh::unindexed_buffer
+//that keeps the fragments close to an edge through barycentrics.
+//The bool tells which one it is
+pub(crate) fn wireframe_pipeline(renderer: &Renderer) -> (Pipeline, bool) {
+    let native = renderer
+        .features
+        .contains(wgpu::Features::NON_FILL_POLYGON_MODE);
+
+    let instance_layout = Instance::desc();
+    let pipeline_builder = PipelineBuilder {
+        name: "wireframe pipeline",
+        uniform_camera: true,
+        vertex_shader_path: "shaders/debug_view.vert.spv",
+        fragment_shader_path: "shaders/debug_wireframe.frag.spv",
+        vertex_buffer_layout: vec![Vertex::desc(), instance_layout.desc()],
+        cull_mode: wgpu::CullMode::None,
+        depth_compare: wgpu::CompareFunction::LessEqual,
+        ..PipelineBuilder::default()
+    }
+    .with_depth(true, false)
+    .with_alpha_blending();
+
+    let pipeline_builder = if native {
+        pipeline_builder
+            .with_polygon_mode(wgpu::PolygonMode::Line)
+            .with_depth_bias(-1, -1.0, 0.0)
+    } else {
+        PipelineBuilder {
+            fragment_shader_path: "shaders/debug_barycentric.frag.spv",
+            ..pipeline_builder
+        }
+    };
+
+    (Pipeline::new(renderer, &pipeline_builder), native)
+}
@@ -0,0 +1,55 @@
+use crate::rot_animation::Lerp;
+
+//Piecewise linear over the particle life, 0.0 at spawn and 1.0 at death
+#[derive(Debug, Clone, PartialEq)]
+pub struct Curve<T: Lerp> {
+    keys: Vec<(f32, T)>,
+}
+
+impl<T: Lerp> Curve<T> {
+    pub fn constant(value: T) -> Self {
+        Self {
+            keys: vec![(0.0, value)],
+        }
+    }
+
+    pub fn linear(start: T, end: T) -> Self {
+        Self {
+            keys: vec![(0.0, start), (1.0, end)],
+        }
+    }
+
+    pub fn new(mut keys: Vec<(f32, T)>) -> Self {
+        keys.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+        Self { keys }
+    }
+
+    pub fn with_key(mut self, time: f32, value: T) -> Self {
+        let index = self.keys.iter().take_while(|key| key.0 <= time).count();
+        self.keys.insert(index, (time, value));
+        self
+    }
+
+    pub fn sample(&self, time: f32) -> T {
+        let next = self.keys.iter().position(|key| key.0 > time);
+
+        match next {
+            Some(0) => self.keys[0].1,
+            Some(next) => {
+                let (start_time, start) = self.keys[next - 1];
+                let (end_time, end) = self.keys[next];
+
+                T::lerp(start, end, (time - start_time) / (end_time - start_time))
+            }
+            None => self.keys.last().expect("curve without keys").1,
+        }
+    }
+
+    //evenly spaced samples from 0.0 to 1.0, what the shaders get
+    pub fn bake(&self, count: usize) -> Vec<T> {
+        (0..count)
+            .map(|index| self.sample(index as f32 / (count - 1).max(1) as f32))
+            .collect()
+    }
+}
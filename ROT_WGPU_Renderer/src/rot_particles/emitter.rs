@@ -0,0 +1,511 @@
+use crate::rot_particles::Curve;
+use crate::rot_pipeline::{
+    BindGroupLayout, ComputePipeline, ComputePipelineBuilder, Pipeline, PipelineBuilder,
+};
+use crate::rot_primitives::{Camera, Material};
+use crate::rot_render_queue::{ComputeCommand, DrawCommand, PerView};
+use crate::Renderer;
+use nalgebra as na;
+use std::rc::Rc;
+use wgpu::util::DeviceExt;
+
+const WORKGROUP_SIZE: u32 = 64;
+//samples of the over-life curves handed to the vertex shader
+const CURVE_SAMPLES: usize = 16;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ParticleBlend {
+    //brighter where particles overlap, fire and sparks
+    Additive,
+    //smoke and dust, not sorted between particles
+    Alpha,
+}
+
+#[derive(Debug, Clone)]
+pub struct EmitterSettings {
+    pub max_particles: u32,
+    //particles per second
+    pub spawn_rate: f32,
+    //seconds, each particle picks one in the range
+    pub lifetime: (f32, f32),
+
+    //particles leave inside a cone around direction, half angle in radians
+    pub direction: na::Vector3<f32>,
+    pub cone_angle: f32,
+    pub speed: (f32, f32),
+    //spawn positions are spread inside a sphere around the emitter
+    pub spawn_radius: f32,
+
+    pub gravity: na::Vector3<f32>,
+    //fraction of the velocity lost per second
+    pub drag: f32,
+
+    //world units
+    pub size_over_life: Curve<f32>,
+    pub color_over_life: Curve<[f32; 4]>,
+
+    //columns and rows of a flipbook texture, played once over the particle life
+    pub flipbook: [u32; 2],
+    pub blend: ParticleBlend,
+}
+
+impl Default for EmitterSettings {
+    fn default() -> Self {
+        Self {
+            max_particles: 1024,
+            spawn_rate: 64.0,
+            lifetime: (1.0, 2.0),
+            direction: na::Vector3::y(),
+            cone_angle: 0.3,
+            speed: (1.0, 2.0),
+            spawn_radius: 0.0,
+            gravity: na::Vector3::new(0.0, -9.81, 0.0),
+            drag: 0.0,
+            size_over_life: Curve::constant(0.1),
+            color_over_life: Curve::linear([1.0; 4], [1.0, 1.0, 1.0, 0.0]),
+            flipbook: [1, 1],
+            blend: ParticleBlend::Additive,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct Particle {
+    position_age: [f32; 4],
+    velocity_lifetime: [f32; 4],
+}
+
+unsafe impl bytemuck::Pod for Particle {}
+unsafe impl bytemuck::Zeroable for Particle {}
+
+impl Particle {
+    //the storage buffer is read directly as per instance data
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Particle>() as wgpu::BufferAddress,
+            step_mode: wgpu::InputStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float4,
+                    offset: 0,
+                    shader_location: 0,
+                },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float4,
+                    offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                },
+            ],
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct SimulationUniform {
+    origin_radius: [f32; 4],
+    direction_cone: [f32; 4],
+    gravity_drag: [f32; 4],
+    speed_lifetime: [f32; 4],
+    delta_time: [f32; 4],
+    //first slot, count, capacity, seed
+    spawn: [u32; 4],
+}
+
+unsafe impl bytemuck::Pod for SimulationUniform {}
+unsafe impl bytemuck::Zeroable for SimulationUniform {}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct RenderUniform {
+    camera_right: [f32; 4],
+    camera_up: [f32; 4],
+    color_over_life: [[f32; 4]; CURVE_SAMPLES],
+    size_over_life: [[f32; 4]; CURVE_SAMPLES / 4],
+    //columns, rows
+    flipbook: [u32; 4],
+}
+
+unsafe impl bytemuck::Pod for RenderUniform {}
+unsafe impl bytemuck::Zeroable for RenderUniform {}
+
+//the render uniform of one view
+struct RenderSlot {
+    buffer: wgpu::Buffer,
+    bind_group: Rc<wgpu::BindGroup>,
+}
+
+//Spawns and simulates its particles in a compute pass and draws them as camera facing
+//billboards, one instance per slot. Slots are reused in a ring, so max_particles should
+//cover spawn_rate times the longest lifetime
+pub struct ParticleEmitter {
+    pub name: String,
+    pub settings: EmitterSettings,
+    pub position: na::Point3<f32>,
+    //false stops spawning, the particles alive keep going
+    pub emitting: bool,
+
+    texture: Rc<Material>,
+
+    capacity: u32,
+    next_slot: u32,
+    spawn_accumulator: f32,
+    pending_burst: u32,
+    frame: u32,
+
+    particle_buffer: Rc<wgpu::Buffer>,
+    simulation_buffer: wgpu::Buffer,
+
+    simulation_bind_group: Rc<wgpu::BindGroup>,
+    //the camera axes differ per view
+    render_slots: PerView<RenderSlot>,
+    render_bind_group_layout: BindGroupLayout,
+    simulation_pipeline: ComputePipeline,
+}
+
+impl ParticleEmitter {
+    //texture is sampled on every billboard, Material::from_color makes a plain one
+    pub fn new(
+        renderer: &Renderer,
+        name: &str,
+        settings: EmitterSettings,
+        texture: Rc<Material>,
+    ) -> Self {
+        let capacity = settings.max_particles.max(1);
+
+        let particle_buffer = ParticleEmitter::create_particle_buffer(renderer, name, capacity);
+        let simulation_buffer = renderer.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("{} Particle Simulation Buffer", name)),
+            size: std::mem::size_of::<SimulationUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let simulation_bind_group_layout =
+            ParticleEmitter::get_simulation_bind_group_layout(renderer);
+        let simulation_bind_group = ParticleEmitter::create_simulation_bind_group(
+            renderer,
+            &simulation_bind_group_layout,
+            &simulation_buffer,
+            &particle_buffer,
+            name,
+        );
+
+        let render_bind_group_layout = ParticleEmitter::get_render_bind_group_layout(renderer);
+
+        let simulation_pipeline = ComputePipeline::new(
+            renderer,
+            &ComputePipelineBuilder {
+                name: "particle simulation pipeline",
+                shader_path: "shaders/particle_simulate.comp.spv",
+                bind_group_layouts: vec![&simulation_bind_group_layout],
+            },
+        );
+
+        Self {
+            name: name.to_string(),
+            settings,
+            position: na::Point3::origin(),
+            emitting: true,
+            texture,
+            capacity,
+            next_slot: 0,
+            spawn_accumulator: 0.0,
+            pending_burst: 0,
+            frame: 0,
+            particle_buffer: Rc::new(particle_buffer),
+            simulation_buffer,
+            simulation_bind_group: Rc::new(simulation_bind_group),
+            render_slots: PerView::new(),
+            render_bind_group_layout,
+            simulation_pipeline,
+        }
+    }
+
+    //spawned on the next update on top of the spawn rate
+    pub fn burst(&mut self, count: u32) {
+        self.pending_burst += count;
+    }
+
+    //drops every particle alive, also picks up a new settings.max_particles
+    pub fn reset(&mut self, renderer: &Renderer) {
+        self.capacity = self.settings.max_particles.max(1);
+        self.next_slot = 0;
+        self.spawn_accumulator = 0.0;
+        self.pending_burst = 0;
+
+        let particle_buffer =
+            ParticleEmitter::create_particle_buffer(renderer, &self.name, self.capacity);
+        self.simulation_bind_group = Rc::new(ParticleEmitter::create_simulation_bind_group(
+            renderer,
+            &ParticleEmitter::get_simulation_bind_group_layout(renderer),
+            &self.simulation_buffer,
+            &particle_buffer,
+            &self.name,
+        ));
+        self.particle_buffer = Rc::new(particle_buffer);
+    }
+
+    //spawns this frame's particles and queues the simulation step
+    pub fn update(&mut self, renderer: &mut Renderer, delta_time: f32) {
+        let settings = &self.settings;
+
+        if self.emitting {
+            self.spawn_accumulator += settings.spawn_rate.max(0.0) * delta_time;
+        }
+        let spawn_count = (self.spawn_accumulator as u32 + self.pending_burst).min(self.capacity);
+        self.spawn_accumulator = self.spawn_accumulator.fract();
+        self.pending_burst = 0;
+
+        let direction = settings
+            .direction
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(na::Vector3::y);
+        let uniform = SimulationUniform {
+            origin_radius: [
+                self.position.x,
+                self.position.y,
+                self.position.z,
+                settings.spawn_radius,
+            ],
+            direction_cone: [
+                direction.x,
+                direction.y,
+                direction.z,
+                settings.cone_angle.max(0.0).min(std::f32::consts::PI).cos(),
+            ],
+            gravity_drag: [
+                settings.gravity.x,
+                settings.gravity.y,
+                settings.gravity.z,
+                settings.drag.max(0.0),
+            ],
+            speed_lifetime: [
+                settings.speed.0,
+                settings.speed.1,
+                settings.lifetime.0,
+                settings.lifetime.1,
+            ],
+            delta_time: [delta_time, 0.0, 0.0, 0.0],
+            spawn: [self.next_slot, spawn_count, self.capacity, self.frame],
+        };
+        renderer
+            .queue
+            .write_buffer(&self.simulation_buffer, 0, bytemuck::cast_slice(&[uniform]));
+
+        self.next_slot = (self.next_slot + spawn_count) % self.capacity;
+        self.frame = self.frame.wrapping_add(1);
+
+        renderer.submit_compute(ComputeCommand {
+            pipeline: Rc::clone(&self.simulation_pipeline.compute_pipeline),
+            bind_groups: vec![Rc::clone(&self.simulation_bind_group)],
+            workgroups: [(self.capacity + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE, 1, 1],
+        });
+    }
+
+    //one instanced draw in the transparent pass, sorted by the emitter position
+    pub fn draw(&self, renderer: &mut Renderer, camera: &Camera) {
+        let (right, up) = camera.billboard_axes();
+
+        let mut color_over_life = [[0.0; 4]; CURVE_SAMPLES];
+        color_over_life.copy_from_slice(&self.settings.color_over_life.bake(CURVE_SAMPLES));
+        let mut size_over_life = [[0.0; 4]; CURVE_SAMPLES / 4];
+        for (index, size) in self
+            .settings
+            .size_over_life
+            .bake(CURVE_SAMPLES)
+            .into_iter()
+            .enumerate()
+        {
+            size_over_life[index / 4][index % 4] = size;
+        }
+
+        let uniform = RenderUniform {
+            camera_right: [right.x, right.y, right.z, 0.0],
+            camera_up: [up.x, up.y, up.z, 0.0],
+            color_over_life,
+            size_over_life,
+            flipbook: [
+                self.settings.flipbook[0].max(1),
+                self.settings.flipbook[1].max(1),
+                0,
+                0,
+            ],
+        };
+        let render_slot = self.render_slots.get(renderer, || {
+            ParticleEmitter::create_render_slot(
+                renderer,
+                &self.render_bind_group_layout,
+                &self.name,
+            )
+        });
+        renderer
+            .queue
+            .write_buffer(&render_slot.buffer, 0, bytemuck::cast_slice(&[uniform]));
+
+        let camera_bind_group = match renderer.camera_bind_group() {
+            Some(bind_group) => bind_group,
+            None => return,
+        };
+        let pipeline = self.pipeline(renderer);
+
+        renderer.submit_transparent(DrawCommand {
+            pipeline: pipeline.clone(),
+            bind_groups: vec![
+                Rc::clone(&self.texture.bind_group),
+                camera_bind_group,
+                Rc::clone(&render_slot.bind_group),
+            ],
+            vertex_buffers: vec![Rc::clone(&self.particle_buffer)],
+            index_buffer: None,
+            indices: 0..6,
+            instances: 0..self.capacity,
+            indirect: None,
+            depth: na::distance(&renderer.camera_position(), &self.position),
+        });
+    }
+
+    fn pipeline(&self, renderer: &Renderer) -> Pipeline {
+        let pipeline_builder = PipelineBuilder {
+            name: "particle pipeline",
+            uniform_material: true,
+            uniform_camera: true,
+            bind_group_layouts: vec![&self.render_bind_group_layout],
+            vertex_shader_path: "shaders/particle.vert.spv",
+            fragment_shader_path: "shaders/particle.frag.spv",
+            vertex_buffer_layout: vec![Particle::desc()],
+            cull_mode: wgpu::CullMode::None,
+            ..PipelineBuilder::default()
+        }
+        .with_depth(true, false);
+
+        let pipeline_builder = match self.settings.blend {
+            ParticleBlend::Additive => pipeline_builder.with_additive_blending(),
+            ParticleBlend::Alpha => pipeline_builder.with_alpha_blending(),
+        };
+
+        Pipeline::new(renderer, &pipeline_builder)
+    }
+
+    //zeroed particles have a lifetime of 0.0, so they start dead
+    fn create_particle_buffer(renderer: &Renderer, name: &str, capacity: u32) -> wgpu::Buffer {
+        renderer
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(&format!("{} Particle Buffer", name)),
+                contents: bytemuck::cast_slice(
+                    vec![
+                        Particle {
+                            position_age: [0.0; 4],
+                            velocity_lifetime: [0.0; 4],
+                        };
+                        capacity as usize
+                    ]
+                    .as_slice(),
+                ),
+                usage: wgpu::BufferUsage::STORAGE | wgpu::BufferUsage::VERTEX,
+            })
+    }
+
+    fn create_render_slot(
+        renderer: &Renderer,
+        layout: &wgpu::BindGroupLayout,
+        name: &str,
+    ) -> RenderSlot {
+        let buffer = renderer.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("{} Particle Render Buffer", name)),
+            size: std::mem::size_of::<RenderUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = renderer
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(&format!("{} Particle Render Bind Group", name)),
+                layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                }],
+            });
+
+        RenderSlot {
+            buffer,
+            bind_group: Rc::new(bind_group),
+        }
+    }
+
+    fn create_simulation_bind_group(
+        renderer: &Renderer,
+        layout: &wgpu::BindGroupLayout,
+        simulation_buffer: &wgpu::Buffer,
+        particle_buffer: &wgpu::Buffer,
+        name: &str,
+    ) -> wgpu::BindGroup {
+        renderer
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(&format!("{} Particle Simulation Bind Group", name)),
+                layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: simulation_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: particle_buffer.as_entire_binding(),
+                    },
+                ],
+            })
+    }
+
+    fn get_simulation_bind_group_layout(renderer: &Renderer) -> wgpu::BindGroupLayout {
+        renderer
+            .device
+            .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Particle Simulation Bind Group Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStage::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStage::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Storage { read_only: false },
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+            })
+    }
+
+    fn get_render_bind_group_layout(renderer: &Renderer) -> BindGroupLayout {
+        BindGroupLayout::new(
+            renderer,
+            "Particle Render Bind Group Layout",
+            &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStage::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        )
+    }
+}
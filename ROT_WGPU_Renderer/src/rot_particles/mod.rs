@@ -0,0 +1,6 @@
+mod curve;
+mod emitter;
+
+pub use crate::rot_animation::Lerp;
+pub use curve::Curve;
+pub use emitter::{EmitterSettings, ParticleBlend, ParticleEmitter};
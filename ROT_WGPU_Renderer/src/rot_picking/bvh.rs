@@ -0,0 +1,178 @@
+use crate::rot_picking::{Ray, RayHit};
+use crate::rot_primitives::{Aabb, Vertex};
+use nalgebra as na;
+
+//leaves hold at most this many triangles
+const MAX_LEAF_TRIANGLES: usize = 4;
+
+#[derive(Debug, Clone)]
+struct BvhNode {
+    aabb: Aabb,
+    //leaf: first triangle and count. Inner: children at first and first + 1, count is 0
+    first: usize,
+    count: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Triangle {
+    corners: [na::Point3<f32>; 3],
+    //in the mesh, indices[3 * index..]
+    index: usize,
+}
+
+//Bounding volume hierarchy over the triangles of a mesh, in its model space.
+//Only needs the vertices and indices, so it also works without a Renderer
+#[derive(Debug, Clone)]
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    triangles: Vec<Triangle>,
+}
+
+impl Bvh {
+    pub fn build(vertices: &[Vertex], indices: &[u32]) -> Self {
+        let triangles = indices
+            .chunks_exact(3)
+            .enumerate()
+            .map(|(index, triangle)| Triangle {
+                corners: [
+                    na::Point3::from(vertices[triangle[0] as usize].position),
+                    na::Point3::from(vertices[triangle[1] as usize].position),
+                    na::Point3::from(vertices[triangle[2] as usize].position),
+                ],
+                index,
+            })
+            .collect::<Vec<_>>();
+
+        let mut bvh = Self {
+            nodes: Vec::with_capacity(2 * triangles.len() / MAX_LEAF_TRIANGLES + 1),
+            triangles,
+        };
+        bvh.nodes.push(BvhNode {
+            aabb: Bvh::bounds(&bvh.triangles),
+            first: 0,
+            count: bvh.triangles.len(),
+        });
+        bvh.split(0);
+
+        bvh
+    }
+
+    pub fn aabb(&self) -> Aabb {
+        self.nodes[0].aabb
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.triangles.len()
+    }
+
+    //closest triangle, hit from either side
+    pub fn intersect(&self, ray: &Ray) -> Option<RayHit> {
+        if self.triangles.is_empty() {
+            return None;
+        }
+
+        let mut closest: Option<(f32, f32, f32, &Triangle)> = None;
+        let mut stack = vec![0];
+
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            let max_distance = closest.map_or(f32::INFINITY, |(distance, ..)| distance);
+            match ray.intersect_aabb(&node.aabb) {
+                Some(hit) if hit.distance <= max_distance => {}
+                _ => continue,
+            }
+
+            if node.count == 0 {
+                stack.push(node.first);
+                stack.push(node.first + 1);
+                continue;
+            }
+
+            for triangle in self.triangles[node.first..node.first + node.count].iter() {
+                let [a, b, c] = &triangle.corners;
+                if let Some((distance, u, v)) = ray.intersect_triangle(a, b, c) {
+                    if closest.map_or(true, |(closest, ..)| distance < closest) {
+                        closest = Some((distance, u, v, triangle));
+                    }
+                }
+            }
+        }
+
+        closest.map(|(distance, u, v, triangle)| {
+            let [a, b, c] = &triangle.corners;
+            let normal = (b - a).cross(&(c - a));
+            let normal = match normal.dot(&ray.direction) > 0.0 {
+                true => -normal,
+                false => normal,
+            };
+
+            RayHit {
+                distance,
+                point: ray.at(distance),
+                normal: normal.normalize(),
+                barycentric: na::Vector3::new(1.0 - u - v, u, v),
+                triangle: Some(triangle.index),
+            }
+        })
+    }
+
+    //median split along the longest axis of the centroids
+    fn split(&mut self, node: usize) {
+        let (first, count) = (self.nodes[node].first, self.nodes[node].count);
+        if count <= MAX_LEAF_TRIANGLES {
+            return;
+        }
+
+        let triangles = &mut self.triangles[first..first + count];
+        let centroids = triangles
+            .iter()
+            .map(Bvh::centroid)
+            .fold(None, |bounds: Option<Aabb>, centroid| match bounds {
+                None => Some(Aabb::new(centroid, centroid)),
+                Some(bounds) => Some(Aabb::new(
+                    bounds.min.inf(&centroid),
+                    bounds.max.sup(&centroid),
+                )),
+            })
+            .unwrap();
+        let extents = centroids.max - centroids.min;
+        let axis = extents.imax();
+
+        triangles.sort_by(|a, b| {
+            Bvh::centroid(a)[axis]
+                .partial_cmp(&Bvh::centroid(b)[axis])
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        let half = count / 2;
+        let children = self.nodes.len();
+        for (first, count) in [(first, half), (first + half, count - half)].iter() {
+            self.nodes.push(BvhNode {
+                aabb: Bvh::bounds(&self.triangles[*first..*first + *count]),
+                first: *first,
+                count: *count,
+            });
+        }
+        self.nodes[node].first = children;
+        self.nodes[node].count = 0;
+
+        self.split(children);
+        self.split(children + 1);
+    }
+
+    fn centroid(triangle: &Triangle) -> na::Point3<f32> {
+        let [a, b, c] = &triangle.corners;
+        na::Point3::from((a.coords + b.coords + c.coords) / 3.0)
+    }
+
+    fn bounds(triangles: &[Triangle]) -> Aabb {
+        triangles
+            .iter()
+            .flat_map(|triangle| triangle.corners.iter())
+            .fold(None, |bounds: Option<Aabb>, corner| match bounds {
+                None => Some(Aabb::new(*corner, *corner)),
+                Some(bounds) => Some(Aabb::new(bounds.min.inf(corner), bounds.max.sup(corner))),
+            })
+            .unwrap_or_else(|| Aabb::new(na::Point3::origin(), na::Point3::origin()))
+    }
+}
@@ -0,0 +1,7 @@
+mod bvh;
+mod picker;
+mod ray;
+
+pub use bvh::Bvh;
+pub use picker::{PickResult, Picker};
+pub use ray::{ObjectHit, Ray, RayHit};
@@ -0,0 +1,376 @@
+use crate::rot_pipeline::{BindGroupLayout, Pipeline, PipelineBuilder};
+use crate::rot_primitives::{Camera, Instance, InstanceId, Object, Texture, Vertex};
+use crate::rot_render_queue::DrawCommand;
+use crate::Renderer;
+use futures::FutureExt;
+use log::warn;
+use nalgebra as na;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PickResult {
+    //index into the objects given to Picker::pick
+    pub object: usize,
+    //into Object::instances, as they were when picked
+    pub instance_index: usize,
+    pub instance: InstanceId,
+    pub world_position: na::Point3<f32>,
+}
+
+//the ids base..base + instances.len() belong to one object
+struct PickRange {
+    base: u32,
+    object: usize,
+    instances: Vec<InstanceId>,
+}
+
+struct PendingPick {
+    ranges: Vec<PickRange>,
+    mapping: Pin<Box<dyn Future<Output = Result<(), wgpu::BufferAsyncError>>>>,
+}
+
+//where the id pixel lands in the readback buffer, the position one follows a row later
+const POSITION_OFFSET: wgpu::BufferAddress =
+    wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as wgpu::BufferAddress;
+
+//Draws object and instance ids into an R32Uint target, with the world position next to them,
+//and reads back the pixel under the cursor without stalling the frame:
+//  picker.pick(renderer, &camera, &[&units, &buildings], cursor);
+//  ...a frame or two later
+//  if let Some(hit) = picker.poll(renderer) { ... }
+pub struct Picker {
+    ids: Texture,
+    positions: Texture,
+    depth: Texture,
+    readback: wgpu::Buffer,
+
+    //one per object drawn, reused by the following picks
+    pick_uniforms: Vec<(wgpu::Buffer, Rc<wgpu::BindGroup>)>,
+    bind_group_layout: BindGroupLayout,
+
+    pending: Option<PendingPick>,
+}
+
+impl Picker {
+    pub fn new(renderer: &Renderer) -> Self {
+        let (ids, positions, depth) = Picker::create_targets(renderer);
+        let readback = renderer.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Picker Readback Buffer"),
+            size: POSITION_OFFSET * 2,
+            usage: wgpu::BufferUsage::MAP_READ | wgpu::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            ids,
+            positions,
+            depth,
+            readback,
+            pick_uniforms: Vec::new(),
+            bind_group_layout: Picker::get_bind_group_layout(renderer),
+            pending: None,
+        }
+    }
+
+    //cursor in window pixels, as Event::MouseMovement gives it.
+    //False while the previous pick is still on its way back, or outside the window
+    pub fn pick(
+        &mut self,
+        renderer: &Renderer,
+        camera: &Camera,
+        objects: &[&Object],
+        cursor: na::Point2<f64>,
+    ) -> bool {
+        if self.pending.is_some() {
+            return false;
+        }
+
+        let size = (
+            renderer.swapchain_descriptor.width,
+            renderer.swapchain_descriptor.height,
+        );
+        if self.ids.dimensions != size {
+            let (ids, positions, depth) = Picker::create_targets(renderer);
+            self.ids = ids;
+            self.positions = positions;
+            self.depth = depth;
+        }
+
+        if cursor.x < 0.0 || cursor.y < 0.0 {
+            return false;
+        }
+        let (x, y) = (cursor.x as u32, cursor.y as u32);
+        if x >= size.0 || y >= size.1 {
+            return false;
+        }
+
+        // IDS -------------------------------------------------------
+        while self.pick_uniforms.len() < objects.len() {
+            let uniform = self.create_pick_uniform(renderer);
+            self.pick_uniforms.push(uniform);
+        }
+
+        //0 is the background
+        let mut base = 1;
+        let mut ranges = Vec::with_capacity(objects.len());
+        for (index, object) in objects.iter().enumerate() {
+            renderer.queue.write_buffer(
+                &self.pick_uniforms[index].0,
+                0,
+                bytemuck::cast_slice(&[base, 0, 0, 0]),
+            );
+
+            ranges.push(PickRange {
+                base,
+                object: index,
+                instances: object.instance_ids().to_vec(),
+            });
+            base += object.instance_count() as u32;
+        }
+
+        //the id targets are never multisampled
+        let pipeline = Picker::pipeline(renderer, &self.bind_group_layout).for_target(
+            renderer,
+            renderer.swapchain_descriptor.format,
+            1,
+        );
+        let commands = objects
+            .iter()
+            .zip(self.pick_uniforms.iter())
+            .flat_map(|(object, (_, bind_group))| {
+                object.pick_commands(&pipeline, &camera.bind_group, bind_group)
+            })
+            .collect::<Vec<DrawCommand>>();
+
+        // PASS ------------------------------------------------------
+        let mut encoder = renderer
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Picking Encoder"),
+            });
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Picking pass"),
+                color_attachments: &[
+                    wgpu::RenderPassColorAttachmentDescriptor {
+                        attachment: &self.ids.view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                            store: true,
+                        },
+                    },
+                    wgpu::RenderPassColorAttachmentDescriptor {
+                        attachment: &self.positions.view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                            store: true,
+                        },
+                    },
+                ],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachmentDescriptor {
+                    attachment: &self.depth.view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(match renderer.reverse_z() {
+                            true => 0.0,
+                            false => 1.0,
+                        }),
+                        store: true,
+                    }),
+                    stencil_ops: None,
+                }),
+            });
+
+            //same projection as the camera view, but only the pixel under the cursor is shaded
+            let rect = camera.viewport.rect;
+            render_pass.set_viewport(
+                rect.x * size.0 as f32,
+                rect.y * size.1 as f32,
+                rect.width * size.0 as f32,
+                rect.height * size.1 as f32,
+                0.0,
+                1.0,
+            );
+            render_pass.set_scissor_rect(x, y, 1, 1);
+
+            for command in commands.iter() {
+                command.record(&mut render_pass);
+            }
+        }
+
+        for (texture, offset) in [(&self.ids, 0), (&self.positions, POSITION_OFFSET)].iter() {
+            encoder.copy_texture_to_buffer(
+                wgpu::TextureCopyView {
+                    texture: &texture.texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d { x, y, z: 0 },
+                },
+                wgpu::BufferCopyView {
+                    buffer: &self.readback,
+                    layout: wgpu::TextureDataLayout {
+                        offset: *offset,
+                        bytes_per_row: wgpu::COPY_BYTES_PER_ROW_ALIGNMENT,
+                        rows_per_image: 1,
+                    },
+                },
+                wgpu::Extent3d {
+                    width: 1,
+                    height: 1,
+                    depth: 1,
+                },
+            );
+        }
+
+        renderer.queue.submit(Some(encoder.finish()));
+
+        self.pending = Some(PendingPick {
+            ranges,
+            mapping: Box::pin(self.readback.slice(..).map_async(wgpu::MapMode::Read)),
+        });
+
+        true
+    }
+
+    //None while the pick is pending and when it hit nothing, is_pending tells them apart
+    pub fn poll(&mut self, renderer: &Renderer) -> Option<PickResult> {
+        renderer.device.poll(wgpu::Maintain::Poll);
+
+        let mut pending = self.pending.take()?;
+        match pending.mapping.as_mut().now_or_never() {
+            None => {
+                self.pending = Some(pending);
+                return None;
+            }
+            Some(Err(error)) => {
+                warn!("Dropping pick: {:?}", error);
+                return None;
+            }
+            Some(Ok(())) => {}
+        }
+
+        let (id, position) = {
+            let data = self.readback.slice(..).get_mapped_range();
+            let id = bytemuck::cast_slice::<u8, u32>(&data[0..4])[0];
+            let position = bytemuck::cast_slice::<u8, f32>(
+                &data[POSITION_OFFSET as usize..POSITION_OFFSET as usize + 12],
+            );
+
+            (id, na::Point3::new(position[0], position[1], position[2]))
+        };
+        self.readback.unmap();
+
+        pending
+            .ranges
+            .iter()
+            .find(|range| id >= range.base && id < range.base + range.instances.len() as u32)
+            .map(|range| {
+                let instance_index = (id - range.base) as usize;
+
+                PickResult {
+                    object: range.object,
+                    instance_index,
+                    instance: range.instances[instance_index],
+                    world_position: position,
+                }
+            })
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    fn pipeline(renderer: &Renderer, bind_group_layout: &BindGroupLayout) -> Pipeline {
+        let instance_layout = Instance::desc();
+        let pipeline_builder = PipelineBuilder {
+            name: "picking pipeline",
+            uniform_camera: true,
+            bind_group_layouts: vec![bind_group_layout],
+            vertex_shader_path: "shaders/pick.vert.spv",
+            fragment_shader_path: "shaders/pick.frag.spv",
+            vertex_buffer_layout: vec![Vertex::desc(), instance_layout.desc()],
+            ..PipelineBuilder::default()
+        }
+        .with_color_target(wgpu::ColorTargetState {
+            format: wgpu::TextureFormat::R32Uint,
+            alpha_blend: wgpu::BlendState::REPLACE,
+            color_blend: wgpu::BlendState::REPLACE,
+            write_mask: wgpu::ColorWrite::ALL,
+        })
+        .with_color_target(wgpu::ColorTargetState {
+            format: wgpu::TextureFormat::Rgba32Float,
+            alpha_blend: wgpu::BlendState::REPLACE,
+            color_blend: wgpu::BlendState::REPLACE,
+            write_mask: wgpu::ColorWrite::ALL,
+        });
+
+        Pipeline::new(renderer, &pipeline_builder)
+    }
+
+    fn create_targets(renderer: &Renderer) -> (Texture, Texture, Texture) {
+        let size = (
+            renderer.swapchain_descriptor.width,
+            renderer.swapchain_descriptor.height,
+        );
+        let usage = wgpu::TextureUsage::RENDER_ATTACHMENT | wgpu::TextureUsage::COPY_SRC;
+
+        (
+            Texture::new(
+                renderer,
+                size,
+                wgpu::TextureFormat::R32Uint,
+                usage,
+                "picking ids",
+            ),
+            Texture::new(
+                renderer,
+                size,
+                wgpu::TextureFormat::Rgba32Float,
+                usage,
+                "picking positions",
+            ),
+            Texture::depth_attachment(renderer, size, "picking depth"),
+        )
+    }
+
+    fn create_pick_uniform(&self, renderer: &Renderer) -> (wgpu::Buffer, Rc<wgpu::BindGroup>) {
+        let buffer = renderer.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Pick Uniform Buffer"),
+            size: std::mem::size_of::<[u32; 4]>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = renderer
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Pick Uniform Bind Group"),
+                layout: &self.bind_group_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                }],
+            });
+
+        (buffer, Rc::new(bind_group))
+    }
+
+    pub fn get_bind_group_layout(renderer: &Renderer) -> BindGroupLayout {
+        BindGroupLayout::new(
+            renderer,
+            "Pick Uniform Bind Group",
+            &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStage::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        )
+    }
+}
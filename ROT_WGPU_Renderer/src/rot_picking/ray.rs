@@ -0,0 +1,422 @@
+use crate::rot_primitives::{
+    Aabb, BoundingSphere, Camera, Instance, InstanceId, Mesh, Object, ViewportRect,
+};
+use nalgebra as na;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Ray {
+    pub origin: na::Point3<f32>,
+    //unit length from Ray::new, hit distances are measured in it
+    pub direction: na::Vector3<f32>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RayHit {
+    pub distance: f32,
+    pub point: na::Point3<f32>,
+    //facing the ray origin
+    pub normal: na::Vector3<f32>,
+    //weights of the triangle corners, zero for volume hits
+    pub barycentric: na::Vector3<f32>,
+    //index into the mesh triangles, indices[3 * triangle..]
+    pub triangle: Option<usize>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ObjectHit {
+    pub instance: InstanceId,
+    //into Object::instances
+    pub instance_index: usize,
+    //into Object::meshes
+    pub mesh: usize,
+    //in world space
+    pub hit: RayHit,
+}
+
+impl Ray {
+    pub fn new(origin: na::Point3<f32>, direction: na::Vector3<f32>) -> Self {
+        Self {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    //screen in window pixels from the top-left, like Event::MouseMovement.
+    //Works for orthographic cameras too, their rays start on the near plane
+    pub fn from_screen(
+        camera: &Camera,
+        screen: na::Point2<f32>,
+        screen_size: na::Vector2<f32>,
+    ) -> Self {
+        Ray::from_view_proj(
+            &camera.view_proj(),
+            &camera.viewport.rect,
+            camera.projection().is_reverse_z(),
+            screen,
+            screen_size,
+        )
+    }
+
+    //from_screen without the Camera, for the matrices of a CameraStatus
+    pub fn from_view_proj(
+        view_proj: &na::Matrix4<f32>,
+        rect: &ViewportRect,
+        reverse_z: bool,
+        screen: na::Point2<f32>,
+        screen_size: na::Vector2<f32>,
+    ) -> Self {
+        let local = na::Vector2::new(
+            (screen.x / screen_size.x - rect.x) / rect.width,
+            (screen.y / screen_size.y - rect.y) / rect.height,
+        );
+        let ndc = na::Vector2::new(local.x * 2.0 - 1.0, 1.0 - local.y * 2.0);
+
+        //depth 0.5 stays finite with an infinite far plane, where 0.0 is infinity
+        let near_depth = match reverse_z {
+            true => 1.0,
+            false => 0.0,
+        };
+        let inverse = view_proj
+            .try_inverse()
+            .unwrap_or_else(na::Matrix4::identity);
+        let unproject = |depth: f32| {
+            na::Point3::from_homogeneous(inverse * na::Vector4::new(ndc.x, ndc.y, depth, 1.0))
+                .unwrap_or_else(na::Point3::origin)
+        };
+
+        let near = unproject(near_depth);
+        Ray::new(near, unproject(0.5) - near)
+    }
+
+    pub fn at(&self, distance: f32) -> na::Point3<f32> {
+        self.origin + self.direction * distance
+    }
+
+    //into the model space of an instance. The direction keeps the scale, so distances
+    //found with it are still world distances
+    pub fn to_instance(&self, instance: &Instance) -> Ray {
+        let inverse = instance.isometry.inverse();
+
+        Ray {
+            origin: na::Point3::from(
+                (inverse * self.origin)
+                    .coords
+                    .component_div(&instance.scale),
+            ),
+            direction: (inverse * self.direction).component_div(&instance.scale),
+        }
+    }
+
+    //entry distance, 0.0 from inside
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<RayHit> {
+        let mut enter = (f32::NEG_INFINITY, 0);
+        let mut exit = (f32::INFINITY, 0);
+
+        for axis in 0..3 {
+            let inverse = 1.0 / self.direction[axis];
+            let mut near = (aabb.min[axis] - self.origin[axis]) * inverse;
+            let mut far = (aabb.max[axis] - self.origin[axis]) * inverse;
+            if near > far {
+                std::mem::swap(&mut near, &mut far);
+            }
+
+            //NaN from a parallel axis starting on a face is ignored by the comparisons
+            if near > enter.0 {
+                enter = (near, axis);
+            }
+            if far < exit.0 {
+                exit = (far, axis);
+            }
+        }
+
+        if enter.0 > exit.0 || exit.0 < 0.0 {
+            return None;
+        }
+
+        let (distance, axis) = match enter.0 >= 0.0 {
+            true => enter,
+            false => (0.0, exit.1),
+        };
+        let mut normal = na::Vector3::zeros();
+        normal[axis] = -self.direction[axis].signum();
+
+        Some(self.volume_hit(distance, normal))
+    }
+
+    pub fn intersect_sphere(&self, sphere: &BoundingSphere) -> Option<RayHit> {
+        let offset = self.origin - sphere.center;
+        let a = self.direction.magnitude_squared();
+        let b = offset.dot(&self.direction);
+        let c = offset.magnitude_squared() - sphere.radius * sphere.radius;
+
+        let discriminant = b * b - a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+
+        let root = discriminant.sqrt();
+        let distance = match ((-b - root) / a, (-b + root) / a) {
+            (near, _) if near >= 0.0 => near,
+            (_, far) if far >= 0.0 => 0.0,
+            _ => return None,
+        };
+
+        let normal = (self.at(distance) - sphere.center)
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(|| -self.direction.normalize());
+
+        Some(self.volume_hit(distance, normal))
+    }
+
+    //Moller-Trumbore, both sides. Returns the distance and the weights of b and c
+    pub fn intersect_triangle(
+        &self,
+        a: &na::Point3<f32>,
+        b: &na::Point3<f32>,
+        c: &na::Point3<f32>,
+    ) -> Option<(f32, f32, f32)> {
+        let edge_1 = b - a;
+        let edge_2 = c - a;
+        let p = self.direction.cross(&edge_2);
+        let determinant = edge_1.dot(&p);
+        if determinant.abs() < f32::EPSILON * edge_1.magnitude() * edge_2.magnitude() {
+            return None;
+        }
+
+        let inverse = 1.0 / determinant;
+        let offset = self.origin - a;
+        let u = offset.dot(&p) * inverse;
+        if u < 0.0 || u > 1.0 {
+            return None;
+        }
+
+        let q = offset.cross(&edge_1);
+        let v = self.direction.dot(&q) * inverse;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let distance = edge_2.dot(&q) * inverse;
+        match distance >= 0.0 {
+            true => Some((distance, u, v)),
+            false => None,
+        }
+    }
+
+    //in the mesh model space, through its BVH
+    pub fn intersect_mesh(&self, mesh: &Mesh) -> Option<RayHit> {
+        mesh.bvh().intersect(self)
+    }
+
+    //closest hit over every instance and mesh, in world space
+    pub fn intersect_object(&self, object: &Object) -> Option<ObjectHit> {
+        let mut closest: Option<ObjectHit> = None;
+
+        for (instance_index, (instance, id)) in object
+            .instances()
+            .iter()
+            .zip(object.instance_ids().iter())
+            .enumerate()
+        {
+            let max_distance = closest.map_or(f32::INFINITY, |closest| closest.hit.distance);
+            match self.intersect_sphere(&instance.transform_sphere(&object.bounding_sphere)) {
+                Some(bounds) if bounds.distance < max_distance => {}
+                _ => continue,
+            }
+
+            let local = self.to_instance(instance);
+            for (mesh_index, mesh) in object.meshes.iter().enumerate() {
+                let max_distance = closest.map_or(max_distance, |closest| closest.hit.distance);
+                let hit = match local.intersect_mesh(mesh) {
+                    Some(hit) if hit.distance < max_distance => hit,
+                    _ => continue,
+                };
+
+                //the inverse transpose of the scale keeps the normal perpendicular
+                let normal = instance.isometry.rotation * hit.normal.component_div(&instance.scale);
+                closest = Some(ObjectHit {
+                    instance: *id,
+                    instance_index,
+                    mesh: mesh_index,
+                    hit: RayHit {
+                        point: self.at(hit.distance),
+                        normal: normal.normalize(),
+                        ..hit
+                    },
+                });
+            }
+        }
+
+        closest
+    }
+
+    fn volume_hit(&self, distance: f32, normal: na::Vector3<f32>) -> RayHit {
+        RayHit {
+            distance,
+            point: self.at(distance),
+            normal,
+            barycentric: na::Vector3::zeros(),
+            triangle: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rot_picking::Bvh;
+    use crate::rot_primitives::{CameraStatus, Projection, Vertex};
+
+    fn vertex(position: [f32; 3]) -> Vertex {
+        Vertex {
+            position,
+            tex_coords: [0.0; 2],
+            normal: [0.0, 0.0, 1.0],
+        }
+    }
+
+    //two triangles covering x and y in -1..1, facing +z
+    fn quad(z: f32) -> (Vec<Vertex>, Vec<u32>) {
+        let vertices = vec![
+            vertex([-1.0, -1.0, z]),
+            vertex([1.0, -1.0, z]),
+            vertex([1.0, 1.0, z]),
+            vertex([-1.0, 1.0, z]),
+        ];
+
+        (vertices, vec![0, 1, 2, 0, 2, 3])
+    }
+
+    #[test]
+    fn hits_and_misses_a_box() {
+        let aabb = Aabb::new(
+            na::Point3::new(-1.0, -1.0, -1.0),
+            na::Point3::new(1.0, 1.0, 1.0),
+        );
+
+        let ray = Ray::new(na::Point3::new(0.0, 0.0, 5.0), -na::Vector3::z());
+        let hit = ray.intersect_aabb(&aabb).unwrap();
+        assert!((hit.distance - 4.0).abs() < 1e-5);
+        assert_eq!(hit.normal, na::Vector3::z());
+
+        let inside = Ray::new(na::Point3::origin(), na::Vector3::x());
+        assert!(inside.intersect_aabb(&aabb).unwrap().distance.abs() < 1e-6);
+
+        let beside = Ray::new(na::Point3::new(2.0, 0.0, 5.0), -na::Vector3::z());
+        assert!(beside.intersect_aabb(&aabb).is_none());
+        let away = Ray::new(na::Point3::new(0.0, 0.0, 5.0), na::Vector3::z());
+        assert!(away.intersect_aabb(&aabb).is_none());
+    }
+
+    #[test]
+    fn hits_and_misses_a_sphere() {
+        let sphere = BoundingSphere::new(na::Point3::new(0.0, 0.0, -10.0), 2.0);
+
+        let ray = Ray::new(na::Point3::origin(), -na::Vector3::z());
+        let hit = ray.intersect_sphere(&sphere).unwrap();
+        assert!((hit.distance - 8.0).abs() < 1e-5);
+        assert!((hit.normal - na::Vector3::z()).magnitude() < 1e-5);
+
+        let beside = Ray::new(na::Point3::new(3.0, 0.0, 0.0), -na::Vector3::z());
+        assert!(beside.intersect_sphere(&sphere).is_none());
+        let away = Ray::new(na::Point3::origin(), na::Vector3::z());
+        assert!(away.intersect_sphere(&sphere).is_none());
+    }
+
+    #[test]
+    fn hits_and_misses_a_mesh() {
+        let (vertices, indices) = quad(0.0);
+        let bvh = Bvh::build(&vertices, &indices);
+
+        let ray = Ray::new(na::Point3::new(0.5, 0.25, 3.0), -na::Vector3::z());
+        let hit = bvh.intersect(&ray).unwrap();
+        assert!((hit.distance - 3.0).abs() < 1e-5);
+        assert!((hit.point - na::Point3::new(0.5, 0.25, 0.0)).magnitude() < 1e-5);
+        assert!((hit.normal - na::Vector3::z()).magnitude() < 1e-5);
+        assert!(hit.triangle.is_some());
+
+        //from behind the normal still faces the ray
+        let below = Ray::new(na::Point3::new(0.5, 0.25, -3.0), na::Vector3::z());
+        assert!((bvh.intersect(&below).unwrap().normal + na::Vector3::z()).magnitude() < 1e-5);
+
+        let beside = Ray::new(na::Point3::new(1.5, 0.0, 3.0), -na::Vector3::z());
+        assert!(bvh.intersect(&beside).is_none());
+    }
+
+    #[test]
+    fn bvh_matches_brute_force() {
+        //a stack of quads, enough triangles for several levels
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        for layer in 0..16 {
+            let (quad_vertices, quad_indices) = quad(layer as f32 * 0.5);
+            let offset = vertices.len() as u32;
+            vertices.extend(quad_vertices.into_iter().map(|mut vertex| {
+                vertex.position[0] += layer as f32 * 0.3;
+                vertex
+            }));
+            indices.extend(quad_indices.iter().map(|index| index + offset));
+        }
+        let bvh = Bvh::build(&vertices, &indices);
+
+        //deterministic rays through the whole stack from all sides
+        let mut seed = 7u32;
+        let mut random = || {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (seed >> 8) as f32 / (1 << 24) as f32 * 2.0 - 1.0
+        };
+
+        for _ in 0..500 {
+            let origin = na::Point3::new(random() * 10.0, random() * 10.0, random() * 10.0);
+            let target = na::Point3::new(random() * 3.0 + 2.0, random(), random() * 4.0 + 4.0);
+            let ray = Ray::new(origin, target - origin);
+
+            let brute_force = indices
+                .chunks_exact(3)
+                .filter_map(|triangle| {
+                    let corner = |index: u32| na::Point3::from(vertices[index as usize].position);
+                    ray.intersect_triangle(
+                        &corner(triangle[0]),
+                        &corner(triangle[1]),
+                        &corner(triangle[2]),
+                    )
+                })
+                .map(|(distance, ..)| distance)
+                .fold(None, |closest: Option<f32>, distance| {
+                    Some(closest.map_or(distance, |closest| closest.min(distance)))
+                });
+
+            match (bvh.intersect(&ray), brute_force) {
+                (Some(hit), Some(distance)) => assert!((hit.distance - distance).abs() < 1e-4),
+                (None, None) => {}
+                (hit, distance) => panic!("bvh {:?}, brute force {:?}", hit, distance),
+            }
+        }
+    }
+
+    #[test]
+    fn screen_center_looks_at_the_target() {
+        let status = CameraStatus {
+            eye: na::Point3::new(1.0, 2.0, 5.0),
+            target: na::Point3::new(1.0, 0.0, -3.0),
+            up: na::Vector3::y(),
+            aspect: 16.0 / 9.0,
+            projection: Projection::perspective(std::f32::consts::FRAC_PI_4, 0.1, 100.0),
+        };
+        let view_proj = status.projection_matrix() * status.view();
+
+        let ray = Ray::from_view_proj(
+            &view_proj,
+            &ViewportRect::default(),
+            status.projection.is_reverse_z(),
+            na::Point2::new(640.0, 360.0),
+            na::Vector2::new(1280.0, 720.0),
+        );
+
+        let forward = (status.target - status.eye).normalize();
+        assert!((ray.direction - forward).magnitude() < 1e-4);
+        //starts on the near plane, on the line from the eye through the target
+        let offset = ray.origin - status.eye;
+        assert!(offset.cross(&forward).magnitude() < 1e-4);
+    }
+}
@@ -0,0 +1,33 @@
+use crate::Renderer;
+use std::ops::Deref;
+
+//A wgpu::BindGroupLayout that keeps its entries, for PipelineBuilder::bind_group_layouts.
+//Pipelines are cached by these entries, wgpu itself treats equal layouts as the same one
+pub struct BindGroupLayout {
+    pub layout: wgpu::BindGroupLayout,
+    pub entries: Vec<wgpu::BindGroupLayoutEntry>,
+}
+
+impl BindGroupLayout {
+    pub fn new(renderer: &Renderer, label: &str, entries: &[wgpu::BindGroupLayoutEntry]) -> Self {
+        let layout = renderer
+            .device
+            .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some(label),
+                entries,
+            });
+
+        Self {
+            layout,
+            entries: entries.to_vec(),
+        }
+    }
+}
+
+impl Deref for BindGroupLayout {
+    type Target = wgpu::BindGroupLayout;
+
+    fn deref(&self) -> &Self::Target {
+        &self.layout
+    }
+}
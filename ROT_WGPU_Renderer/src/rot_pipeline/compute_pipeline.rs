@@ -0,0 +1,71 @@
+use crate::Renderer;
+use std::rc::Rc;
+
+pub struct ComputePipeline {
+    pub compute_pipeline: Rc<wgpu::ComputePipeline>,
+}
+
+impl ComputePipeline {
+    pub fn new(renderer: &Renderer, compute_builder: &ComputePipelineBuilder) -> Self {
+        let cached = renderer
+            .pipeline_cache
+            .borrow()
+            .get_compute(compute_builder.shader_path);
+
+        let compute_pipeline = match cached {
+            Some(compute_pipeline) => compute_pipeline,
+            None => {
+                let module = ComputePipeline::load_shader_module(renderer, compute_builder);
+
+                let pipeline_layout =
+                    renderer
+                        .device
+                        .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                            label: Some(format!("{} {}", compute_builder.name, "layout").as_str()),
+                            bind_group_layouts: compute_builder.bind_group_layouts.as_slice(),
+                            push_constant_ranges: &[],
+                        });
+
+                let compute_pipeline =
+                    renderer
+                        .device
+                        .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                            label: Some(compute_builder.name),
+                            layout: Some(&pipeline_layout),
+                            module: &module,
+                            entry_point: "main",
+                        });
+
+                renderer
+                    .pipeline_cache
+                    .borrow_mut()
+                    .insert_compute(compute_builder.shader_path, compute_pipeline)
+            }
+        };
+
+        Self { compute_pipeline }
+    }
+
+    fn load_shader_module(
+        renderer: &Renderer,
+        compute_builder: &ComputePipelineBuilder,
+    ) -> wgpu::ShaderModule {
+        // COMPUTE ---------------------------------------------------
+        let shader_path = compute_builder.shader_path;
+        let shader_bytes = std::fs::read(shader_path).unwrap();
+        renderer
+            .device
+            .create_shader_module(&wgpu::ShaderModuleDescriptor {
+                label: Some(shader_path),
+                source: wgpu::util::make_spirv(&shader_bytes),
+                flags: wgpu::ShaderFlags::VALIDATION,
+            })
+    }
+}
+
+//compute pipelines are cached by shader, so a shader must always be used with the same layouts
+pub struct ComputePipelineBuilder<'a> {
+    pub name: &'a str,
+    pub shader_path: &'a str,
+    pub bind_group_layouts: Vec<&'a wgpu::BindGroupLayout>,
+}
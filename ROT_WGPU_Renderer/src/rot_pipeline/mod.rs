@@ -1,53 +1,92 @@
+mod bind_group_layout;
+mod compute_pipeline;
+mod pipeline_cache;
+
+pub use bind_group_layout::BindGroupLayout;
+pub use compute_pipeline::{ComputePipeline, ComputePipelineBuilder};
+pub use pipeline_cache::{PipelineCache, PipelineKey, VertexLayoutKey};
+
 use crate::rot_primitives::{Camera, Instance, Light, Material, Primitive, Vertex};
 use crate::Renderer;
+use std::rc::Rc;
 
+//Cheap to clone, every clone shares the same wgpu::RenderPipeline
+#[derive(Clone)]
 pub struct Pipeline {
-    pub render_pipeline_layout: wgpu::PipelineLayout,
-    pub render_pipeline: wgpu::RenderPipeline,
-    pub vertex_module: wgpu::ShaderModule,
-    pub fragment_module: wgpu::ShaderModule,
+    pub key: Rc<PipelineKey>,
+    pub render_pipeline: Rc<wgpu::RenderPipeline>,
+    //kept to build the same pipeline for other attachments, see for_target
+    layout: Rc<wgpu::PipelineLayout>,
 }
 
 impl Pipeline {
-    pub fn new(renderer: &Renderer, pipeline_builder: PipelineBuilder) -> Self {
-        let render_pipeline_layout = Pipeline::create_pipeline_layout(renderer, &pipeline_builder);
+    pub fn new(renderer: &Renderer, pipeline_builder: &PipelineBuilder) -> Self {
+        let key = PipelineKey::new(renderer, pipeline_builder);
 
-        let (vertex_module, fragment_module) =
-            Pipeline::load_vertex_shader_module(renderer, &pipeline_builder);
+        let cached = renderer.pipeline_cache.borrow().get(&key);
+        match cached {
+            Some(pipeline) => pipeline,
+            None => {
+                let layout = Pipeline::create_pipeline_layout(renderer, pipeline_builder);
 
-        let render_pipeline = Pipeline::create_pipeline(
-            renderer,
-            &render_pipeline_layout,
-            &vertex_module,
-            &fragment_module,
-            &pipeline_builder,
-        );
+                Pipeline::build(renderer, key, Rc::new(layout))
+            }
+        }
+    }
 
-        Self {
-            render_pipeline_layout,
-            render_pipeline,
-            vertex_module,
-            fragment_module,
+    //The same pipeline for an attachment of another format or sample count, built the first
+    //time it is asked for. Only pipelines drawing into the swapchain format change format
+    pub fn for_target(
+        &self,
+        renderer: &Renderer,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Pipeline {
+        if self.key.fits(format, sample_count) {
+            return self.clone();
+        }
+
+        let key = self.key.retargeted(format, sample_count);
+        let cached = renderer.pipeline_cache.borrow().get(&key);
+        match cached {
+            Some(pipeline) => pipeline,
+            None => Pipeline::build(renderer, key, Rc::clone(&self.layout)),
         }
     }
 
+    pub fn fits(&self, format: wgpu::TextureFormat, sample_count: u32) -> bool {
+        self.key.fits(format, sample_count)
+    }
+
+    fn build(renderer: &Renderer, key: PipelineKey, layout: Rc<wgpu::PipelineLayout>) -> Pipeline {
+        let (vertex_module, fragment_module) = Pipeline::load_vertex_shader_module(renderer, &key);
+
+        let render_pipeline =
+            Pipeline::create_pipeline(renderer, &layout, &vertex_module, &fragment_module, &key);
+
+        renderer
+            .pipeline_cache
+            .borrow_mut()
+            .insert(key, render_pipeline, layout)
+    }
+
     fn load_vertex_shader_module(
         renderer: &Renderer,
-        pipeline_builder: &PipelineBuilder,
+        key: &PipelineKey,
     ) -> (wgpu::ShaderModule, wgpu::ShaderModule) {
         // VERTEX ----------------------------------------------------
-        let vertex_path = pipeline_builder.vertex_shader_path;
-        let vert_bytes = std::fs::read(vertex_path.clone()).unwrap();
+        let vertex_path = key.vertex_shader_path.as_str();
+        let vert_bytes = std::fs::read(vertex_path).unwrap();
         let vertex_module = renderer
             .device
             .create_shader_module(&wgpu::ShaderModuleDescriptor {
-                label: Some(vertex_path.as_str()),
+                label: Some(vertex_path),
                 source: wgpu::util::make_spirv(&vert_bytes),
                 flags: wgpu::ShaderFlags::VALIDATION,
             });
 
-        let frag_path = pipeline_builder.fragment_shader_path;
-        let frag_bytes = std::fs::read(frag_path.clone()).unwrap();
+        let frag_path = key.fragment_shader_path.as_str();
+        let frag_bytes = std::fs::read(frag_path).unwrap();
         let frag_module = renderer
             .device
             .create_shader_module(&wgpu::ShaderModuleDescriptor {
@@ -63,29 +102,39 @@ impl Pipeline {
         renderer: &Renderer,
         pipeline_builder: &PipelineBuilder,
     ) -> wgpu::PipelineLayout {
-        let mut bind_gruop_layouts = Vec::new();
+        let mut bind_group_layouts = Vec::new();
 
         if pipeline_builder.uniform_material {
             let material_layout = Material::get_bind_group_layout(renderer);
-            bind_gruop_layouts.push(material_layout);
+            bind_group_layouts.push(material_layout);
         }
 
         if pipeline_builder.uniform_camera {
             let camera_layout = Camera::get_bind_group_layout(renderer);
-            bind_gruop_layouts.push(camera_layout);
+            bind_group_layouts.push(camera_layout);
         }
 
         if pipeline_builder.uniform_light {
             let light_layout = Light::get_bind_group_layout(renderer);
-            bind_gruop_layouts.push(light_layout);
+            bind_group_layouts.push(light_layout);
         }
 
+        let bind_group_layouts = bind_group_layouts
+            .iter()
+            .chain(
+                pipeline_builder
+                    .bind_group_layouts
+                    .iter()
+                    .map(|layout| &layout.layout),
+            )
+            .collect::<Vec<_>>();
+
         let pipeline_layout =
             renderer
                 .device
                 .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                     label: Some(format!("{} {}", pipeline_builder.name, "layout").as_str()),
-                    bind_group_layouts: &[bind_gruop_layouts.as_ref()],
+                    bind_group_layouts: bind_group_layouts.as_slice(),
                     push_constant_ranges: &[],
                 });
 
@@ -97,62 +146,253 @@ impl Pipeline {
         render_pipeline_layout: &wgpu::PipelineLayout,
         vertex_module: &wgpu::ShaderModule,
         fragment_module: &wgpu::ShaderModule,
-        pipeline_builder: &PipelineBuilder,
+        key: &PipelineKey,
     ) -> wgpu::RenderPipeline {
+        let vertex_buffer_layout = key
+            .vertex_layouts
+            .iter()
+            .map(|layout| wgpu::VertexBufferLayout {
+                array_stride: layout.array_stride,
+                step_mode: layout.step_mode,
+                attributes: layout.attributes.as_slice(),
+            })
+            .collect::<Vec<_>>();
+
         // CREATE -------------------------------------------------------
         renderer
             .device
             .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: Some(pipeline_builder.name),
+                //shared by every builder with the same key, whatever their names
+                label: Some(key.vertex_shader_path.as_str()),
                 layout: Some(render_pipeline_layout),
                 vertex: wgpu::VertexState {
                     module: vertex_module,
                     entry_point: "main",
-                    buffers: &pipeline_builder.vertex_buffer_layout.as_slice(),
+                    buffers: vertex_buffer_layout.as_slice(),
                 },
                 fragment: Some(wgpu::FragmentState {
                     module: fragment_module,
                     entry_point: "main",
-                    targets: &[wgpu::ColorTargetState {
-                        format: renderer.swapchain_descriptor.format,
-                        alpha_blend: wgpu::BlendState::REPLACE,
-                        color_blend: wgpu::BlendState::REPLACE,
-                        write_mask: wgpu::ColorWrite::ALL,
-                    }],
+                    targets: key.color_targets.as_slice(),
                 }),
                 primitive: wgpu::PrimitiveState {
-                    topology: wgpu::PrimitiveTopology::TriangleList,
-                    strip_index_format: None,
-                    front_face: wgpu::FrontFace::Ccw,
-                    cull_mode: wgpu::CullMode::Back,
-                    polygon_mode: wgpu::PolygonMode::Fill,
+                    topology: key.topology,
+                    strip_index_format: key.strip_index_format,
+                    front_face: key.front_face,
+                    cull_mode: key.cull_mode,
+                    polygon_mode: key.polygon_mode,
                 },
-                depth_stencil: Some(wgpu::DepthStencilState {
-                    format: wgpu::TextureFormat::Depth32Float,
-                    depth_write_enabled: true,
-                    depth_compare: wgpu::CompareFunction::Less,
-                    stencil: wgpu::StencilState::default(),
-                    bias: wgpu::DepthBiasState::default(),
+                depth_stencil: key.depth_format.map(|format| wgpu::DepthStencilState {
+                    format,
+                    depth_write_enabled: key.depth_write_enabled,
+                    depth_compare: key.depth_compare,
+                    stencil: key.stencil.clone(),
+                    bias: wgpu::DepthBiasState {
+                        constant: key.depth_bias.0,
+                        slope_scale: f32::from_bits(key.depth_bias.1),
+                        clamp: f32::from_bits(key.depth_bias.2),
+                    },
                     clamp_depth: false,
                 }),
                 multisample: wgpu::MultisampleState {
-                    count: 1,
+                    count: key.sample_count,
                     mask: !0,
-                    alpha_to_coverage_enabled: false,
+                    alpha_to_coverage_enabled: key.alpha_to_coverage_enabled,
                 },
             })
     }
 }
 
+#[derive(Clone)]
 pub struct PipelineBuilder<'a> {
     pub name: &'a str,
 
     pub uniform_material: bool,
     pub uniform_camera: bool,
     pub uniform_light: bool,
+    //bound after the uniforms above
+    pub bind_group_layouts: Vec<&'a BindGroupLayout>,
 
     pub vertex_shader_path: &'a str,
     pub fragment_shader_path: &'a str,
 
     pub vertex_buffer_layout: Vec<wgpu::VertexBufferLayout<'a>>,
+
+    // PRIMITIVE ----------------------------------------------------
+    pub topology: wgpu::PrimitiveTopology,
+    pub strip_index_format: Option<wgpu::IndexFormat>,
+    pub front_face: wgpu::FrontFace,
+    pub cull_mode: wgpu::CullMode,
+    pub polygon_mode: wgpu::PolygonMode,
+
+    // COLOR --------------------------------------------------------
+    //used for the swapchain target when color_targets is empty
+    pub color_blend: wgpu::BlendState,
+    pub alpha_blend: wgpu::BlendState,
+    pub write_mask: wgpu::ColorWrite,
+    //one entry per render target, in the same order as the render pass attachments
+    pub color_targets: Vec<wgpu::ColorTargetState>,
+
+    // DEPTH & STENCIL ----------------------------------------------
+    //None disables the depth/stencil state entirely
+    pub depth_format: Option<wgpu::TextureFormat>,
+    pub depth_test_enabled: bool,
+    pub depth_write_enabled: bool,
+    pub depth_compare: wgpu::CompareFunction,
+    pub stencil: wgpu::StencilState,
+    pub depth_bias: wgpu::DepthBiasState,
+
+    // MULTISAMPLE --------------------------------------------------
+    pub alpha_to_coverage_enabled: bool,
+}
+
+impl<'a> Default for PipelineBuilder<'a> {
+    fn default() -> Self {
+        Self {
+            name: "pipeline",
+
+            uniform_material: false,
+            uniform_camera: false,
+            uniform_light: false,
+            bind_group_layouts: Vec::new(),
+
+            vertex_shader_path: "",
+            fragment_shader_path: "",
+
+            vertex_buffer_layout: Vec::new(),
+
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: wgpu::CullMode::Back,
+            polygon_mode: wgpu::PolygonMode::Fill,
+
+            color_blend: wgpu::BlendState::REPLACE,
+            alpha_blend: wgpu::BlendState::REPLACE,
+            write_mask: wgpu::ColorWrite::ALL,
+            color_targets: Vec::new(),
+
+            depth_format: Some(wgpu::TextureFormat::Depth32Float),
+            depth_test_enabled: true,
+            depth_write_enabled: true,
+            depth_compare: wgpu::CompareFunction::Less,
+            stencil: wgpu::StencilState::default(),
+            depth_bias: wgpu::DepthBiasState::default(),
+
+            alpha_to_coverage_enabled: false,
+        }
+    }
+}
+
+impl<'a> PipelineBuilder<'a> {
+    pub fn with_topology(mut self, topology: wgpu::PrimitiveTopology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    pub fn with_cull_mode(mut self, cull_mode: wgpu::CullMode) -> Self {
+        self.cull_mode = cull_mode;
+        self
+    }
+
+    pub fn with_polygon_mode(mut self, polygon_mode: wgpu::PolygonMode) -> Self {
+        self.polygon_mode = polygon_mode;
+        self
+    }
+
+    pub fn with_blend(
+        mut self,
+        color_blend: wgpu::BlendState,
+        alpha_blend: wgpu::BlendState,
+    ) -> Self {
+        self.color_blend = color_blend;
+        self.alpha_blend = alpha_blend;
+        self
+    }
+
+    //the alpha channel composes over the destination, keeping it meaningful
+    pub fn with_alpha_blending(self) -> Self {
+        self.with_blend(blend::ALPHA, blend::PREMULTIPLIED_ALPHA)
+    }
+
+    pub fn with_additive_blending(self) -> Self {
+        self.with_blend(blend::ADDITIVE, blend::PREMULTIPLIED_ALPHA)
+    }
+
+    pub fn with_depth(mut self, test_enabled: bool, write_enabled: bool) -> Self {
+        self.depth_test_enabled = test_enabled;
+        self.depth_write_enabled = write_enabled;
+        self
+    }
+
+    pub fn without_depth(mut self) -> Self {
+        self.depth_format = None;
+        self
+    }
+
+    pub fn with_stencil(mut self, stencil: wgpu::StencilState) -> Self {
+        self.stencil = stencil;
+        self
+    }
+
+    pub fn with_depth_bias(mut self, constant: i32, slope_scale: f32, clamp: f32) -> Self {
+        self.depth_bias = wgpu::DepthBiasState {
+            constant,
+            slope_scale,
+            clamp,
+        };
+        self
+    }
+
+    pub fn with_color_target(mut self, target: wgpu::ColorTargetState) -> Self {
+        self.color_targets.push(target);
+        self
+    }
+
+    //what the pipeline will actually be built with
+    pub(crate) fn resolved_color_targets(
+        &self,
+        swapchain_format: wgpu::TextureFormat,
+    ) -> Vec<wgpu::ColorTargetState> {
+        if self.color_targets.is_empty() {
+            vec![wgpu::ColorTargetState {
+                format: swapchain_format,
+                alpha_blend: self.alpha_blend.clone(),
+                color_blend: self.color_blend.clone(),
+                write_mask: self.write_mask,
+            }]
+        } else {
+            self.color_targets.clone()
+        }
+    }
+
+    pub(crate) fn resolved_depth_compare(&self) -> wgpu::CompareFunction {
+        if self.depth_test_enabled {
+            self.depth_compare
+        } else {
+            wgpu::CompareFunction::Always
+        }
+    }
+}
+
+pub mod blend {
+    pub const REPLACE: wgpu::BlendState = wgpu::BlendState::REPLACE;
+
+    pub const ALPHA: wgpu::BlendState = wgpu::BlendState {
+        src_factor: wgpu::BlendFactor::SrcAlpha,
+        dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+        operation: wgpu::BlendOperation::Add,
+    };
+
+    pub const PREMULTIPLIED_ALPHA: wgpu::BlendState = wgpu::BlendState {
+        src_factor: wgpu::BlendFactor::One,
+        dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+        operation: wgpu::BlendOperation::Add,
+    };
+
+    pub const ADDITIVE: wgpu::BlendState = wgpu::BlendState {
+        src_factor: wgpu::BlendFactor::SrcAlpha,
+        dst_factor: wgpu::BlendFactor::One,
+        operation: wgpu::BlendOperation::Add,
+    };
 }
@@ -0,0 +1,231 @@
+use crate::rot_pipeline::{Pipeline, PipelineBuilder};
+use crate::rot_render_queue::ViewClearPipelines;
+use crate::Renderer;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub struct VertexLayoutKey {
+    pub array_stride: wgpu::BufferAddress,
+    pub step_mode: wgpu::InputStepMode,
+    pub attributes: Vec<wgpu::VertexAttribute>,
+}
+
+impl VertexLayoutKey {
+    fn from_layout(layout: &wgpu::VertexBufferLayout) -> Self {
+        Self {
+            array_stride: layout.array_stride,
+            step_mode: layout.step_mode,
+            attributes: layout.attributes.to_vec(),
+        }
+    }
+}
+
+//Everything that ends up inside the wgpu::RenderPipelineDescriptor
+//two builders with the same key can share the same wgpu::RenderPipeline
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub struct PipelineKey {
+    pub vertex_shader_path: String,
+    pub fragment_shader_path: String,
+
+    pub uniform_material: bool,
+    pub uniform_camera: bool,
+    pub uniform_light: bool,
+    //entries of PipelineBuilder::bind_group_layouts, in order
+    pub bind_group_layouts: Vec<Vec<wgpu::BindGroupLayoutEntry>>,
+
+    pub vertex_layouts: Vec<VertexLayoutKey>,
+
+    pub topology: wgpu::PrimitiveTopology,
+    pub strip_index_format: Option<wgpu::IndexFormat>,
+    pub front_face: wgpu::FrontFace,
+    pub cull_mode: wgpu::CullMode,
+    pub polygon_mode: wgpu::PolygonMode,
+
+    pub color_targets: Vec<wgpu::ColorTargetState>,
+    pub targets_swapchain: bool,
+
+    pub depth_format: Option<wgpu::TextureFormat>,
+    pub depth_write_enabled: bool,
+    pub depth_compare: wgpu::CompareFunction,
+    pub stencil: wgpu::StencilState,
+    //wgpu::DepthBiasState holds floats, so it is keyed by their bits
+    pub depth_bias: (i32, u32, u32),
+
+    pub sample_count: u32,
+    pub alpha_to_coverage_enabled: bool,
+}
+
+impl PipelineKey {
+    pub fn new(renderer: &Renderer, pipeline_builder: &PipelineBuilder) -> Self {
+        Self {
+            vertex_shader_path: pipeline_builder.vertex_shader_path.to_string(),
+            fragment_shader_path: pipeline_builder.fragment_shader_path.to_string(),
+
+            uniform_material: pipeline_builder.uniform_material,
+            uniform_camera: pipeline_builder.uniform_camera,
+            uniform_light: pipeline_builder.uniform_light,
+            bind_group_layouts: pipeline_builder
+                .bind_group_layouts
+                .iter()
+                .map(|layout| layout.entries.clone())
+                .collect(),
+
+            vertex_layouts: pipeline_builder
+                .vertex_buffer_layout
+                .iter()
+                .map(VertexLayoutKey::from_layout)
+                .collect(),
+
+            topology: pipeline_builder.topology,
+            strip_index_format: pipeline_builder.strip_index_format,
+            front_face: pipeline_builder.front_face,
+            cull_mode: pipeline_builder.cull_mode,
+            polygon_mode: pipeline_builder.polygon_mode,
+
+            color_targets: pipeline_builder
+                .resolved_color_targets(renderer.swapchain_descriptor.format),
+            targets_swapchain: pipeline_builder.color_targets.is_empty(),
+
+            depth_format: pipeline_builder.depth_format,
+            depth_write_enabled: pipeline_builder.depth_write_enabled,
+            depth_compare: match renderer.reverse_z() {
+                true => reversed(pipeline_builder.resolved_depth_compare()),
+                false => pipeline_builder.resolved_depth_compare(),
+            },
+            stencil: pipeline_builder.stencil.clone(),
+            depth_bias: (
+                pipeline_builder.depth_bias.constant,
+                pipeline_builder.depth_bias.slope_scale.to_bits(),
+                pipeline_builder.depth_bias.clamp.to_bits(),
+            ),
+
+            sample_count: renderer.sample_count,
+            alpha_to_coverage_enabled: pipeline_builder.alpha_to_coverage_enabled,
+        }
+    }
+
+    //whether it can draw into an attachment of this format and sample count.
+    //Pipelines drawing into custom targets don't care about the swapchain format
+    pub fn fits(&self, format: wgpu::TextureFormat, sample_count: u32) -> bool {
+        self.sample_count == sample_count
+            && (!self.targets_swapchain
+                || self
+                    .color_targets
+                    .iter()
+                    .all(|target| target.format == format))
+    }
+
+    pub fn retargeted(&self, format: wgpu::TextureFormat, sample_count: u32) -> Self {
+        let mut key = self.clone();
+        key.sample_count = sample_count;
+        if key.targets_swapchain {
+            for target in key.color_targets.iter_mut() {
+                target.format = format;
+            }
+        }
+
+        key
+    }
+}
+
+//the same test with depth running 1.0 near to 0.0 far
+fn reversed(compare: wgpu::CompareFunction) -> wgpu::CompareFunction {
+    match compare {
+        wgpu::CompareFunction::Less => wgpu::CompareFunction::Greater,
+        wgpu::CompareFunction::LessEqual => wgpu::CompareFunction::GreaterEqual,
+        wgpu::CompareFunction::Greater => wgpu::CompareFunction::Less,
+        wgpu::CompareFunction::GreaterEqual => wgpu::CompareFunction::LessEqual,
+        compare => compare,
+    }
+}
+
+#[derive(Default)]
+pub struct PipelineCache {
+    pipelines: HashMap<PipelineKey, Pipeline>,
+    //keyed by shader path, they don't depend on the swapchain
+    compute_pipelines: HashMap<String, Rc<wgpu::ComputePipeline>>,
+    //keyed by the format and sample count of the attachment they clear
+    clear_pipelines: HashMap<(wgpu::TextureFormat, u32), Rc<ViewClearPipelines>>,
+}
+
+impl PipelineCache {
+    pub fn new() -> Self {
+        Self {
+            pipelines: HashMap::new(),
+            compute_pipelines: HashMap::new(),
+            clear_pipelines: HashMap::new(),
+        }
+    }
+
+    pub fn get(&self, key: &PipelineKey) -> Option<Pipeline> {
+        self.pipelines.get(key).cloned()
+    }
+
+    pub fn insert(
+        &mut self,
+        key: PipelineKey,
+        render_pipeline: wgpu::RenderPipeline,
+        layout: Rc<wgpu::PipelineLayout>,
+    ) -> Pipeline {
+        let key = Rc::new(key);
+        let pipeline = Pipeline {
+            key: Rc::clone(&key),
+            render_pipeline: Rc::new(render_pipeline),
+            layout,
+        };
+        self.pipelines.insert((*key).clone(), pipeline.clone());
+
+        pipeline
+    }
+
+    pub fn get_compute(&self, shader_path: &str) -> Option<Rc<wgpu::ComputePipeline>> {
+        self.compute_pipelines.get(shader_path).cloned()
+    }
+
+    pub fn insert_compute(
+        &mut self,
+        shader_path: &str,
+        pipeline: wgpu::ComputePipeline,
+    ) -> Rc<wgpu::ComputePipeline> {
+        let pipeline = Rc::new(pipeline);
+        self.compute_pipelines
+            .insert(shader_path.to_string(), Rc::clone(&pipeline));
+
+        pipeline
+    }
+
+    pub fn get_clear(
+        &self,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Option<Rc<ViewClearPipelines>> {
+        self.clear_pipelines.get(&(format, sample_count)).cloned()
+    }
+
+    pub fn insert_clear(
+        &mut self,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        pipelines: ViewClearPipelines,
+    ) -> Rc<ViewClearPipelines> {
+        let pipelines = Rc::new(pipelines);
+        self.clear_pipelines
+            .insert((format, sample_count), Rc::clone(&pipelines));
+
+        pipelines
+    }
+
+    //Drops every pipeline built for another swapchain format or sample count. Whoever still
+    //holds one gets it rebuilt when it is drawn, see Pipeline::for_target
+    pub fn invalidate(&mut self, format: wgpu::TextureFormat, sample_count: u32) {
+        self.pipelines
+            .retain(|key, _| key.fits(format, sample_count));
+        //render targets stay single sampled, only the window attachment moves
+        self.clear_pipelines
+            .retain(|&(clear_format, clear_sample_count), _| {
+                clear_sample_count == 1
+                    || (clear_format, clear_sample_count) == (format, sample_count)
+            });
+    }
+}
@@ -0,0 +1,346 @@
+use crate::rot_primitives::Vertex;
+use nalgebra as na;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Aabb {
+    pub min: na::Point3<f32>,
+    pub max: na::Point3<f32>,
+}
+
+impl Aabb {
+    pub fn new(min: na::Point3<f32>, max: na::Point3<f32>) -> Self {
+        Self { min, max }
+    }
+
+    pub fn from_vertices(vertices: &[Vertex]) -> Self {
+        if vertices.is_empty() {
+            return Aabb::new(na::Point3::origin(), na::Point3::origin());
+        }
+
+        let mut min = na::Point3::from(vertices[0].position);
+        let mut max = min;
+        for vertex in vertices.iter() {
+            let position = na::Point3::from(vertex.position);
+            min = min.inf(&position);
+            max = max.sup(&position);
+        }
+
+        Aabb::new(min, max)
+    }
+
+    pub fn center(&self) -> na::Point3<f32> {
+        na::center(&self.min, &self.max)
+    }
+
+    pub fn half_extents(&self) -> na::Vector3<f32> {
+        (self.max - self.min) * 0.5
+    }
+
+    pub fn merged(&self, other: &Aabb) -> Aabb {
+        Aabb::new(self.min.inf(&other.min), self.max.sup(&other.max))
+    }
+
+    //still axis aligned, so it grows with the rotation
+    pub fn transformed(&self, isometry: &na::Isometry3<f32>) -> Aabb {
+        let center = isometry * self.center();
+        let rotation = isometry.rotation.to_rotation_matrix();
+        let abs_rotation = rotation.matrix().abs();
+        let half_extents = abs_rotation * self.half_extents();
+
+        Aabb::new(center - half_extents, center + half_extents)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BoundingSphere {
+    pub center: na::Point3<f32>,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    pub fn new(center: na::Point3<f32>, radius: f32) -> Self {
+        Self { center, radius }
+    }
+
+    //centered on the AABB, not the tightest sphere but cheap and stable
+    pub fn from_vertices(vertices: &[Vertex]) -> Self {
+        let center = Aabb::from_vertices(vertices).center();
+        let radius = vertices
+            .iter()
+            .map(|vertex| na::distance(&center, &na::Point3::from(vertex.position)))
+            .fold(0.0, f32::max);
+
+        BoundingSphere::new(center, radius)
+    }
+
+    pub fn merged(&self, other: &BoundingSphere) -> BoundingSphere {
+        let offset = other.center - self.center;
+        let distance = offset.magnitude();
+
+        if distance + other.radius <= self.radius {
+            return *self;
+        }
+        if distance + self.radius <= other.radius {
+            return *other;
+        }
+
+        let radius = (distance + self.radius + other.radius) * 0.5;
+        let center = self.center + offset * ((radius - self.radius) / distance);
+
+        BoundingSphere::new(center, radius)
+    }
+
+    pub fn transformed(&self, isometry: &na::Isometry3<f32>) -> BoundingSphere {
+        BoundingSphere::new(isometry * self.center, self.radius)
+    }
+}
+
+//n.dot(p) + d >= 0 for every point on the inner side
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Plane {
+    pub normal: na::Vector3<f32>,
+    pub d: f32,
+}
+
+impl Plane {
+    fn from_row(row: na::RowVector4<f32>) -> Self {
+        let normal = na::Vector3::new(row[0], row[1], row[2]);
+        let magnitude = normal.magnitude();
+
+        //the far plane of an infinite projection, everything is in front of it
+        if magnitude <= f32::EPSILON {
+            return Self {
+                normal: na::Vector3::zeros(),
+                d: 1.0,
+            };
+        }
+
+        Self {
+            normal: normal / magnitude,
+            d: row[3] / magnitude,
+        }
+    }
+
+    pub fn distance(&self, point: &na::Point3<f32>) -> f32 {
+        self.normal.dot(&point.coords) + self.d
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Frustum {
+    //left, right, bottom, top, near, far
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    //Gribb-Hartmann extraction from an OpenGL style (-1..1 depth) view projection
+    pub fn from_view_proj(view_proj: &na::Matrix4<f32>) -> Self {
+        let row = |index: usize| view_proj.row(index).into_owned();
+
+        Self {
+            planes: [
+                Plane::from_row(row(3) + row(0)),
+                Plane::from_row(row(3) - row(0)),
+                Plane::from_row(row(3) + row(1)),
+                Plane::from_row(row(3) - row(1)),
+                Plane::from_row(row(3) + row(2)),
+                Plane::from_row(row(3) - row(2)),
+            ],
+        }
+    }
+
+    //same from a wgpu style (0..1 depth) one, what Camera::view_proj gives.
+    //With reverse Z the near and far planes swap places
+    pub fn from_wgpu_view_proj(view_proj: &na::Matrix4<f32>) -> Self {
+        let row = |index: usize| view_proj.row(index).into_owned();
+
+        Self {
+            planes: [
+                Plane::from_row(row(3) + row(0)),
+                Plane::from_row(row(3) - row(0)),
+                Plane::from_row(row(3) + row(1)),
+                Plane::from_row(row(3) - row(1)),
+                Plane::from_row(row(2)),
+                Plane::from_row(row(3) - row(2)),
+            ],
+        }
+    }
+
+    pub fn contains_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.distance(&sphere.center) >= -sphere.radius)
+    }
+
+    pub fn contains_aabb(&self, aabb: &Aabb) -> bool {
+        let center = aabb.center();
+        let half_extents = aabb.half_extents();
+
+        self.planes.iter().all(|plane| {
+            let radius = half_extents.dot(&plane.normal.abs());
+            plane.distance(&center) >= -radius
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rot_primitives::{Projection, OPENGL_TO_WGPU_MATRIX};
+
+    fn vertex(position: [f32; 3]) -> Vertex {
+        Vertex {
+            position,
+            tex_coords: [0.0; 2],
+            normal: [0.0, 0.0, 1.0],
+        }
+    }
+
+    fn unit_box() -> Aabb {
+        Aabb::new(
+            na::Point3::new(-1.0, -1.0, -1.0),
+            na::Point3::new(1.0, 1.0, 1.0),
+        )
+    }
+
+    //looking down -z from the origin, 90 degrees each way
+    fn perspective() -> na::Matrix4<f32> {
+        na::Perspective3::new(1.0, std::f32::consts::FRAC_PI_2, 1.0, 100.0).to_homogeneous()
+    }
+
+    fn planes_match(a: &Frustum, b: &Frustum) -> bool {
+        a.planes
+            .iter()
+            .zip(b.planes.iter())
+            .all(|(a, b)| (a.normal - b.normal).magnitude() < 1e-4 && (a.d - b.d).abs() < 1e-3)
+    }
+
+    #[test]
+    fn aabb_wraps_the_vertices() {
+        let vertices = [
+            vertex([1.0, -2.0, 0.5]),
+            vertex([-3.0, 4.0, 0.0]),
+            vertex([0.0, 0.0, -1.0]),
+        ];
+        let aabb = Aabb::from_vertices(&vertices);
+
+        assert_eq!(aabb.min, na::Point3::new(-3.0, -2.0, -1.0));
+        assert_eq!(aabb.max, na::Point3::new(1.0, 4.0, 0.5));
+        assert_eq!(aabb.center(), na::Point3::new(-1.0, 1.0, -0.25));
+        assert_eq!(
+            Aabb::from_vertices(&[]).half_extents(),
+            na::Vector3::zeros()
+        );
+    }
+
+    #[test]
+    fn rotated_aabb_grows_to_fit() {
+        let aabb = Aabb::new(
+            na::Point3::new(-2.0, -1.0, -1.0),
+            na::Point3::new(2.0, 1.0, 1.0),
+        );
+
+        let quarter_turn = na::Isometry3::new(
+            na::Vector3::new(5.0, 0.0, 0.0),
+            na::Vector3::z() * std::f32::consts::FRAC_PI_2,
+        );
+        let turned = aabb.transformed(&quarter_turn);
+        assert!((turned.center() - na::Point3::new(5.0, 0.0, 0.0)).magnitude() < 1e-5);
+        assert!((turned.half_extents() - na::Vector3::new(1.0, 2.0, 1.0)).magnitude() < 1e-5);
+
+        let eighth_turn = na::Isometry3::rotation(na::Vector3::z() * std::f32::consts::FRAC_PI_4);
+        let half_extents = unit_box().transformed(&eighth_turn).half_extents();
+        assert!((half_extents.x - std::f32::consts::SQRT_2).abs() < 1e-5);
+        assert!((half_extents.z - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn merged_spheres_enclose_both() {
+        let big = BoundingSphere::new(na::Point3::origin(), 5.0);
+        let inside = BoundingSphere::new(na::Point3::new(1.0, 0.0, 0.0), 1.0);
+        assert_eq!(big.merged(&inside), big);
+        assert_eq!(inside.merged(&big), big);
+
+        let left = BoundingSphere::new(na::Point3::new(-2.0, 0.0, 0.0), 1.0);
+        let right = BoundingSphere::new(na::Point3::new(4.0, 0.0, 0.0), 1.0);
+        let merged = left.merged(&right);
+        assert!((merged.center - na::Point3::new(1.0, 0.0, 0.0)).magnitude() < 1e-5);
+        assert!((merged.radius - 4.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn gribb_hartmann_planes_of_an_orthographic_box() {
+        let ortho = na::Orthographic3::new(-1.0, 3.0, -2.0, 2.0, 1.0, 10.0).to_homogeneous();
+        let frustum = Frustum::from_view_proj(&ortho);
+
+        //inward normals, the box spans x -1..3, y -2..2 and z -1..-10
+        let expected = [
+            (na::Vector3::x(), 1.0),
+            (-na::Vector3::x(), 3.0),
+            (na::Vector3::y(), 2.0),
+            (-na::Vector3::y(), 2.0),
+            (-na::Vector3::z(), -1.0),
+            (na::Vector3::z(), 10.0),
+        ];
+        for (plane, (normal, d)) in frustum.planes.iter().zip(expected.iter()) {
+            assert!((plane.normal - normal).magnitude() < 1e-5, "{:?}", plane);
+            assert!((plane.d - d).abs() < 1e-4, "{:?}", plane);
+        }
+    }
+
+    #[test]
+    fn wgpu_depth_range_gives_the_same_planes() {
+        let opengl = Frustum::from_view_proj(&perspective());
+        let wgpu = Frustum::from_wgpu_view_proj(&(OPENGL_TO_WGPU_MATRIX * perspective()));
+
+        assert!(planes_match(&opengl, &wgpu));
+    }
+
+    #[test]
+    fn spheres_are_culled_outside_any_plane() {
+        let frustum = Frustum::from_view_proj(&perspective());
+        let sphere = |x: f32, z: f32, radius: f32| {
+            frustum.contains_sphere(&BoundingSphere::new(na::Point3::new(x, 0.0, z), radius))
+        };
+
+        assert!(sphere(0.0, -10.0, 1.0));
+        //behind the camera, in front of the near plane and past the far one
+        assert!(!sphere(0.0, 10.0, 1.0));
+        assert!(!sphere(0.0, -0.2, 0.5));
+        assert!(!sphere(0.0, -150.0, 10.0));
+        //the side planes run along x = -z, touching counts as visible
+        assert!(!sphere(13.0, -10.0, 1.0));
+        assert!(sphere(10.5, -10.0, 1.0));
+    }
+
+    #[test]
+    fn boxes_straddling_a_plane_are_kept() {
+        let frustum = Frustum::from_view_proj(&perspective());
+        let at = |x: f32, z: f32| {
+            let offset = na::Vector3::new(x, 0.0, z);
+            let aabb = unit_box();
+            Aabb::new(aabb.min + offset, aabb.max + offset)
+        };
+
+        assert!(frustum.contains_aabb(&at(0.0, -10.0)));
+        assert!(frustum.contains_aabb(&at(10.5, -10.0)));
+        assert!(frustum.contains_aabb(&at(0.0, -100.5)));
+        assert!(!frustum.contains_aabb(&at(13.0, -10.0)));
+        assert!(!frustum.contains_aabb(&at(0.0, 5.0)));
+    }
+
+    #[test]
+    fn infinite_projection_never_culls_by_distance() {
+        let infinite = Projection::InfinitePerspective {
+            fovy: std::f32::consts::FRAC_PI_2,
+            znear: 1.0,
+        }
+        .matrix(1.0);
+        let frustum = Frustum::from_wgpu_view_proj(&infinite);
+
+        let far = BoundingSphere::new(na::Point3::new(0.0, 0.0, -1.0e6), 1.0);
+        assert!(frustum.contains_sphere(&far));
+        let near = BoundingSphere::new(na::Point3::new(0.0, 0.0, -0.2), 0.5);
+        assert!(!frustum.contains_sphere(&near));
+    }
+}
@@ -1,27 +1,36 @@
-use crate::rot_primitives::Primitive;
+use crate::rot_primitives::{
+    CameraController, Frustum, KeyboardController, Primitive, Projection, RenderTarget, Viewport,
+};
 use crate::Renderer;
 use bytemuck::{Pod, Zeroable};
 use nalgebra as na;
+use std::rc::Rc;
 use wgpu::util::DeviceExt;
 use wgpu::{BindGroup, BindGroupLayout};
 
+//na::Matrix4::new takes rows: z' = 0.5 * z + 0.5 * w, from -1..1 to 0..1 depth
 #[rustfmt::skip]
 pub const OPENGL_TO_WGPU_MATRIX: na::Matrix4<f32> = na::Matrix4::new(
     1.0, 0.0, 0.0, 0.0,
     0.0, 1.0, 0.0, 0.0,
-    0.0, 0.0, 0.5, 0.0,
-    0.0, 0.0, 0.5, 1.0,
+    0.0, 0.0, 0.5, 0.5,
+    0.0, 0.0, 0.0, 1.0,
 );
 
 pub struct Camera {
     camera_status: CameraStatus,
 
-    controller: CameraController,
+    controller: Box<dyn CameraController>,
+
+    //where it draws, the whole window by default
+    pub viewport: Viewport,
+    //None draws into the window
+    render_target: Option<Rc<RenderTarget>>,
 
     pub uniform: CameraUniform,
 
     pub buffer: wgpu::Buffer,
-    pub bind_group: wgpu::BindGroup,
+    pub bind_group: Rc<wgpu::BindGroup>,
     pub bind_group_layout: wgpu::BindGroupLayout,
 }
 
@@ -42,13 +51,11 @@ impl Camera {
             target,
             up,
             aspect,
-            fovy,
-            znear,
-            zfar,
+            projection: Projection::perspective(fovy, znear, zfar),
         };
         let uniform = CameraUniform::new(&camera_status);
 
-        let controller = CameraController::new(speed);
+        let controller = Box::new(KeyboardController::new(speed));
 
         let buffer = renderer
             .device
@@ -65,21 +72,140 @@ impl Camera {
         Self {
             camera_status,
             controller,
+            viewport: Viewport::default(),
+            render_target: None,
             uniform,
             buffer,
-            bind_group,
+            bind_group: Rc::new(bind_group),
             bind_group_layout,
         }
     }
 
+    pub fn eye(&self) -> na::Point3<f32> {
+        self.camera_status.eye
+    }
+
+    pub fn target(&self) -> na::Point3<f32> {
+        self.camera_status.target
+    }
+
+    //orthographic and custom projections report the default 45 degrees
+    pub fn fovy(&self) -> f32 {
+        self.camera_status
+            .projection
+            .fovy()
+            .unwrap_or(std::f32::consts::FRAC_PI_4)
+    }
+
+    pub fn projection(&self) -> Projection {
+        self.camera_status.projection
+    }
+
+    //picked up on the next on_update, like the rest of the setters
+    pub fn set_eye(&mut self, eye: na::Point3<f32>) {
+        self.camera_status.eye = eye;
+    }
+
+    pub fn set_target(&mut self, target: na::Point3<f32>) {
+        self.camera_status.target = target;
+    }
+
+    //only perspective projections have one
+    pub fn set_fovy(&mut self, fovy: f32) {
+        match &mut self.camera_status.projection {
+            Projection::Perspective { fovy: current, .. }
+            | Projection::InfinitePerspective { fovy: current, .. } => *current = fovy,
+            _ => {}
+        }
+    }
+
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.camera_status.projection = projection;
+    }
+
+    pub fn with_projection(mut self, projection: Projection) -> Self {
+        self.set_projection(projection);
+        self
+    }
+
+    pub fn view(&self) -> na::Matrix4<f32> {
+        self.camera_status.view()
+    }
+
+    pub fn projection_matrix(&self) -> na::Matrix4<f32> {
+        self.camera_status.projection_matrix()
+    }
+
+    pub fn view_proj(&self) -> na::Matrix4<f32> {
+        self.projection_matrix() * self.view()
+    }
+
+    //world space right and up of the view, for quads that always face the camera
+    pub fn billboard_axes(&self) -> (na::Vector3<f32>, na::Vector3<f32>) {
+        let status = &self.camera_status;
+        let forward = (status.target - status.eye).normalize();
+        let right = forward.cross(&status.up).normalize();
+
+        (right, right.cross(&forward))
+    }
+
+    pub fn frustum(&self) -> Frustum {
+        Frustum::from_wgpu_view_proj(&self.view_proj())
+    }
+
+    pub fn with_viewport(mut self, viewport: Viewport) -> Self {
+        self.viewport = viewport;
+        self
+    }
+
+    pub fn render_target(&self) -> Option<&Rc<RenderTarget>> {
+        self.render_target.as_ref()
+    }
+
+    //the viewport rect is then relative to the target
+    pub fn set_render_target(&mut self, render_target: Option<Rc<RenderTarget>>) {
+        self.render_target = render_target;
+    }
+
+    pub fn with_render_target(mut self, render_target: Rc<RenderTarget>) -> Self {
+        self.set_render_target(Some(render_target));
+        self
+    }
+
+    //replaces the KeyboardController the camera starts with
+    pub fn set_controller<C: CameraController + 'static>(&mut self, controller: C) {
+        self.controller = Box::new(controller);
+    }
+
+    pub fn with_controller<C: CameraController + 'static>(mut self, controller: C) -> Self {
+        self.set_controller(controller);
+        self
+    }
+
+    pub fn controller_mut(&mut self) -> &mut dyn CameraController {
+        self.controller.as_mut()
+    }
+
+    //Renderer::set_camera hands it to the engine, which grabs the window cursor
+    pub fn cursor_grab(&self) -> bool {
+        self.controller.cursor_grab()
+    }
+
     pub fn on_event(&mut self, event: &rot_events::event::Event) {
         self.controller.on_event(event);
     }
 
-    pub fn on_update(&mut self, renderer: &Renderer) {
-        self.camera_status.aspect = (renderer.swapchain_descriptor.width as f32
-            / renderer.swapchain_descriptor.height as f32);
-        self.controller.on_update(&mut self.camera_status);
+    pub fn on_update(&mut self, renderer: &Renderer, delta_time: f32) {
+        let (width, height) = match self.render_target.as_ref() {
+            Some(render_target) => render_target.dimensions(),
+            None => (
+                renderer.swapchain_descriptor.width,
+                renderer.swapchain_descriptor.height,
+            ),
+        };
+        self.camera_status.aspect = self.viewport.rect.aspect(width, height);
+        self.controller
+            .on_update(&mut self.camera_status, delta_time);
         self.uniform.update(&self.camera_status);
         renderer
             .queue
@@ -110,7 +236,7 @@ impl Camera {
                 label: Some("Camera Uniform Bind Group"),
                 entries: &[wgpu::BindGroupLayoutEntry {
                     binding: 0,
-                    visibility: wgpu::ShaderStage::VERTEX,
+                    visibility: wgpu::ShaderStage::VERTEX | wgpu::ShaderStage::FRAGMENT,
                     ty: wgpu::BindingType::Buffer {
                         ty: wgpu::BufferBindingType::Uniform,
                         has_dynamic_offset: false,
@@ -128,15 +254,32 @@ pub struct CameraStatus {
     pub up: na::Vector3<f32>,
 
     pub aspect: f32,
-    pub fovy: f32,
-    pub znear: f32,
-    pub zfar: f32,
+    pub projection: Projection,
+}
+
+impl CameraStatus {
+    pub fn view(&self) -> na::Matrix4<f32> {
+        na::Isometry3::look_at_rh(&self.eye, &self.target, &self.up).to_homogeneous()
+    }
+
+    pub fn projection_matrix(&self) -> na::Matrix4<f32> {
+        self.projection.matrix(self.aspect)
+    }
 }
 
+//std140 layout of the camera block, shaders may declare only the leading members they use:
+//  uniform Uniforms { mat4 u_view_proj; };
 #[repr(C)]
 #[derive(Default, Debug, Copy, Clone)]
 pub struct CameraUniform {
     pub view_proj: [[f32; 4]; 4],
+    pub view: [[f32; 4]; 4],
+    pub proj: [[f32; 4]; 4],
+    pub inv_view_proj: [[f32; 4]; 4],
+    pub inv_view: [[f32; 4]; 4],
+    pub inv_proj: [[f32; 4]; 4],
+    //world space, w is 1.0
+    pub position: [f32; 4],
 }
 
 unsafe impl Zeroable for CameraUniform {}
@@ -144,115 +287,37 @@ unsafe impl Pod for CameraUniform {}
 
 impl CameraUniform {
     pub fn new(status: &CameraStatus) -> Self {
-        let view = na::Isometry3::look_at_rh(&status.eye, &status.target, &status.up);
-        let proj = na::Perspective3::new(status.aspect, status.fovy, status.znear, status.zfar);
+        let mut uniform = CameraUniform::default();
+        uniform.update(status);
 
-        let matrix = OPENGL_TO_WGPU_MATRIX * proj.to_homogeneous() * view.to_homogeneous();
-
-        Self {
-            view_proj: matrix.into(),
-        }
+        uniform
     }
 
     pub fn update(&mut self, camera: &CameraStatus) {
-        let view = na::Isometry3::look_at_rh(&camera.eye, &camera.target, &camera.up);
-        let proj = na::Perspective3::new(camera.aspect, camera.fovy, camera.znear, camera.zfar);
-
-        let matrix = proj.to_homogeneous() * view.to_homogeneous();
-
-        self.view_proj = matrix.into()
-    }
-}
-
-pub struct CameraController {
-    speed: f32,
-    is_up_pressed: bool,
-    is_down_pressed: bool,
-    is_forward_pressed: bool,
-    is_backward_pressed: bool,
-    is_left_pressed: bool,
-    is_right_pressed: bool,
-}
-
-impl CameraController {
-    fn new(speed: f32) -> Self {
-        Self {
-            speed,
-            is_up_pressed: false,
-            is_down_pressed: false,
-            is_forward_pressed: false,
-            is_backward_pressed: false,
-            is_left_pressed: false,
-            is_right_pressed: false,
-        }
-    }
-
-    fn on_event(&mut self, event: &rot_events::event::Event) {
-        use rot_events::event::{Event, State};
-        use rot_events::KeyboardInput::KeyCode;
-
-        match event {
-            Event::MouseButton(_) => {}
-            Event::MouseWheel(_) => {}
-            Event::MouseMovement(_) => {}
-            Event::KeyboardInput(ev) => match ev.state {
-                State::Pressed => match ev.virtual_keycode {
-                    None => {}
-                    Some(keycode) => match keycode {
-                        KeyCode::LShift => self.is_up_pressed = true,
-                        KeyCode::LAlt => self.is_down_pressed = true,
-                        KeyCode::W => self.is_forward_pressed = true,
-                        KeyCode::S => self.is_backward_pressed = true,
-                        KeyCode::A => self.is_left_pressed = true,
-                        KeyCode::D => self.is_right_pressed = true,
-                        _ => {}
-                    },
-                },
-                State::Released => match ev.virtual_keycode {
-                    None => {}
-                    Some(keycode) => match keycode {
-                        KeyCode::LShift => self.is_up_pressed = false,
-                        KeyCode::LAlt => self.is_down_pressed = false,
-                        KeyCode::W => self.is_forward_pressed = false,
-                        KeyCode::S => self.is_backward_pressed = false,
-                        KeyCode::A => self.is_left_pressed = false,
-                        KeyCode::D => self.is_right_pressed = false,
-                        _ => {}
-                    },
-                },
-            },
-        }
+        self.set_matrices(&camera.view(), &camera.projection_matrix(), &camera.eye);
     }
 
-    pub fn on_update(&self, camera: &mut CameraStatus) {
-        let forward = camera.target - camera.eye;
-        let forward_mag = forward.magnitude();
-
-        if self.is_forward_pressed && forward_mag > self.speed {
-            camera.eye += forward.normalize() * self.speed
-        }
-        if self.is_backward_pressed {
-            camera.eye -= forward.normalize() * self.speed;
-        }
-
-        if self.is_right_pressed {
-            // Rescale the distance between the target and eye so
-            // that it doesn't change. The eye therefore still
-            // lies on the circle made by the target and eye.
-            let right = forward.normalize().cross(&camera.up);
-            camera.eye = camera.target - (forward + right * self.speed).normalize() * forward_mag;
-        }
-        if self.is_left_pressed {
-            let right = forward.normalize().cross(&camera.up);
-            camera.eye = camera.target - (forward - right * self.speed).normalize() * forward_mag;
-        }
-
-        if self.is_down_pressed {
-            camera.eye -= camera.up * self.speed;
-        }
+    //a singular projection, like a degenerate custom one, leaves zeroed inverses
+    pub fn set_matrices(
+        &mut self,
+        view: &na::Matrix4<f32>,
+        proj: &na::Matrix4<f32>,
+        position: &na::Point3<f32>,
+    ) {
+        let view_proj = proj * view;
+        let inverse = |matrix: &na::Matrix4<f32>| -> [[f32; 4]; 4] {
+            matrix
+                .try_inverse()
+                .unwrap_or_else(na::Matrix4::zeros)
+                .into()
+        };
 
-        if self.is_up_pressed {
-            camera.eye += camera.up * self.speed;
-        }
+        self.view_proj = view_proj.into();
+        self.view = (*view).into();
+        self.proj = (*proj).into();
+        self.inv_view_proj = inverse(&view_proj);
+        self.inv_view = inverse(view);
+        self.inv_proj = inverse(proj);
+        self.position = position.to_homogeneous().into();
     }
 }
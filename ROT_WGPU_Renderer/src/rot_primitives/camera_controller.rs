@@ -0,0 +1,578 @@
+use crate::rot_primitives::CameraStatus;
+use nalgebra as na;
+use rot_events::event::{Event, State};
+use rot_events::KeyboardInput::KeyCode;
+use rot_events::MouseInput::Button;
+
+//Moves a Camera from input. Events arrive as they happen, on_update applies them once per frame
+pub trait CameraController {
+    fn on_event(&mut self, event: &Event);
+
+    fn on_update(&mut self, camera: &mut CameraStatus, delta_time: f32);
+
+    //hide the cursor and keep it in the window, for mouse look
+    fn cursor_grab(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct KeyBindings {
+    pub forward: KeyCode,
+    pub backward: KeyCode,
+    pub left: KeyCode,
+    pub right: KeyCode,
+    pub up: KeyCode,
+    pub down: KeyCode,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            forward: KeyCode::W,
+            backward: KeyCode::S,
+            left: KeyCode::A,
+            right: KeyCode::D,
+            up: KeyCode::LShift,
+            down: KeyCode::LAlt,
+        }
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone)]
+struct MovementKeys {
+    forward: bool,
+    backward: bool,
+    left: bool,
+    right: bool,
+    up: bool,
+    down: bool,
+}
+
+impl MovementKeys {
+    fn on_event(&mut self, bindings: &KeyBindings, event: &Event) {
+        let (keycode, pressed) = match event {
+            Event::KeyboardInput(ev) => match ev.virtual_keycode {
+                Some(keycode) => (keycode, ev.state == State::Pressed),
+                None => return,
+            },
+            _ => return,
+        };
+
+        if keycode == bindings.forward {
+            self.forward = pressed;
+        }
+        if keycode == bindings.backward {
+            self.backward = pressed;
+        }
+        if keycode == bindings.left {
+            self.left = pressed;
+        }
+        if keycode == bindings.right {
+            self.right = pressed;
+        }
+        if keycode == bindings.up {
+            self.up = pressed;
+        }
+        if keycode == bindings.down {
+            self.down = pressed;
+        }
+    }
+
+    //x right, y up, z forward
+    fn axis(&self) -> na::Vector3<f32> {
+        let axis = |positive: bool, negative: bool| positive as i32 as f32 - negative as i32 as f32;
+
+        na::Vector3::new(
+            axis(self.right, self.left),
+            axis(self.up, self.down),
+            axis(self.forward, self.backward),
+        )
+    }
+}
+
+//Buttons held, plus the motion and wheel gathered since the last update
+#[derive(Debug, Default, Copy, Clone)]
+struct MouseState {
+    left: bool,
+    right: bool,
+    middle: bool,
+    motion: [f32; 2],
+    wheel: f32,
+}
+
+impl MouseState {
+    fn on_event(&mut self, event: &Event) {
+        match event {
+            Event::MouseButton(ev) => {
+                let pressed = ev.state == State::Pressed;
+                match ev.button {
+                    Button::Left => self.left = pressed,
+                    Button::Right => self.right = pressed,
+                    Button::Middle => self.middle = pressed,
+                    Button::Other(_) => {}
+                }
+            }
+            Event::MouseMotion(ev) => {
+                self.motion[0] += ev.delta.x as f32;
+                self.motion[1] += ev.delta.y as f32;
+            }
+            Event::MouseWheel(ev) => self.wheel += ev.line_delta.y as f32,
+            _ => {}
+        }
+    }
+
+    fn is_held(&self, button: Button) -> bool {
+        match button {
+            Button::Left => self.left,
+            Button::Right => self.right,
+            Button::Middle => self.middle,
+            Button::Other(_) => false,
+        }
+    }
+
+    //motion and wheel since the last call
+    fn take(&mut self) -> ([f32; 2], f32) {
+        let taken = (self.motion, self.wheel);
+        self.motion = [0.0; 2];
+        self.wheel = 0.0;
+
+        taken
+    }
+}
+
+//yaw around +Y and pitch above the horizon of a direction
+fn yaw_pitch(direction: &na::Vector3<f32>) -> (f32, f32) {
+    let direction = direction.normalize();
+
+    (
+        direction.x.atan2(direction.z),
+        direction.y.max(-1.0).min(1.0).asin(),
+    )
+}
+
+fn from_yaw_pitch(yaw: f32, pitch: f32) -> na::Vector3<f32> {
+    na::Vector3::new(
+        yaw.sin() * pitch.cos(),
+        pitch.sin(),
+        yaw.cos() * pitch.cos(),
+    )
+}
+
+//turns the view around the eye by a mouse motion, keeping the target distance
+fn look(camera: &mut CameraStatus, motion: [f32; 2], sensitivity: f32, max_pitch: f32) {
+    let offset = camera.target - camera.eye;
+    let (yaw, pitch) = yaw_pitch(&offset);
+
+    let yaw = yaw - motion[0] * sensitivity;
+    let pitch = (pitch - motion[1] * sensitivity)
+        .max(-max_pitch)
+        .min(max_pitch);
+
+    camera.target = camera.eye + from_yaw_pitch(yaw, pitch) * offset.magnitude();
+}
+
+//Walks with the keys and orbits the target sideways, what Camera::new starts with
+pub struct KeyboardController {
+    //units per second
+    pub speed: f32,
+    pub bindings: KeyBindings,
+
+    keys: MovementKeys,
+}
+
+impl KeyboardController {
+    pub fn new(speed: f32) -> Self {
+        Self {
+            speed,
+            bindings: KeyBindings::default(),
+            keys: MovementKeys::default(),
+        }
+    }
+}
+
+impl CameraController for KeyboardController {
+    fn on_event(&mut self, event: &Event) {
+        self.keys.on_event(&self.bindings, event);
+    }
+
+    fn on_update(&mut self, camera: &mut CameraStatus, delta_time: f32) {
+        let step = self.speed * delta_time;
+        let axis = self.keys.axis();
+
+        let forward = camera.target - camera.eye;
+        let forward_mag = forward.magnitude();
+
+        //stops short of the target
+        if axis.z > 0.0 && forward_mag > step {
+            camera.eye += forward.normalize() * step;
+        }
+        if axis.z < 0.0 {
+            camera.eye -= forward.normalize() * step;
+        }
+
+        //circles the target at the same distance
+        if axis.x != 0.0 {
+            let right = forward.normalize().cross(&camera.up);
+            camera.eye =
+                camera.target - (forward + right * step * axis.x).normalize() * forward_mag;
+        }
+
+        camera.eye += camera.up * step * axis.y;
+    }
+}
+
+//Arcball around the target: drag to rotate, wheel to zoom, middle drag to pan. Assumes Y up
+pub struct OrbitController {
+    //radians per pixel
+    pub rotate_sensitivity: f32,
+    //fraction of the distance per pixel
+    pub pan_sensitivity: f32,
+    //fraction of the distance per wheel line
+    pub zoom_sensitivity: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    pub rotate_button: Button,
+    pub pan_button: Button,
+
+    mouse: MouseState,
+}
+
+impl Default for OrbitController {
+    fn default() -> Self {
+        Self {
+            rotate_sensitivity: 0.005,
+            pan_sensitivity: 0.001,
+            zoom_sensitivity: 0.1,
+            min_distance: 0.1,
+            max_distance: 1000.0,
+            rotate_button: Button::Left,
+            pan_button: Button::Middle,
+            mouse: MouseState::default(),
+        }
+    }
+}
+
+impl OrbitController {
+    pub fn new() -> Self {
+        OrbitController::default()
+    }
+}
+
+impl CameraController for OrbitController {
+    fn on_event(&mut self, event: &Event) {
+        self.mouse.on_event(event);
+    }
+
+    fn on_update(&mut self, camera: &mut CameraStatus, _delta_time: f32) {
+        let (motion, wheel) = self.mouse.take();
+
+        let offset = camera.eye - camera.target;
+        let (mut yaw, mut pitch) = yaw_pitch(&offset);
+        let mut distance = offset.magnitude();
+
+        if self.mouse.is_held(self.rotate_button) {
+            //stays just short of the poles, where the view flips
+            let max_pitch = std::f32::consts::FRAC_PI_2 - 0.01;
+            yaw -= motion[0] * self.rotate_sensitivity;
+            pitch = (pitch + motion[1] * self.rotate_sensitivity)
+                .max(-max_pitch)
+                .min(max_pitch);
+        }
+
+        if self.mouse.is_held(self.pan_button) {
+            let forward = -offset.normalize();
+            let right = forward.cross(&camera.up).normalize();
+            let up = right.cross(&forward);
+
+            camera.target += (up * motion[1] - right * motion[0]) * self.pan_sensitivity * distance;
+        }
+
+        distance = (distance * (1.0 - wheel * self.zoom_sensitivity))
+            .max(self.min_distance)
+            .min(self.max_distance);
+
+        camera.eye = camera.target + from_yaw_pitch(yaw, pitch) * distance;
+    }
+}
+
+//Free flight along the view, with mouse look while look_button is held
+pub struct FlyController {
+    //units per second
+    pub speed: f32,
+    //radians per pixel
+    pub look_sensitivity: f32,
+    pub bindings: KeyBindings,
+    //None looks all the time, with the cursor grabbed
+    pub look_button: Option<Button>,
+
+    keys: MovementKeys,
+    mouse: MouseState,
+}
+
+impl FlyController {
+    pub fn new(speed: f32) -> Self {
+        Self {
+            speed,
+            look_sensitivity: 0.002,
+            bindings: KeyBindings::default(),
+            look_button: Some(Button::Right),
+            keys: MovementKeys::default(),
+            mouse: MouseState::default(),
+        }
+    }
+
+    fn is_looking(&self) -> bool {
+        self.look_button
+            .map_or(true, |button| self.mouse.is_held(button))
+    }
+}
+
+impl CameraController for FlyController {
+    fn on_event(&mut self, event: &Event) {
+        self.keys.on_event(&self.bindings, event);
+        self.mouse.on_event(event);
+    }
+
+    fn on_update(&mut self, camera: &mut CameraStatus, delta_time: f32) {
+        let (motion, _) = self.mouse.take();
+        if self.is_looking() {
+            let max_pitch = std::f32::consts::FRAC_PI_2 - 0.01;
+            look(camera, motion, self.look_sensitivity, max_pitch);
+        }
+
+        let forward = (camera.target - camera.eye).normalize();
+        let right = forward.cross(&camera.up).normalize();
+        let axis = self.keys.axis();
+
+        let movement = (right * axis.x + camera.up * axis.y + forward * axis.z)
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(na::Vector3::zeros)
+            * self.speed
+            * delta_time;
+        camera.eye += movement;
+        camera.target += movement;
+    }
+
+    fn cursor_grab(&self) -> bool {
+        self.is_looking()
+    }
+}
+
+//Walks on the ground plane with a clamped pitch. The cursor stays grabbed
+//until release_key, a left click grabs it again
+pub struct FirstPersonController {
+    //units per second
+    pub speed: f32,
+    //radians per pixel
+    pub look_sensitivity: f32,
+    //radians above and below the horizon
+    pub max_pitch: f32,
+    pub bindings: KeyBindings,
+    pub release_key: KeyCode,
+
+    captured: bool,
+    keys: MovementKeys,
+    mouse: MouseState,
+}
+
+impl FirstPersonController {
+    pub fn new(speed: f32) -> Self {
+        Self {
+            speed,
+            look_sensitivity: 0.002,
+            max_pitch: 85.0f32.to_radians(),
+            bindings: KeyBindings::default(),
+            release_key: KeyCode::Esc,
+            captured: true,
+            keys: MovementKeys::default(),
+            mouse: MouseState::default(),
+        }
+    }
+}
+
+impl CameraController for FirstPersonController {
+    fn on_event(&mut self, event: &Event) {
+        match event {
+            Event::KeyboardInput(ev)
+                if ev.state == State::Pressed && ev.virtual_keycode == Some(self.release_key) =>
+            {
+                self.captured = false
+            }
+            Event::MouseButton(ev) if ev.state == State::Pressed && ev.button == Button::Left => {
+                self.captured = true
+            }
+            _ => {}
+        }
+
+        self.keys.on_event(&self.bindings, event);
+        self.mouse.on_event(event);
+    }
+
+    fn on_update(&mut self, camera: &mut CameraStatus, delta_time: f32) {
+        let (motion, _) = self.mouse.take();
+        if self.captured {
+            look(camera, motion, self.look_sensitivity, self.max_pitch);
+        }
+
+        //no flying, the view direction is flattened onto the ground
+        let (yaw, _) = yaw_pitch(&(camera.target - camera.eye));
+        let forward = from_yaw_pitch(yaw, 0.0);
+        let right = forward.cross(&na::Vector3::y());
+        let axis = self.keys.axis();
+
+        let movement = (right * axis.x + forward * axis.z)
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(na::Vector3::zeros)
+            * self.speed
+            * delta_time;
+        camera.eye += movement;
+        camera.target += movement;
+    }
+
+    fn cursor_grab(&self) -> bool {
+        self.captured
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rot_primitives::Projection;
+    use rot_events::KeyboardInput::KeyboardInputEvent;
+    use rot_events::MouseInput::{Coord, MouseButton, MouseMotion, MouseWheel};
+
+    fn status() -> CameraStatus {
+        CameraStatus {
+            eye: na::Point3::new(0.0, 0.0, 5.0),
+            target: na::Point3::origin(),
+            up: na::Vector3::y(),
+            aspect: 1.0,
+            projection: Projection::perspective(std::f32::consts::FRAC_PI_4, 0.1, 100.0),
+        }
+    }
+
+    fn key(keycode: KeyCode, state: State) -> Event {
+        Event::KeyboardInput(KeyboardInputEvent {
+            state,
+            scancode: 0,
+            virtual_keycode: Some(keycode),
+        })
+    }
+
+    fn button(button: Button, state: State) -> Event {
+        Event::MouseButton(MouseButton { state, button })
+    }
+
+    fn motion(x: f64, y: f64) -> Event {
+        Event::MouseMotion(MouseMotion {
+            delta: Coord { x, y },
+        })
+    }
+
+    fn wheel(lines: f64) -> Event {
+        Event::MouseWheel(MouseWheel {
+            line_delta: Coord { x: 0.0, y: lines },
+        })
+    }
+
+    fn close(a: &na::Point3<f32>, b: &na::Point3<f32>) -> bool {
+        na::distance(a, b) < 1e-4
+    }
+
+    #[test]
+    fn keyboard_walks_towards_the_target_and_stops_short() {
+        let mut controller = KeyboardController::new(2.0);
+        let mut camera = status();
+
+        controller.on_event(&key(KeyCode::W, State::Pressed));
+        controller.on_update(&mut camera, 0.5);
+        assert!(close(&camera.eye, &na::Point3::new(0.0, 0.0, 4.0)));
+
+        controller.on_update(&mut camera, 10.0);
+        assert!(close(&camera.eye, &na::Point3::new(0.0, 0.0, 4.0)));
+
+        controller.on_event(&key(KeyCode::W, State::Released));
+        controller.on_event(&key(KeyCode::LShift, State::Pressed));
+        controller.on_update(&mut camera, 0.5);
+        assert!(close(&camera.eye, &na::Point3::new(0.0, 1.0, 4.0)));
+    }
+
+    #[test]
+    fn orbit_rotates_only_while_dragging_and_keeps_the_distance() {
+        let mut controller = OrbitController::new();
+        let mut camera = status();
+
+        controller.on_event(&motion(100.0, 0.0));
+        controller.on_update(&mut camera, 0.016);
+        assert!(close(&camera.eye, &na::Point3::new(0.0, 0.0, 5.0)));
+
+        controller.on_event(&button(Button::Left, State::Pressed));
+        controller.on_event(&motion(100.0, 50.0));
+        controller.on_update(&mut camera, 0.016);
+        assert!(!close(&camera.eye, &na::Point3::new(0.0, 0.0, 5.0)));
+        assert!((na::distance(&camera.eye, &camera.target) - 5.0).abs() < 1e-4);
+        assert!(close(&camera.target, &na::Point3::origin()));
+    }
+
+    #[test]
+    fn orbit_zooms_within_its_limits_and_pans_both_ends() {
+        let mut controller = OrbitController::new();
+        let mut camera = status();
+
+        controller.on_event(&wheel(5.0));
+        controller.on_update(&mut camera, 0.016);
+        assert!((na::distance(&camera.eye, &camera.target) - 2.5).abs() < 1e-4);
+
+        controller.on_event(&wheel(100.0));
+        controller.on_update(&mut camera, 0.016);
+        assert!((na::distance(&camera.eye, &camera.target) - 0.1).abs() < 1e-4);
+
+        let mut camera = status();
+        controller.on_event(&button(Button::Middle, State::Pressed));
+        controller.on_event(&motion(-200.0, 0.0));
+        controller.on_update(&mut camera, 0.016);
+        //dragging left moves the view to the right, along +x when looking down -z
+        assert!(close(&camera.target, &na::Point3::new(1.0, 0.0, 0.0)));
+        assert!(close(&camera.eye, &na::Point3::new(1.0, 0.0, 5.0)));
+    }
+
+    #[test]
+    fn fly_moves_eye_and_target_together() {
+        let mut controller = FlyController::new(4.0);
+        let mut camera = status();
+
+        controller.on_event(&key(KeyCode::W, State::Pressed));
+        controller.on_event(&key(KeyCode::D, State::Pressed));
+        controller.on_update(&mut camera, 0.5);
+
+        let step = 2.0 / std::f32::consts::SQRT_2;
+        assert!(close(&camera.eye, &na::Point3::new(step, 0.0, 5.0 - step)));
+        assert!(close(&camera.target, &na::Point3::new(step, 0.0, -step)));
+        assert!(!controller.cursor_grab());
+
+        controller.on_event(&button(Button::Right, State::Pressed));
+        assert!(controller.cursor_grab());
+    }
+
+    #[test]
+    fn first_person_clamps_pitch_and_stays_on_the_ground() {
+        let mut controller = FirstPersonController::new(1.0);
+        let mut camera = status();
+
+        //far past straight down
+        controller.on_event(&motion(0.0, 10_000.0));
+        controller.on_update(&mut camera, 0.0);
+        let (_, pitch) = yaw_pitch(&(camera.target - camera.eye));
+        assert!((pitch + controller.max_pitch).abs() < 1e-4);
+
+        controller.on_event(&key(KeyCode::W, State::Pressed));
+        controller.on_update(&mut camera, 1.0);
+        assert!(close(&camera.eye, &na::Point3::new(0.0, 0.0, 4.0)));
+
+        controller.on_event(&key(KeyCode::Esc, State::Pressed));
+        assert!(!controller.cursor_grab());
+        controller.on_event(&button(Button::Left, State::Pressed));
+        assert!(controller.cursor_grab());
+    }
+}
@@ -10,6 +10,7 @@ impl DepthBufferTexture {
     pub(crate) fn new(
         device: &wgpu::Device,
         sc_desc: &wgpu::SwapChainDescriptor,
+        sample_count: u32,
         name: &str,
     ) -> Self {
         let depth_format = wgpu::TextureFormat::Depth32Float;
@@ -24,10 +25,13 @@ impl DepthBufferTexture {
             label: Some(name),
             size,
             mip_level_count: 1,
-            sample_count: 1,
+            sample_count,
             dimension: wgpu::TextureDimension::D2,
             format: depth_format,
-            usage: wgpu::TextureUsage::RENDER_ATTACHMENT | wgpu::TextureUsage::SAMPLED,
+            usage: match sample_count {
+                1 => wgpu::TextureUsage::RENDER_ATTACHMENT | wgpu::TextureUsage::SAMPLED,
+                _ => wgpu::TextureUsage::RENDER_ATTACHMENT,
+            },
         });
 
         let view = depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
@@ -0,0 +1,333 @@
+use crate::rot_pipeline::{ComputePipeline, ComputePipelineBuilder};
+use crate::rot_primitives::{BoundingSphere, Frustum, Instance, InstanceUniform};
+use crate::rot_render_queue::{ComputeCommand, PerView};
+use crate::Renderer;
+use std::ops::Range;
+use std::rc::Rc;
+use wgpu::util::DeviceExt;
+
+const WORKGROUP_SIZE: u32 = 64;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct CullingUniform {
+    planes: [[f32; 4]; 6],
+    //model space, every instance transforms it by its own model matrix
+    sphere: [f32; 4],
+    instance_count: u32,
+    draw_count: u32,
+    _padding: [u32; 2],
+}
+
+unsafe impl bytemuck::Pod for CullingUniform {}
+unsafe impl bytemuck::Zeroable for CullingUniform {}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct DrawIndexedIndirect {
+    index_count: u32,
+    instance_count: u32,
+    first_index: u32,
+    base_vertex: i32,
+    first_instance: u32,
+}
+
+unsafe impl bytemuck::Pod for DrawIndexedIndirect {}
+unsafe impl bytemuck::Zeroable for DrawIndexedIndirect {}
+
+//Frustum culls every instance of an Object in a compute pass and feeds
+//draw_indexed_indirect with the survivors, no per instance work on the CPU
+pub struct GpuCuller {
+    name: String,
+    capacity: usize,
+    instance_count: u32,
+    //index count of each mesh, one indirect draw per entry
+    index_counts: Vec<u32>,
+
+    instances_buffer: wgpu::Buffer,
+    //every camera culls the same instances into its own
+    views: PerView<CulledView>,
+
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline: ComputePipeline,
+}
+
+//What the instances culled for one view are drawn with
+pub struct CulledView {
+    uniform_buffer: wgpu::Buffer,
+    pub visible_buffer: Rc<wgpu::Buffer>,
+    pub indirect_buffer: Rc<wgpu::Buffer>,
+    bind_group: Rc<wgpu::BindGroup>,
+}
+
+impl GpuCuller {
+    pub fn new(renderer: &Renderer, name: &str, index_counts: Vec<u32>, capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+
+        let instances_buffer = GpuCuller::create_instances_buffer(renderer, name, capacity);
+
+        let bind_group_layout = GpuCuller::get_bind_group_layout(renderer);
+        let pipeline = ComputePipeline::new(
+            renderer,
+            &ComputePipelineBuilder {
+                name: "cull instances pipeline",
+                shader_path: "shaders/cull_instances.comp.spv",
+                bind_group_layouts: vec![&bind_group_layout],
+            },
+        );
+
+        Self {
+            name: name.to_string(),
+            capacity,
+            instance_count: 0,
+            index_counts,
+            instances_buffer,
+            views: PerView::new(),
+            bind_group_layout,
+            pipeline,
+        }
+    }
+
+    //only needed when the instances change, not every frame.
+    //dirty are the ranges to rewrite, everything is rewritten if the buffers had to grow
+    pub fn upload_instances(
+        &mut self,
+        renderer: &Renderer,
+        instances: &[Instance],
+        dirty: &[Range<usize>],
+    ) {
+        let full = [0..instances.len()];
+        let dirty = if instances.len() > self.capacity {
+            self.grow(renderer, instances.len());
+            &full[..]
+        } else {
+            dirty
+        };
+
+        let instance_size = std::mem::size_of::<InstanceUniform>();
+
+        for range in dirty.iter() {
+            let range = range.start.min(instances.len())..range.end.min(instances.len());
+            if range.start == range.end {
+                continue;
+            }
+
+            let uniforms = instances[range.clone()]
+                .iter()
+                .map(|instance| instance.uniform)
+                .collect::<Vec<_>>();
+
+            renderer.queue.write_buffer(
+                &self.instances_buffer,
+                (range.start * instance_size) as wgpu::BufferAddress,
+                bytemuck::cast_slice(&uniforms),
+            );
+        }
+
+        self.instance_count = instances.len() as u32;
+    }
+
+    //resets the indirect draws of the current view and hands back its dispatch and what to draw
+    pub fn prepare(
+        &self,
+        renderer: &Renderer,
+        frustum: &Frustum,
+        bounding_sphere: &BoundingSphere,
+    ) -> (ComputeCommand, Rc<CulledView>) {
+        let mut planes = [[0.0; 4]; 6];
+        for (plane, frustum_plane) in planes.iter_mut().zip(frustum.planes.iter()) {
+            *plane = [
+                frustum_plane.normal.x,
+                frustum_plane.normal.y,
+                frustum_plane.normal.z,
+                frustum_plane.d,
+            ];
+        }
+
+        let center = bounding_sphere.center;
+        let uniform = CullingUniform {
+            planes,
+            sphere: [center.x, center.y, center.z, bounding_sphere.radius],
+            instance_count: self.instance_count,
+            draw_count: self.index_counts.len() as u32,
+            _padding: [0; 2],
+        };
+
+        let view = self.views.get(renderer, || self.create_view(renderer));
+        renderer
+            .queue
+            .write_buffer(&view.uniform_buffer, 0, bytemuck::cast_slice(&[uniform]));
+        renderer.queue.write_buffer(
+            &view.indirect_buffer,
+            0,
+            bytemuck::cast_slice(GpuCuller::reset_draws(&self.index_counts).as_slice()),
+        );
+
+        let command = ComputeCommand {
+            pipeline: Rc::clone(&self.pipeline.compute_pipeline),
+            bind_groups: vec![Rc::clone(&view.bind_group)],
+            workgroups: [
+                (self.instance_count + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE,
+                1,
+                1,
+            ],
+        };
+
+        (command, view)
+    }
+
+    pub fn indirect_offset(draw: usize) -> wgpu::BufferAddress {
+        (draw * std::mem::size_of::<DrawIndexedIndirect>()) as wgpu::BufferAddress
+    }
+
+    fn reset_draws(index_counts: &[u32]) -> Vec<DrawIndexedIndirect> {
+        index_counts
+            .iter()
+            .map(|&index_count| DrawIndexedIndirect {
+                index_count,
+                instance_count: 0,
+                first_index: 0,
+                base_vertex: 0,
+                first_instance: 0,
+            })
+            .collect()
+    }
+
+    fn grow(&mut self, renderer: &Renderer, required: usize) {
+        while self.capacity < required {
+            self.capacity *= 2;
+        }
+
+        self.instances_buffer =
+            GpuCuller::create_instances_buffer(renderer, &self.name, self.capacity);
+        //the views bind the old buffers and are too small
+        self.views.clear();
+    }
+
+    fn create_view(&self, renderer: &Renderer) -> CulledView {
+        let uniform_buffer = renderer.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("{} Culling Buffer", self.name)),
+            size: std::mem::size_of::<CullingUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let indirect_buffer =
+            renderer
+                .device
+                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some(&format!("{} Indirect Buffer", self.name)),
+                    contents: bytemuck::cast_slice(
+                        GpuCuller::reset_draws(&self.index_counts).as_slice(),
+                    ),
+                    usage: wgpu::BufferUsage::INDIRECT
+                        | wgpu::BufferUsage::STORAGE
+                        | wgpu::BufferUsage::COPY_DST,
+                });
+
+        //laid out like the instance buffer, so Instance::desc() reads it directly
+        let visible_buffer = renderer.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("{} Visible Instances Buffer", self.name)),
+            size: (std::mem::size_of::<InstanceUniform>() * self.capacity) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsage::STORAGE | wgpu::BufferUsage::VERTEX,
+            mapped_at_creation: false,
+        });
+
+        let bind_group = GpuCuller::create_bind_group(
+            renderer,
+            &self.bind_group_layout,
+            &uniform_buffer,
+            &self.instances_buffer,
+            &visible_buffer,
+            &indirect_buffer,
+            &self.name,
+        );
+
+        CulledView {
+            uniform_buffer,
+            visible_buffer: Rc::new(visible_buffer),
+            indirect_buffer: Rc::new(indirect_buffer),
+            bind_group: Rc::new(bind_group),
+        }
+    }
+
+    fn create_instances_buffer(renderer: &Renderer, name: &str, capacity: usize) -> wgpu::Buffer {
+        let instance_size = std::mem::size_of::<InstanceUniform>() as wgpu::BufferAddress;
+
+        renderer.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("{} Culling Instances Buffer", name)),
+            size: instance_size * capacity as wgpu::BufferAddress,
+            usage: wgpu::BufferUsage::STORAGE | wgpu::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn create_bind_group(
+        renderer: &Renderer,
+        layout: &wgpu::BindGroupLayout,
+        uniform_buffer: &wgpu::Buffer,
+        instances_buffer: &wgpu::Buffer,
+        visible_buffer: &wgpu::Buffer,
+        indirect_buffer: &wgpu::Buffer,
+        name: &str,
+    ) -> wgpu::BindGroup {
+        renderer
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some(&format!("{} Culling Bind Group", name)),
+                layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: uniform_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: instances_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: visible_buffer.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: indirect_buffer.as_entire_binding(),
+                    },
+                ],
+            })
+    }
+
+    pub(crate) fn get_bind_group_layout(renderer: &Renderer) -> wgpu::BindGroupLayout {
+        let storage = |binding: u32, read_only: bool| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStage::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+
+        renderer
+            .device
+            .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("Culling Bind Group Layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStage::COMPUTE,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                    storage(1, true),
+                    storage(2, false),
+                    storage(3, false),
+                ],
+            })
+    }
+}
@@ -1,53 +1,228 @@
+use crate::rot_primitives::BoundingSphere;
 use nalgebra as na;
 use nalgebra::UnitQuaternion;
 
+//Shader location of the first per instance attribute, 0..5 are left to the Vertex
+pub const FIRST_INSTANCE_LOCATION: u32 = 5;
+
+//Vertex formats a field takes, matrices use one location per column
+pub trait AttributeFormat {
+    const FORMATS: &'static [wgpu::VertexFormat];
+}
+
+macro_rules! attribute_format {
+    ($($ty:ty => [$($format:ident),*]),* $(,)?) => {
+        $(
+            impl AttributeFormat for $ty {
+                const FORMATS: &'static [wgpu::VertexFormat] = &[$(wgpu::VertexFormat::$format),*];
+            }
+        )*
+    };
+}
+
+attribute_format! {
+    f32 => [Float],
+    [f32; 2] => [Float2],
+    [f32; 3] => [Float3],
+    [f32; 4] => [Float4],
+    [[f32; 4]; 3] => [Float4, Float4, Float4],
+    [[f32; 4]; 4] => [Float4, Float4, Float4, Float4],
+    u32 => [Uint],
+    [u32; 2] => [Uint2],
+    [u32; 3] => [Uint3],
+    [u32; 4] => [Uint4],
+    i32 => [Int],
+    [i32; 2] => [Int2],
+    [i32; 3] => [Int3],
+    [i32; 4] => [Int4],
+}
+
+//Implemented by instance_data!, lists the formats of every field in declaration order
+pub trait InstanceData: bytemuck::Pod {
+    fn formats() -> Vec<wgpu::VertexFormat>;
+}
+
+//Declares a #[repr(C)] per instance struct and derives its vertex layout from the fields
+#[macro_export]
+macro_rules! instance_data {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $($field_vis:vis $field:ident : $ty:ty),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[repr(C)]
+        #[derive(Copy, Clone, Debug)]
+        $vis struct $name {
+            $($field_vis $field: $ty),*
+        }
+
+        unsafe impl $crate::bytemuck::Zeroable for $name {}
+        unsafe impl $crate::bytemuck::Pod for $name {}
+
+        impl $crate::rot_primitives::InstanceData for $name {
+            fn formats() -> Vec<$crate::wgpu::VertexFormat> {
+                let mut formats = Vec::new();
+                $(
+                    formats.extend_from_slice(
+                        <$ty as $crate::rot_primitives::AttributeFormat>::FORMATS,
+                    );
+                )*
+                formats
+            }
+        }
+    };
+}
+
+pub struct InstanceLayout {
+    pub array_stride: wgpu::BufferAddress,
+    pub attributes: Vec<wgpu::VertexAttribute>,
+}
+
+impl InstanceLayout {
+    pub fn new<T: InstanceData>(first_location: u32) -> Self {
+        let mut offset = 0;
+        let attributes = T::formats()
+            .into_iter()
+            .enumerate()
+            .map(|(index, format)| {
+                let attribute = wgpu::VertexAttribute {
+                    format,
+                    offset,
+                    shader_location: first_location + index as u32,
+                };
+                offset += format.size();
+
+                attribute
+            })
+            .collect::<Vec<_>>();
+
+        //every supported field is made of 4 byte scalars, so repr(C) adds no padding
+        debug_assert_eq!(offset as usize, std::mem::size_of::<T>());
+
+        Self {
+            array_stride: std::mem::size_of::<T>() as wgpu::BufferAddress,
+            attributes,
+        }
+    }
+
+    pub fn desc(&self) -> wgpu::VertexBufferLayout {
+        wgpu::VertexBufferLayout {
+            array_stride: self.array_stride,
+            step_mode: wgpu::InputStepMode::Instance,
+            attributes: self.attributes.as_slice(),
+        }
+    }
+
+    //first free location after this layout, for a second per instance buffer
+    pub fn next_location(&self) -> u32 {
+        self.attributes
+            .last()
+            .map(|attribute| attribute.shader_location + 1)
+            .unwrap_or(FIRST_INSTANCE_LOCATION)
+    }
+}
+
+//Stays valid while the instance lives, unlike its index that moves on removal
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct InstanceId(pub(crate) u32);
+
+impl InstanceId {
+    pub fn raw(&self) -> u32 {
+        self.0
+    }
+}
+
 pub struct Instance {
     pub isometry: na::Isometry3<f32>,
+    pub scale: na::Vector3<f32>,
+
+    pub tint: [f32; 4],
+    pub custom: [f32; 4],
+    pub material_index: u32,
 
     pub uniform: InstanceUniform,
 }
 
 impl Instance {
     pub fn new(isometry: na::Isometry3<f32>) -> Self {
-        Self {
+        let mut instance = Self {
             isometry,
-            uniform: InstanceUniform {
-                model: isometry.to_homogeneous().into(),
-            },
-        }
+            scale: na::Vector3::new(1.0, 1.0, 1.0),
+            tint: [1.0; 4],
+            custom: [0.0; 4],
+            material_index: 0,
+            uniform: InstanceUniform::default(),
+        };
+        instance.update();
+
+        instance
     }
 
-    pub fn update(&mut self) {
-        self.uniform.model = self.isometry.to_homogeneous().into();
+    pub fn with_scale(mut self, scale: na::Vector3<f32>) -> Self {
+        self.scale = scale;
+        self.update();
+        self
     }
 
-    pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
-        wgpu::VertexBufferLayout {
-            array_stride: std::mem::size_of::<InstanceUniform>() as wgpu::BufferAddress,
-            step_mode: wgpu::InputStepMode::Instance,
-            attributes: &[
-                wgpu::VertexAttribute {
-                    format: wgpu::VertexFormat::Float4,
-                    offset: 0,
-                    shader_location: 5,
-                },
-                wgpu::VertexAttribute {
-                    format: wgpu::VertexFormat::Float4,
-                    offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
-                    shader_location: 6,
-                },
-                wgpu::VertexAttribute {
-                    format: wgpu::VertexFormat::Float4,
-                    offset: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
-                    shader_location: 7,
-                },
-                wgpu::VertexAttribute {
-                    format: wgpu::VertexFormat::Float4,
-                    offset: std::mem::size_of::<[f32; 12]>() as wgpu::BufferAddress,
-                    shader_location: 8,
-                },
-            ],
+    pub fn with_tint(mut self, tint: [f32; 4]) -> Self {
+        self.tint = tint;
+        self.update();
+        self
+    }
+
+    pub fn with_custom(mut self, custom: [f32; 4]) -> Self {
+        self.custom = custom;
+        self.update();
+        self
+    }
+
+    pub fn with_material_index(mut self, material_index: u32) -> Self {
+        self.material_index = material_index;
+        self.update();
+        self
+    }
+
+    pub fn update(&mut self) {
+        let model =
+            self.isometry.to_homogeneous() * na::Matrix4::new_nonuniform_scaling(&self.scale);
+
+        //inverse transpose keeps normals perpendicular under non uniform scale
+        let linear = model.fixed_slice::<3, 3>(0, 0).into_owned();
+        let normal = linear
+            .try_inverse()
+            .unwrap_or_else(na::Matrix3::identity)
+            .transpose();
+
+        self.uniform.model = model.into();
+        for column in 0..3 {
+            self.uniform.normal[column] = [
+                normal[(0, column)],
+                normal[(1, column)],
+                normal[(2, column)],
+                0.0,
+            ];
         }
+        self.uniform.tint = self.tint;
+        self.uniform.custom = self.custom;
+        self.uniform.indices = [self.material_index, 0, 0, 0];
+    }
+
+    //bounding volumes only follow the largest axis, they stay spheres
+    pub fn max_scale(&self) -> f32 {
+        self.scale.abs().max()
+    }
+
+    //model space sphere to world space, following the scale too
+    pub fn transform_sphere(&self, sphere: &BoundingSphere) -> BoundingSphere {
+        let center = na::Point3::from(sphere.center.coords.component_mul(&self.scale));
+
+        BoundingSphere::new(self.isometry * center, sphere.radius * self.max_scale())
+    }
+
+    pub fn desc() -> InstanceLayout {
+        InstanceLayout::new::<InstanceUniform>(FIRST_INSTANCE_LOCATION)
     }
 }
 
@@ -60,11 +235,15 @@ impl Default for Instance {
     }
 }
 
-#[repr(C)]
-#[derive(Default, Debug, Copy, Clone)]
-pub struct InstanceUniform {
-    pub model: [[f32; 4]; 4],
+instance_data! {
+    #[derive(Default)]
+    pub struct InstanceUniform {
+        pub model: [[f32; 4]; 4],
+        //mat3 with every column padded to a vec4
+        pub normal: [[f32; 4]; 3],
+        pub tint: [f32; 4],
+        pub custom: [f32; 4],
+        //x is the material or texture array index, the rest is free for the user
+        pub indices: [u32; 4],
+    }
 }
-
-unsafe impl bytemuck::Zeroable for InstanceUniform {}
-unsafe impl bytemuck::Pod for InstanceUniform {}
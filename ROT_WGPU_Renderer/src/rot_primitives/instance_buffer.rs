@@ -0,0 +1,152 @@
+use crate::rot_primitives::{Instance, InstanceUniform};
+use crate::rot_render_queue::PerView;
+use crate::Renderer;
+use std::ops::Range;
+use std::rc::Rc;
+
+//sorted by start, overlapping and touching ranges become one
+fn merge_ranges(mut ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    ranges.sort_by_key(|range| range.start);
+
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+    for range in ranges.into_iter() {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+
+    merged
+}
+
+//GPU copy of an Object instances. The capacity doubles when it runs out and
+//only the dirty ranges are written with queue.write_buffer
+pub struct InstanceBuffer {
+    name: String,
+    capacity: usize,
+
+    pub buffer: Rc<wgpu::Buffer>,
+    //the visible instances get packed here when CPU culling or LOD drops some.
+    //Each camera sees others, see culled_buffer
+    culled_buffers: PerView<wgpu::Buffer>,
+
+    //sorted and merged, may reach past the instance count after a removal
+    dirty: Vec<Range<usize>>,
+}
+
+impl InstanceBuffer {
+    pub fn new(renderer: &Renderer, name: &str, capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+
+        Self {
+            name: name.to_string(),
+            capacity,
+            buffer: Rc::new(InstanceBuffer::create_buffer(
+                renderer, name, "Instance", capacity,
+            )),
+            culled_buffers: PerView::new(),
+            dirty: Vec::new(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    //for the view of the current camera, sized like the instance buffer
+    pub fn culled_buffer(&self, renderer: &Renderer) -> Rc<wgpu::Buffer> {
+        self.culled_buffers.get(renderer, || {
+            InstanceBuffer::create_buffer(renderer, &self.name, "Culled Instance", self.capacity)
+        })
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+
+    pub fn dirty_ranges(&self) -> &[Range<usize>] {
+        self.dirty.as_slice()
+    }
+
+    pub fn mark_dirty(&mut self, range: Range<usize>) {
+        self.dirty.push(range);
+        self.dirty = merge_ranges(std::mem::take(&mut self.dirty));
+    }
+
+    //Returns true when the buffers were recreated, their old content is gone
+    pub fn reserve(&mut self, renderer: &Renderer, required: usize) -> bool {
+        if required <= self.capacity {
+            return false;
+        }
+
+        while self.capacity < required {
+            self.capacity *= 2;
+        }
+
+        self.buffer = Rc::new(InstanceBuffer::create_buffer(
+            renderer,
+            &self.name,
+            "Instance",
+            self.capacity,
+        ));
+        self.culled_buffers.clear();
+
+        true
+    }
+
+    //instances must already be updated, ranges past their end are dropped
+    pub fn flush(&mut self, renderer: &Renderer, instances: &[Instance]) {
+        let instance_size = std::mem::size_of::<InstanceUniform>();
+
+        for range in self.dirty.drain(..) {
+            let range = range.start.min(instances.len())..range.end.min(instances.len());
+            if range.start == range.end {
+                continue;
+            }
+
+            let uniforms = instances[range.clone()]
+                .iter()
+                .map(|instance| instance.uniform)
+                .collect::<Vec<_>>();
+
+            renderer.queue.write_buffer(
+                &self.buffer,
+                (range.start * instance_size) as wgpu::BufferAddress,
+                bytemuck::cast_slice(uniforms.as_slice()),
+            );
+        }
+    }
+
+    fn create_buffer(renderer: &Renderer, name: &str, kind: &str, capacity: usize) -> wgpu::Buffer {
+        let instance_size = std::mem::size_of::<InstanceUniform>() as wgpu::BufferAddress;
+
+        renderer.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(&format!("{} {} Buffer", name, kind)),
+            size: instance_size * capacity as wgpu::BufferAddress,
+            usage: wgpu::BufferUsage::VERTEX | wgpu::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlapping_and_touching_ranges_merge() {
+        assert_eq!(merge_ranges(vec![0..4, 2..6]), vec![0..6]);
+        assert_eq!(merge_ranges(vec![0..4, 4..6]), vec![0..6]);
+        assert_eq!(merge_ranges(vec![1..8, 2..3]), vec![1..8]);
+    }
+
+    #[test]
+    fn disjoint_ranges_stay_apart_and_sorted() {
+        assert_eq!(
+            merge_ranges(vec![10..12, 0..2, 5..6]),
+            vec![0..2, 5..6, 10..12]
+        );
+        assert_eq!(merge_ranges(vec![10..12, 0..2, 2..10]), vec![0..12]);
+        assert!(merge_ranges(Vec::new()).is_empty());
+    }
+}
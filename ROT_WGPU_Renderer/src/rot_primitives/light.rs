@@ -1,7 +1,9 @@
 use crate::rot_pipeline::{Pipeline, PipelineBuilder};
 use crate::rot_primitives::{Instance, Object, Vertex};
+use crate::rot_render_queue::DrawCommand;
 use crate::Renderer;
 use nalgebra as na;
+use std::rc::Rc;
 use wgpu::util::DeviceExt;
 
 #[derive(Debug, Copy, Clone)]
@@ -33,7 +35,7 @@ pub struct Light {
     model: Object,
 
     pub buffer: wgpu::Buffer,
-    pub bind_group: wgpu::BindGroup,
+    pub bind_group: Rc<wgpu::BindGroup>,
     pub bind_group_layout: wgpu::BindGroupLayout,
 
     pub pipeline: Pipeline,
@@ -55,17 +57,19 @@ impl Light {
 
         let bind_group = Light::create_bind_group(renderer, &bind_group_layout, &buffer, name);
 
+        let pipeline_name = format!("{} pipeline", name);
         let pipeline_buider = PipelineBuilder {
-            name: format!("{} pipeline", name).as_str(),
+            name: pipeline_name.as_str(),
             uniform_material: false,
             uniform_camera: true,
             uniform_light: true,
             vertex_shader_path: "shaders/light.vert.spv",
             fragment_shader_path: "shaders/light.frag.spv",
             vertex_buffer_layout: vec![Vertex::desc()],
+            ..PipelineBuilder::default()
         };
 
-        let pipeline = Pipeline::new(renderer, pipeline_buider);
+        let pipeline = Pipeline::new(renderer, &pipeline_buider);
 
         let object = Object::load(renderer, "model/light/Bulbs.obj", "light");
 
@@ -74,25 +78,50 @@ impl Light {
             uniform,
             model: object,
             buffer,
-            bind_group,
+            bind_group: Rc::new(bind_group),
             bind_group_layout,
             pipeline,
         }
     }
 
     pub fn draw(&self, renderer: &mut Renderer) {
-        renderer.draw_frame(&self.model, &self.pipeline);
+        let camera_bind_group = match renderer.camera_bind_group() {
+            Some(bind_group) => bind_group,
+            None => return,
+        };
+
+        for mesh in self.model.meshes.iter() {
+            renderer.submit_opaque(DrawCommand {
+                pipeline: self.pipeline.clone(),
+                bind_groups: vec![Rc::clone(&camera_bind_group), Rc::clone(&self.bind_group)],
+                vertex_buffers: vec![Rc::clone(&mesh.vertex_buffer)],
+                index_buffer: Some(Rc::clone(&mesh.index_buffer)),
+                indices: 0..mesh.size as u32,
+                instances: 0..1,
+                indirect: None,
+                depth: 0.0,
+            });
+        }
     }
 
-    pub fn on_update(&mut self, renderer: &Renderer) {
-        let old_position: na::Vector3<f32> = self.uniform.position.into();
+    pub fn position(&self) -> na::Point3<f32> {
+        na::Point3::from(na::Vector3::from(self.uniform.position))
+    }
 
-        self.uniform.position = (na::UnitQuaternion::from_axis_angle(
-            na::Vector3::y_axis(),
-            std::f32::consts::FRAC_PI_8,
-        ) * old_position)
-            .into();
+    pub fn set_position(&mut self, position: na::Point3<f32>) {
+        self.uniform.position = position.coords.into();
+    }
+
+    pub fn color(&self) -> [f32; 3] {
+        self.uniform.color
+    }
 
+    pub fn set_color(&mut self, color: [f32; 3]) {
+        self.uniform.color = color;
+    }
+
+    //uploads what the setters changed, move it with a rot_animation::Animator
+    pub fn on_update(&mut self, renderer: &Renderer) {
         renderer
             .queue
             .write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[self.uniform]));
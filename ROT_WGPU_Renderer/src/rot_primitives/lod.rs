@@ -0,0 +1,243 @@
+use crate::rot_primitives::{Instance, InstanceId, InstanceUniform, Mesh};
+use crate::rot_render_queue::PerView;
+use crate::Renderer;
+use nalgebra as na;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LodMetric {
+    //thresholds are camera distances, ascending
+    Distance,
+    //thresholds are the fraction of the screen height covered by the bounding sphere, descending
+    ScreenSize,
+}
+
+pub struct LodLevel {
+    pub meshes: Vec<Mesh>,
+    pub threshold: f32,
+
+    //one per view, see LodChain::instance_buffer
+    instance_buffers: PerView<wgpu::Buffer>,
+}
+
+pub struct LodChain {
+    pub metric: LodMetric,
+    //fraction of a threshold an instance must go past before it switches level, 0.0 disables it
+    pub hysteresis: f32,
+
+    //level 0 is drawn with the Object own meshes, these are the coarser ones
+    pub levels: Vec<LodLevel>,
+
+    //last level picked for each instance, by view index as every camera sees them from elsewhere
+    current: RefCell<HashMap<(usize, InstanceId), usize>>,
+    //in instances, of every level buffer
+    capacity: usize,
+}
+
+//fraction of the screen height a sphere this far from the camera covers.
+//w grows with the distance under a perspective projection and stays 1.0 under an orthographic one
+fn screen_size(projection: &na::Matrix4<f32>, radius: f32, distance: f32) -> f32 {
+    let w = projection[(3, 3)] - projection[(3, 2)] * distance;
+    radius * projection[(1, 1)].abs() / w.max(1e-6)
+}
+
+impl LodChain {
+    pub fn new(metric: LodMetric, hysteresis: f32) -> Self {
+        Self {
+            metric,
+            hysteresis,
+            levels: Vec::new(),
+            current: RefCell::new(HashMap::new()),
+            capacity: 1,
+        }
+    }
+
+    pub fn push_level(&mut self, meshes: Vec<Mesh>, threshold: f32, capacity: usize) {
+        self.levels.push(LodLevel {
+            meshes,
+            threshold,
+            instance_buffers: PerView::new(),
+        });
+        self.resize(capacity);
+    }
+
+    //the buffers are created again at this size when the views need them
+    pub fn resize(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        for level in self.levels.iter_mut() {
+            level.instance_buffers.clear();
+        }
+    }
+
+    //the instances drawn with level, 1 and up, for the view of the current camera
+    pub fn instance_buffer(&self, renderer: &Renderer, level: usize) -> Rc<wgpu::Buffer> {
+        self.levels[level - 1].instance_buffers.get(renderer, || {
+            LodChain::create_instance_buffer(renderer, self.capacity)
+        })
+    }
+
+    pub fn forget(&self, id: InstanceId) {
+        self.current
+            .borrow_mut()
+            .retain(|&(_, instance), _| instance != id);
+    }
+
+    //Splits the visible instances into one bucket per level, index 0 being the base meshes.
+    //visible pairs every instance with its id inside the Object, used for hysteresis
+    pub fn bucket<'a>(
+        &self,
+        renderer: &Renderer,
+        visible: &[(InstanceId, &'a Instance)],
+        bounding_radius: f32,
+    ) -> Vec<Vec<&'a Instance>> {
+        let mut buckets = vec![Vec::new(); self.levels.len() + 1];
+        let mut current = self.current.borrow_mut();
+
+        let view = renderer.view_index();
+        let camera_position = renderer.camera_position();
+        let projection = renderer.camera_projection();
+
+        for &(id, instance) in visible.iter() {
+            let position = na::Point3::from(instance.isometry.translation.vector);
+            let distance = na::distance(&camera_position, &position);
+            let coarseness = match self.metric {
+                LodMetric::Distance => distance,
+                LodMetric::ScreenSize => {
+                    let radius = bounding_radius * instance.max_scale();
+                    1.0 / screen_size(projection, radius, distance).max(1e-6)
+                }
+            };
+
+            let level = self.select(coarseness, current.get(&(view, id)).copied().unwrap_or(0));
+            current.insert((view, id), level);
+            buckets[level].push(instance);
+        }
+
+        buckets
+    }
+
+    fn select(&self, coarseness: f32, current: usize) -> usize {
+        let switch_value = |level: usize| {
+            //level 0 starts at nothing, the rest at their own threshold
+            let threshold = self.levels[level - 1].threshold;
+            match self.metric {
+                LodMetric::Distance => threshold,
+                LodMetric::ScreenSize => 1.0 / threshold.max(1e-6),
+            }
+        };
+
+        let mut level = current.min(self.levels.len());
+        while level < self.levels.len()
+            && coarseness >= switch_value(level + 1) * (1.0 + self.hysteresis)
+        {
+            level += 1;
+        }
+        while level > 0 && coarseness < switch_value(level) * (1.0 - self.hysteresis) {
+            level -= 1;
+        }
+
+        level
+    }
+
+    fn create_instance_buffer(renderer: &Renderer, capacity: usize) -> wgpu::Buffer {
+        let instance_size = std::mem::size_of::<InstanceUniform>() as wgpu::BufferAddress;
+
+        renderer.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Lod Instance Buffer"),
+            size: instance_size * capacity as wgpu::BufferAddress,
+            usage: wgpu::BufferUsage::VERTEX | wgpu::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rot_primitives::Projection;
+
+    fn chain(metric: LodMetric, hysteresis: f32, thresholds: &[f32]) -> LodChain {
+        let mut chain = LodChain::new(metric, hysteresis);
+        for &threshold in thresholds.iter() {
+            chain.push_level(Vec::new(), threshold, 1);
+        }
+
+        chain
+    }
+
+    #[test]
+    fn distance_switches_at_each_threshold() {
+        let chain = chain(LodMetric::Distance, 0.0, &[10.0, 20.0]);
+
+        assert_eq!(chain.select(9.99, 0), 0);
+        assert_eq!(chain.select(10.0, 0), 1);
+        assert_eq!(chain.select(19.99, 0), 1);
+        assert_eq!(chain.select(20.0, 0), 2);
+        assert_eq!(chain.select(500.0, 0), 2);
+
+        assert_eq!(chain.select(19.99, 2), 1);
+        assert_eq!(chain.select(9.99, 2), 0);
+    }
+
+    #[test]
+    fn hysteresis_holds_the_level_inside_the_band() {
+        let chain = chain(LodMetric::Distance, 0.1, &[10.0, 20.0]);
+
+        //going out, a level is only taken 10% past its threshold
+        assert_eq!(chain.select(10.5, 0), 0);
+        assert_eq!(chain.select(11.0, 0), 1);
+        assert_eq!(chain.select(21.5, 1), 1);
+        assert_eq!(chain.select(22.0, 1), 2);
+
+        //coming back, it is only left 10% before it
+        assert_eq!(chain.select(18.5, 2), 2);
+        assert_eq!(chain.select(17.9, 2), 1);
+        assert_eq!(chain.select(9.5, 1), 1);
+        assert_eq!(chain.select(8.9, 1), 0);
+
+        //the band only delays switches, far past it every level changes at once
+        assert_eq!(chain.select(30.0, 0), 2);
+        assert_eq!(chain.select(1.0, 2), 0);
+    }
+
+    #[test]
+    fn screen_size_thresholds_descend() {
+        let chain = chain(LodMetric::ScreenSize, 0.0, &[0.5, 0.1]);
+        let select = |screen_size: f32, current: usize| chain.select(1.0 / screen_size, current);
+
+        assert_eq!(select(0.8, 0), 0);
+        assert_eq!(select(0.5, 0), 1);
+        assert_eq!(select(0.2, 0), 1);
+        assert_eq!(select(0.1, 0), 2);
+        assert_eq!(select(0.6, 2), 0);
+    }
+
+    #[test]
+    fn levels_past_the_chain_are_clamped() {
+        let chain = chain(LodMetric::Distance, 0.0, &[10.0]);
+
+        assert_eq!(chain.select(5.0, 3), 0);
+        assert_eq!(chain.select(15.0, 3), 1);
+    }
+
+    #[test]
+    fn screen_size_follows_the_projection() {
+        let perspective = Projection::perspective(std::f32::consts::FRAC_PI_2, 0.1, 100.0);
+        let matrix = perspective.matrix(1.0);
+        assert!((screen_size(&matrix, 1.0, 10.0) - 0.1).abs() < 1e-5);
+        assert!((screen_size(&matrix, 1.0, 20.0) - 0.05).abs() < 1e-5);
+
+        let infinite = Projection::InfinitePerspective {
+            fovy: std::f32::consts::FRAC_PI_2,
+            znear: 0.1,
+        };
+        assert!((screen_size(&infinite.matrix(1.0), 1.0, 10.0) - 0.1).abs() < 1e-5);
+
+        //a 4 units high view, no matter how far
+        let orthographic = Projection::orthographic(4.0, 0.1, 100.0).matrix(2.0);
+        assert!((screen_size(&orthographic, 1.0, 10.0) - 0.5).abs() < 1e-5);
+        assert!((screen_size(&orthographic, 1.0, 50.0) - 0.5).abs() < 1e-5);
+    }
+}
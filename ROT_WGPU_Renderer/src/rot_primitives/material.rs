@@ -1,48 +1,180 @@
-use crate::rot_primitives::Primitive;
+use crate::rot_primitives::{Primitive, Texture};
 use crate::Renderer;
+use std::rc::Rc;
+use wgpu::util::DeviceExt;
 use wgpu::{BindGroup, BindGroupLayout};
 
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum AlphaMode {
+    //alpha is ignored, drawn in the opaque pass
+    Opaque,
+    //fragments below the cutoff are discarded, still drawn in the opaque pass
+    Mask(f32),
+    //alpha blended, drawn back-to-front after every opaque draw
+    Blend,
+}
+
+impl AlphaMode {
+    pub fn is_transparent(&self) -> bool {
+        *self == AlphaMode::Blend
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct MaterialUniform {
+    opacity: f32,
+    alpha_cutoff: f32,
+    _padding: [f32; 2],
+}
+
+unsafe impl bytemuck::Pod for MaterialUniform {}
+unsafe impl bytemuck::Zeroable for MaterialUniform {}
+
+impl MaterialUniform {
+    fn build(opacity: f32, alpha_mode: AlphaMode) -> Self {
+        let alpha_cutoff = match alpha_mode {
+            AlphaMode::Mask(cutoff) => cutoff,
+            _ => 0.0,
+        };
+
+        Self {
+            opacity,
+            alpha_cutoff,
+            _padding: [0.0; 2],
+        }
+    }
+}
+
 pub struct Material {
     pub name: String,
-    pub bind_group: wgpu::BindGroup,
+    pub bind_group: Rc<wgpu::BindGroup>,
     pub bind_group_layout: wgpu::BindGroupLayout,
 
-    texture_view: wgpu::TextureView,
-    sampler: wgpu::Sampler,
+    pub alpha_mode: AlphaMode,
+    pub opacity: f32,
+    //of the diffuse texture, in pixels
+    pub dimensions: (u32, u32),
+
+    uniform: MaterialUniform,
+    buffer: wgpu::Buffer,
+
+    texture: Rc<Texture>,
 }
 
 impl Material {
     pub fn build(diffuse_src: std::path::PathBuf, renderer: &Renderer, name: &str) -> Self {
-        let diffuse_texture = Material::upload_image(diffuse_src.clone(), renderer, name);
-        let (texture_view, sampler) =
-            Material::create_view_and_sampler(&diffuse_texture, renderer, name);
+        let diffuse_bytes = std::fs::read(diffuse_src).unwrap();
+        let diffuse_image = image::load_from_memory(diffuse_bytes.as_slice()).unwrap();
+
+        Material::from_rgba(&diffuse_image.to_rgba8(), renderer, name)
+    }
+
+    //1x1 diffuse texture, for generated meshes and debug visuals
+    pub fn from_color(color: [u8; 4], renderer: &Renderer, name: &str) -> Self {
+        let rgba = image::RgbaImage::from_pixel(1, 1, image::Rgba(color));
+
+        Material::from_rgba(&rgba, renderer, name)
+    }
+
+    pub fn from_rgba(rgba: &image::RgbaImage, renderer: &Renderer, name: &str) -> Self {
+        Material::from_rgba_format(rgba, renderer, name, wgpu::TextureFormat::Rgba8UnormSrgb)
+    }
+
+    //for textures holding data instead of colors, like distance fields, sampled without sRGB decoding
+    pub fn from_rgba_linear(rgba: &image::RgbaImage, renderer: &Renderer, name: &str) -> Self {
+        Material::from_rgba_format(rgba, renderer, name, wgpu::TextureFormat::Rgba8Unorm)
+    }
+
+    fn from_rgba_format(
+        rgba: &image::RgbaImage,
+        renderer: &Renderer,
+        name: &str,
+        format: wgpu::TextureFormat,
+    ) -> Self {
+        let alpha_mode = Material::detect_alpha_mode(rgba);
+        let texture = Texture::from_rgba(rgba, renderer, &format!("{} diffuse", name), format);
+
+        Material::from_texture(Rc::new(texture), alpha_mode, renderer, name)
+    }
+
+    //shares the texture, like the color of a RenderTarget, which then shows what its camera draws
+    pub fn from_texture(
+        texture: Rc<Texture>,
+        alpha_mode: AlphaMode,
+        renderer: &Renderer,
+        name: &str,
+    ) -> Self {
+        let opacity = 1.0;
+        let uniform = MaterialUniform::build(opacity, alpha_mode);
+        let buffer = renderer
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(&format!("{} Material Buffer", name)),
+                contents: bytemuck::cast_slice(&[uniform]),
+                usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+            });
 
         let bind_group_layout = Material::get_bind_group_layout(renderer);
         let bind_group = Material::create_bind_group(
             renderer,
             &bind_group_layout,
-            &texture_view,
-            &sampler,
+            &texture.view,
+            &texture.sampler,
+            &buffer,
             name,
         );
 
-        let fragment_module = Material::load_shader(renderer, name);
-
         Self {
             name: name.to_string(),
-            bind_group,
+            bind_group: Rc::new(bind_group),
             bind_group_layout,
-            texture_view,
-            sampler,
-            fragment_module,
+            alpha_mode,
+            opacity,
+            dimensions: texture.dimensions,
+            uniform,
+            buffer,
+            texture,
         }
     }
 
+    //MTL "d" (dissolve). Anything below 1.0 makes the material transparent
+    pub fn set_dissolve(&mut self, renderer: &Renderer, dissolve: f32) {
+        self.opacity = dissolve.max(0.0).min(1.0);
+        if self.opacity < 1.0 {
+            self.alpha_mode = AlphaMode::Blend;
+        }
+
+        self.update_uniform(renderer);
+    }
+
+    pub fn set_alpha_mode(&mut self, renderer: &Renderer, alpha_mode: AlphaMode) {
+        self.alpha_mode = alpha_mode;
+
+        self.update_uniform(renderer);
+    }
+
+    pub fn is_transparent(&self) -> bool {
+        self.alpha_mode.is_transparent()
+    }
+
+    pub fn texture(&self) -> &Rc<Texture> {
+        &self.texture
+    }
+
+    fn update_uniform(&mut self, renderer: &Renderer) {
+        self.uniform = MaterialUniform::build(self.opacity, self.alpha_mode);
+        renderer
+            .queue
+            .write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[self.uniform]));
+    }
+
     fn create_bind_group(
         renderer: &Renderer,
         layout: &wgpu::BindGroupLayout,
         viewer: &wgpu::TextureView,
         sampler: &wgpu::Sampler,
+        buffer: &wgpu::Buffer,
         name: &str,
     ) -> wgpu::BindGroup {
         renderer
@@ -59,6 +191,10 @@ impl Material {
                         binding: 1,
                         resource: wgpu::BindingResource::Sampler(sampler),
                     },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: buffer.as_entire_binding(),
+                    },
                 ],
             })
     }
@@ -88,84 +224,35 @@ impl Material {
                         },
                         count: None,
                     },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: wgpu::ShaderStage::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
                 ],
             })
     }
 
-    fn create_view_and_sampler(
-        texture: &wgpu::Texture,
-        renderer: &Renderer,
-        name: &str,
-    ) -> (wgpu::TextureView, wgpu::Sampler) {
-        let diffuse_texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
-        let diffuse_sampler = renderer.device.create_sampler(&wgpu::SamplerDescriptor {
-            label: Some(&format!(" {} Sampler", name)),
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Nearest,
-            mipmap_filter: wgpu::FilterMode::Nearest,
-            ..Default::default()
-        });
-
-        (diffuse_texture_view, diffuse_sampler)
-    }
-
-    fn load_shader(renderer: &Renderer, name: &str) -> wgpu::ShaderModule {
-        // FRAGMENT ----------------------------------------------------
-        let fragment_path = format!("shaders/test.frag.spv");
-        let frag_bytes = std::fs::read(fragment_path.clone()).unwrap();
-        let fragment_module = renderer
-            .device
-            .create_shader_module(&wgpu::ShaderModuleDescriptor {
-                label: Some(fragment_path.as_str()),
-                source: wgpu::util::make_spirv(&frag_bytes),
-                flags: wgpu::ShaderFlags::VALIDATION,
-            });
-
-        fragment_module
-    }
-
-    fn upload_image(path: std::path::PathBuf, renderer: &Renderer, name: &str) -> wgpu::Texture {
-        let diffuse_bytes = std::fs::read(path).unwrap();
-        let diffuse_image = image::load_from_memory(diffuse_bytes.as_slice()).unwrap();
-        let diffuse_rgba = diffuse_image.to_rgba8();
-
-        use image::GenericImageView;
-        let dimensions = diffuse_image.dimensions();
-
-        let texture_size = wgpu::Extent3d {
-            width: dimensions.0,
-            height: dimensions.1,
-            depth: 1,
-        };
-
-        let diffuse_texture = renderer.device.create_texture(&wgpu::TextureDescriptor {
-            label: Some(&format!("{} diffuse texture", name)),
-            size: texture_size,
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8UnormSrgb,
-            usage: wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST,
-        });
-
-        renderer.queue.write_texture(
-            wgpu::TextureCopyView {
-                texture: &diffuse_texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-            },
-            diffuse_rgba.as_raw(),
-            wgpu::TextureDataLayout {
-                offset: 0,
-                bytes_per_row: 4 * dimensions.0,
-                rows_per_image: dimensions.1,
-            },
-            texture_size,
-        );
+    //Only-0-or-255 alpha means a cutout, anything in between needs blending
+    fn detect_alpha_mode(rgba: &image::RgbaImage) -> AlphaMode {
+        let mut has_alpha = false;
+        for pixel in rgba.pixels() {
+            match pixel[3] {
+                255 => {}
+                0 => has_alpha = true,
+                _ => return AlphaMode::Blend,
+            }
+        }
 
-        diffuse_texture
+        if has_alpha {
+            AlphaMode::Mask(0.5)
+        } else {
+            AlphaMode::Opaque
+        }
     }
 }
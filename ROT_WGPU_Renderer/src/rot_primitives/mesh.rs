@@ -1,18 +1,36 @@
+use crate::rot_picking::Bvh;
 use crate::rot_primitives::vertex::Vertex;
+use crate::rot_primitives::{procedural, simplify, Aabb, BoundingSphere};
 use crate::Renderer;
 use nalgebra as na;
 
-use crate::rot_primitives::instance::{Instance, InstanceUniform};
+use std::cell::RefCell;
+use std::rc::Rc;
 use wgpu::util::DeviceExt;
 
 pub struct Mesh {
     pub name: String,
 
-    pub index_buffer: wgpu::Buffer,
-    pub vertex_buffer: wgpu::Buffer,
+    pub index_buffer: Rc<wgpu::Buffer>,
+    pub vertex_buffer: Rc<wgpu::Buffer>,
 
     pub size: usize,
     pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+
+    //model space
+    pub aabb: Aabb,
+    pub bounding_sphere: BoundingSphere,
+
+    //index into the owner Object materials
+    pub material: usize,
+
+    //every index expanded to its own vertex, built on demand for the wireframe fallback
+    unindexed_buffer: RefCell<Option<Rc<wgpu::Buffer>>>,
+    //a model space line per vertex along its normal, built on demand for the vertex normals view
+    normal_lines_buffer: RefCell<Option<Rc<wgpu::Buffer>>>,
+    //built by the first ray cast
+    bvh: RefCell<Option<Rc<Bvh>>>,
 }
 
 impl Mesh {
@@ -33,51 +51,175 @@ impl Mesh {
                 usage: wgpu::BufferUsage::INDEX,
             });
 
+        let aabb = Aabb::from_vertices(vertices.as_slice());
+        let bounding_sphere = BoundingSphere::from_vertices(vertices.as_slice());
+
         Self {
             name: name.to_string(),
-            index_buffer: index,
-            vertex_buffer: vertex,
+            index_buffer: Rc::new(index),
+            vertex_buffer: Rc::new(vertex),
             size: indices.len(),
             vertices,
+            indices,
+            aabb,
+            bounding_sphere,
+            material: 0,
+            unindexed_buffer: RefCell::new(None),
+            normal_lines_buffer: RefCell::new(None),
+            bvh: RefCell::new(None),
         }
     }
-    /*
 
-    fn build_instances() -> Vec<Instance> {
-        let num_of_instances_per_row: u32 = 10;
-        let instance_displacement: na::Vector3<f32> = na::Vector3::new(
-            num_of_instances_per_row as f32 * 0.5,
-            0.0,
-            num_of_instances_per_row as f32 * 0.5,
-        );
+    pub fn len(&self) -> u32 {
+        self.size as u32
+    }
+
+    //For draws that need gl_VertexIndex to walk the triangles, drawn with len() vertices
+    pub fn unindexed_buffer(&self, renderer: &Renderer) -> Rc<wgpu::Buffer> {
+        let mut unindexed_buffer = self.unindexed_buffer.borrow_mut();
+        let buffer = unindexed_buffer.get_or_insert_with(|| {
+            let vertices = self
+                .indices
+                .iter()
+                .map(|&index| self.vertices[index as usize])
+                .collect::<Vec<_>>();
+
+            Rc::new(
+                renderer
+                    .device
+                    .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some(&format!("{} Unindexed Vertex Buffer", self.name)),
+                        contents: bytemuck::cast_slice(vertices.as_slice()),
+                        usage: wgpu::BufferUsage::VERTEX,
+                    }),
+            )
+        });
+
+        Rc::clone(buffer)
+    }
+
+    //Drawn as a LineList with normal_lines_len() vertices, a tenth of the bounding radius long
+    pub fn normal_lines_buffer(&self, renderer: &Renderer) -> Rc<wgpu::Buffer> {
+        let mut normal_lines_buffer = self.normal_lines_buffer.borrow_mut();
+        let buffer = normal_lines_buffer.get_or_insert_with(|| {
+            let length = self.bounding_sphere.radius * 0.1;
+            let vertices = self
+                .vertices
+                .iter()
+                .flat_map(|vertex| {
+                    let normal = na::Vector3::from(vertex.normal)
+                        .try_normalize(f32::EPSILON)
+                        .unwrap_or_else(na::Vector3::zeros);
+                    let end = na::Vector3::from(vertex.position) + normal * length;
 
-        (0..num_of_instances_per_row)
-            .flat_map(|z| {
-                (0..num_of_instances_per_row).map(move |x| {
-                    let translation: na::Vector3<f32> =
-                        na::Vector3::new(x as f32, 0.0, z as f32) - instance_displacement;
-
-                    let axisangle = if translation == na::Vector3::new(0.0, 0.0, 0.0) {
-                        na::Vector3::z() * 0.0
-                    } else {
-                        translation.normalize() * std::f32::consts::PI / 4.0
-                    };
-
-                    let mut instance = Instance {
-                        isometry: na::Isometry3::new(translation, axisangle),
-                        uniform: Default::default(),
-                    };
-
-                    instance.update();
-                    instance
+                    vec![
+                        *vertex,
+                        Vertex {
+                            position: end.into(),
+                            ..*vertex
+                        },
+                    ]
                 })
-            })
-            .collect::<Vec<_>>()
+                .collect::<Vec<_>>();
+
+            Rc::new(
+                renderer
+                    .device
+                    .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some(&format!("{} Normal Lines Buffer", self.name)),
+                        contents: bytemuck::cast_slice(vertices.as_slice()),
+                        usage: wgpu::BufferUsage::VERTEX,
+                    }),
+            )
+        });
+
+        Rc::clone(buffer)
     }
 
-     */
+    pub fn normal_lines_len(&self) -> u32 {
+        2 * self.vertices.len() as u32
+    }
 
-    pub fn len(&self) -> u32 {
-        self.size as u32
+    //for ray casts in model space, vertices and indices are not expected to change afterwards
+    pub fn bvh(&self) -> Rc<Bvh> {
+        let mut bvh = self.bvh.borrow_mut();
+        let bvh = bvh.get_or_insert_with(|| Rc::new(Bvh::build(&self.vertices, &self.indices)));
+
+        Rc::clone(bvh)
+    }
+
+    //Coarser copy for LODs, resolution is the number of cells along the longest axis
+    pub fn simplified(&self, renderer: &Renderer, resolution: u32) -> Mesh {
+        let (vertices, indices) = simplify::simplify(&self.vertices, &self.indices, resolution);
+
+        let mut mesh = Mesh::new(
+            renderer,
+            vertices,
+            indices,
+            &format!("{} lod {}", self.name, resolution),
+        );
+        mesh.material = self.material;
+
+        mesh
+    }
+
+    // PROCEDURAL ---------------------------------------------------
+
+    pub fn cube(renderer: &Renderer, half_extents: na::Vector3<f32>) -> Mesh {
+        let (vertices, indices) = procedural::cube(half_extents);
+        Mesh::new(renderer, vertices, indices, "cube")
+    }
+
+    pub fn uv_sphere(renderer: &Renderer, radius: f32, sectors: u32, stacks: u32) -> Mesh {
+        let (vertices, indices) = procedural::uv_sphere(radius, sectors, stacks);
+        Mesh::new(renderer, vertices, indices, "uv sphere")
+    }
+
+    pub fn icosphere(renderer: &Renderer, radius: f32, subdivisions: u32) -> Mesh {
+        let (vertices, indices) = procedural::icosphere(radius, subdivisions);
+        Mesh::new(renderer, vertices, indices, "icosphere")
+    }
+
+    pub fn plane(renderer: &Renderer, width: f32, depth: f32) -> Mesh {
+        let (vertices, indices) = procedural::plane(width, depth);
+        Mesh::new(renderer, vertices, indices, "plane")
+    }
+
+    pub fn grid(renderer: &Renderer, width: f32, depth: f32, columns: u32, rows: u32) -> Mesh {
+        let (vertices, indices) = procedural::grid(width, depth, columns, rows);
+        Mesh::new(renderer, vertices, indices, "grid")
+    }
+
+    pub fn cylinder(renderer: &Renderer, radius: f32, height: f32, sectors: u32) -> Mesh {
+        let (vertices, indices) = procedural::cylinder(radius, height, sectors);
+        Mesh::new(renderer, vertices, indices, "cylinder")
+    }
+
+    pub fn cone(renderer: &Renderer, radius: f32, height: f32, sectors: u32) -> Mesh {
+        let (vertices, indices) = procedural::cone(radius, height, sectors);
+        Mesh::new(renderer, vertices, indices, "cone")
+    }
+
+    pub fn capsule(
+        renderer: &Renderer,
+        radius: f32,
+        height: f32,
+        sectors: u32,
+        rings: u32,
+    ) -> Mesh {
+        let (vertices, indices) = procedural::capsule(radius, height, sectors, rings);
+        Mesh::new(renderer, vertices, indices, "capsule")
+    }
+
+    pub fn torus(
+        renderer: &Renderer,
+        major_radius: f32,
+        minor_radius: f32,
+        major_segments: u32,
+        minor_segments: u32,
+    ) -> Mesh {
+        let (vertices, indices) =
+            procedural::torus(major_radius, minor_radius, major_segments, minor_segments);
+        Mesh::new(renderer, vertices, indices, "torus")
     }
 }
@@ -1,21 +1,47 @@
+mod bounds;
 mod camera;
+mod camera_controller;
 mod depth_buffer;
+mod gpu_culling;
 mod instance;
+mod instance_buffer;
 mod light;
+mod lod;
 mod material;
 mod mesh;
 mod object;
+mod procedural;
+mod projection;
+mod render_target;
+mod simplify;
 mod texture;
 mod vertex;
+mod viewport;
 
-pub use camera::Camera;
-pub use instance::Instance;
+pub use bounds::{Aabb, BoundingSphere, Frustum, Plane};
+pub use camera::{Camera, CameraStatus};
+pub use camera_controller::{
+    CameraController, FirstPersonController, FlyController, KeyBindings, KeyboardController,
+    OrbitController,
+};
+pub use gpu_culling::{CulledView, GpuCuller};
+pub use instance::{
+    AttributeFormat, Instance, InstanceData, InstanceId, InstanceLayout, InstanceUniform,
+    FIRST_INSTANCE_LOCATION,
+};
+pub use instance_buffer::InstanceBuffer;
 pub use light::Light;
-pub use material::Material;
+pub use lod::{LodChain, LodLevel, LodMetric};
+pub use material::{AlphaMode, Material};
 pub use mesh::Mesh;
-pub use object::Object;
-pub use vertex::Vertex;
+pub use object::{Object, ObjectId, DEFAULT_SHADER};
+pub use projection::Projection;
+pub use render_target::RenderTarget;
+pub use texture::Texture;
+pub use vertex::{SkinnedVertex, Vertex};
+pub use viewport::{ClearPolicy, Viewport, ViewportRect};
 
+pub(crate) use camera::{CameraUniform, OPENGL_TO_WGPU_MATRIX};
 pub(crate) use depth_buffer::DepthBufferTexture;
 
 pub trait Primitive {
@@ -1,24 +1,55 @@
 use crate::rot_primitives::mesh::Mesh;
-use crate::rot_primitives::{Camera, Instance, Material, Vertex};
+use crate::rot_primitives::{
+    AlphaMode, BoundingSphere, CulledView, GpuCuller, Instance, InstanceBuffer, InstanceId,
+    LodChain, LodMetric, Material, Vertex,
+};
 use crate::Renderer;
-use std::error::Error;
+use std::collections::HashMap;
 use std::path::Path;
-use wgpu::util::DeviceExt;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU32, Ordering};
 
+use crate::rot_debug_draw::{self, RenderDebugMode};
 use crate::rot_pipeline::{Pipeline, PipelineBuilder};
-use log::Level::Info;
+use crate::rot_render_queue::DrawCommand;
 use nalgebra as na;
 
+//textured, tinted by the instances, drawn when no shader is given
+pub const DEFAULT_SHADER: &str = "cube";
+
+static NEXT_OBJECT_ID: AtomicU32 = AtomicU32::new(0);
+
+//Unique for the whole run, tells the instance ids of different objects apart
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct ObjectId(u32);
+
+impl ObjectId {
+    pub fn raw(&self) -> u32 {
+        self.0
+    }
+}
+
 pub struct Object {
     pub name: String,
+    id: ObjectId,
 
     pub meshes: Vec<Mesh>,
     pub materials: Vec<Material>,
 
-    pub instances: Vec<Instance>,
-    pub instance_buffer: wgpu::Buffer,
+    //dense, instance_ids[i] is the id of instances[i]
+    instances: Vec<Instance>,
+    instance_ids: Vec<InstanceId>,
+    instance_indices: HashMap<InstanceId, usize>,
+    next_instance_id: u32,
+    instance_buffer: InstanceBuffer,
 
     pub pipelines: Vec<Pipeline>,
+
+    //union of every mesh, in model space
+    pub bounding_sphere: BoundingSphere,
+    pub frustum_culling: bool,
+    gpu_culler: Option<GpuCuller>,
+    pub lod: Option<LodChain>,
 }
 
 impl Object {
@@ -31,27 +62,19 @@ impl Object {
         for mat in obj_materials {
             let diffuse_path = mat.diffuse_texture;
 
-            materials.push(Material::build(
-                containing_folder.join(diffuse_path),
-                renderer,
-                &mat.name,
-            ));
-        }
+            let mut material =
+                Material::build(containing_folder.join(diffuse_path), renderer, &mat.name);
 
-        let pipeline_buider = PipelineBuilder {
-            name: format!("{} pipeline", name).as_str(),
-            uniform_material: true,
-            uniform_camera: true,
-            uniform_light: false,
-            vertex_shader_path: format!("shaders/{}.vert.spv", name).as_str(),
-            fragment_shader_path: format!("shaders/{}.frag.spv", name).as_str(),
-            vertex_buffer_layout: vec![Vertex::desc(), Instance::desc()],
-        };
+            if mat.dissolve < 1.0 {
+                material.set_dissolve(renderer, mat.dissolve);
+            }
+            //the dissolve map itself isn't sampled, it only flags the material as blended
+            if !mat.dissolve_texture.is_empty() {
+                material.set_alpha_mode(renderer, AlphaMode::Blend);
+            }
 
-        let pipelines = materials
-            .iter()
-            .map(|material| Pipeline::new(renderer, pipeline_buider))
-            .collect::<Vec<_>>();
+            materials.push(material);
+        }
 
         let mut meshes: Vec<Mesh> = Vec::new();
         for model in obj_models {
@@ -73,101 +96,639 @@ impl Object {
                 })
             }
 
-            meshes.push(Mesh::new(
-                renderer,
-                vertices,
-                model.mesh.indices,
-                &model.name,
-            ))
-        }
-
-        let instances = Instance::default();
-        let instance_buffer =
-            renderer
-                .device
-                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some(&format!("{} Index Buffer", name)),
-                    contents: bytemuck::cast_slice(&[instances.uniform.model]),
-                    usage: wgpu::BufferUsage::VERTEX | wgpu::BufferUsage::COPY_DST,
-                });
+            let mut mesh = Mesh::new(renderer, vertices, model.mesh.indices, &model.name);
+            mesh.material = model.mesh.material_id.unwrap_or(0);
+
+            meshes.push(mesh)
+        }
+
+        //the shaders are named after the object
+        Object::from_meshes(renderer, meshes, materials, name, name)
+    }
+
+    //Wraps a generated mesh, like Mesh::cube, and a material into an Object
+    pub fn from_mesh(renderer: &Renderer, mesh: Mesh, material: Material, name: &str) -> Self {
+        Object::from_meshes(renderer, vec![mesh], vec![material], name, DEFAULT_SHADER)
+    }
+
+    //shader is the name of the shaders/<shader>.vert/.frag pair,
+    //every mesh.material must index into materials
+    pub fn from_meshes(
+        renderer: &Renderer,
+        meshes: Vec<Mesh>,
+        materials: Vec<Material>,
+        name: &str,
+        shader: &str,
+    ) -> Self {
+        let pipeline_name = format!("{} pipeline", name);
+        let vertex_shader_path = format!("shaders/{}.vert.spv", shader);
+        let fragment_shader_path = format!("shaders/{}.frag.spv", shader);
+        let instance_layout = Instance::desc();
+        let pipeline_buider = PipelineBuilder {
+            name: pipeline_name.as_str(),
+            uniform_material: true,
+            uniform_camera: true,
+            uniform_light: false,
+            vertex_shader_path: vertex_shader_path.as_str(),
+            fragment_shader_path: fragment_shader_path.as_str(),
+            vertex_buffer_layout: vec![Vertex::desc(), instance_layout.desc()],
+            ..PipelineBuilder::default()
+        };
+
+        //identical builders are served by the renderer pipeline cache
+        let pipelines = materials
+            .iter()
+            .map(|material| match material.alpha_mode {
+                AlphaMode::Opaque | AlphaMode::Mask(_) => Pipeline::new(renderer, &pipeline_buider),
+                AlphaMode::Blend => Pipeline::new(
+                    renderer,
+                    &pipeline_buider
+                        .clone()
+                        .with_alpha_blending()
+                        .with_depth(true, false),
+                ),
+            })
+            .collect::<Vec<_>>();
+
+        let bounding_sphere = meshes
+            .iter()
+            .map(|mesh| mesh.bounding_sphere)
+            .fold(
+                None,
+                |merged: Option<BoundingSphere>, sphere| match merged {
+                    None => Some(sphere),
+                    Some(merged) => Some(merged.merged(&sphere)),
+                },
+            )
+            .unwrap_or_else(|| BoundingSphere::new(na::Point3::origin(), 0.0));
+
+        let instance_buffer = InstanceBuffer::new(renderer, name, 1);
 
         Self {
             name: name.to_string(),
+            id: ObjectId(NEXT_OBJECT_ID.fetch_add(1, Ordering::Relaxed)),
 
             meshes,
             materials,
-            instances: vec![],
+            instances: Vec::new(),
+            instance_ids: Vec::new(),
+            instance_indices: HashMap::new(),
+            next_instance_id: 0,
             instance_buffer,
             pipelines,
+            bounding_sphere,
+            frustum_culling: true,
+            gpu_culler: None,
+            lod: None,
+        }
+    }
+
+    //Updates the dirty instances and uploads only their ranges, call it before draw
+    pub fn flush_instances(&mut self, renderer: &Renderer) {
+        if !self.instance_buffer.is_dirty() {
+            return;
+        }
+
+        if self.instance_buffer.reserve(renderer, self.instances.len()) {
+            self.instance_buffer.mark_dirty(0..self.instances.len());
+
+            if let Some(lod) = self.lod.as_mut() {
+                lod.resize(self.instance_buffer.capacity());
+            }
+        }
+
+        for range in self.instance_buffer.dirty_ranges().iter() {
+            let end = range.end.min(self.instances.len());
+            let start = range.start.min(end);
+
+            for instance in self.instances[start..end].iter_mut() {
+                instance.update();
+            }
+        }
+
+        if let Some(culler) = self.gpu_culler.as_mut() {
+            culler.upload_instances(
+                renderer,
+                &self.instances,
+                self.instance_buffer.dirty_ranges(),
+            );
         }
+
+        self.instance_buffer.flush(renderer, &self.instances);
     }
 
-    //potential to paralelize
-    fn update_instance(&mut self, renderer: &Renderer) {
-        self.instances.iter_mut().map(|instance| instance.update());
+    //Culls in a compute shader and draws with draw_indexed_indirect.
+    //Transparent meshes are then sorted as a whole instead of per instance
+    pub fn enable_gpu_culling(&mut self, renderer: &Renderer) {
+        let index_counts = self.meshes.iter().map(|mesh| mesh.len()).collect();
+        let mut culler = GpuCuller::new(renderer, &self.name, index_counts, self.instances.len());
+        culler.upload_instances(renderer, &self.instances, &[0..self.instances.len()]);
 
-        renderer.queue.write_buffer(
-            &self.instance_buffer,
-            0,
-            bytemuck::cast_slice(
-                &self
-                    .instances
-                    .iter()
-                    .map(|isnst| isnst.uniform.model)
-                    .collect::<Vec<_>>()
-                    .as_slice(),
-            ),
-        )
+        self.gpu_culler = Some(culler);
+    }
+
+    pub fn disable_gpu_culling(&mut self) {
+        self.gpu_culler = None;
     }
 
-    pub fn add_instance(&mut self, renderer: &Renderer, isometries: Vec<na::Isometry3<f32>>) {
-        self.instances = isometries
+    //Ignored while GPU culling is enabled
+    pub fn set_lod(&mut self, metric: LodMetric, hysteresis: f32) {
+        self.lod = Some(LodChain::new(metric, hysteresis));
+    }
+
+    //Levels must be added from the finest to the coarsest
+    pub fn add_lod_level(&mut self, meshes: Vec<Mesh>, threshold: f32) {
+        let capacity = self.instance_buffer.capacity();
+        self.lod
+            .get_or_insert_with(|| LodChain::new(LodMetric::Distance, 0.0))
+            .push_level(meshes, threshold, capacity);
+    }
+
+    //Builds every level from the base meshes, each entry is (threshold, resolution)
+    pub fn generate_lods(
+        &mut self,
+        renderer: &Renderer,
+        metric: LodMetric,
+        hysteresis: f32,
+        levels: &[(f32, u32)],
+    ) {
+        self.set_lod(metric, hysteresis);
+
+        for &(threshold, resolution) in levels.iter() {
+            let meshes = self
+                .meshes
+                .iter()
+                .map(|mesh| mesh.simplified(renderer, resolution))
+                .collect();
+
+            self.add_lod_level(meshes, threshold);
+        }
+    }
+
+    pub fn add_instance(&mut self, instance: Instance) -> InstanceId {
+        let id = InstanceId(self.next_instance_id);
+        self.next_instance_id += 1;
+
+        let index = self.instances.len();
+        self.instances.push(instance);
+        self.instance_ids.push(id);
+        self.instance_indices.insert(id, index);
+        self.instance_buffer.mark_dirty(index..index + 1);
+
+        id
+    }
+
+    pub fn add_instances(&mut self, instances: Vec<Instance>) -> Vec<InstanceId> {
+        instances
+            .into_iter()
+            .map(|instance| self.add_instance(instance))
+            .collect()
+    }
+
+    //The last instance takes the removed one place, so only that slot gets rewritten
+    pub fn remove_instance(&mut self, id: InstanceId) -> Option<Instance> {
+        let index = self.instance_indices.remove(&id)?;
+
+        let instance = self.instances.swap_remove(index);
+        self.instance_ids.swap_remove(index);
+        if let Some(&moved) = self.instance_ids.get(index) {
+            self.instance_indices.insert(moved, index);
+        }
+        self.instance_buffer.mark_dirty(index..index + 1);
+
+        if let Some(lod) = self.lod.as_ref() {
+            lod.forget(id);
+        }
+
+        Some(instance)
+    }
+
+    pub fn clear_instances(&mut self) {
+        if let Some(lod) = self.lod.as_ref() {
+            for &id in self.instance_ids.iter() {
+                lod.forget(id);
+            }
+        }
+
+        self.instance_buffer.mark_dirty(0..self.instances.len());
+        self.instances.clear();
+        self.instance_ids.clear();
+        self.instance_indices.clear();
+    }
+
+    pub fn instance(&self, id: InstanceId) -> Option<&Instance> {
+        self.instance_indices
+            .get(&id)
+            .map(|&index| &self.instances[index])
+    }
+
+    //Flags the instance as dirty, it is recomputed and uploaded on the next flush
+    pub fn instance_mut(&mut self, id: InstanceId) -> Option<&mut Instance> {
+        let index = *self.instance_indices.get(&id)?;
+        self.instance_buffer.mark_dirty(index..index + 1);
+
+        Some(&mut self.instances[index])
+    }
+
+    pub fn set_isometry(&mut self, id: InstanceId, isometry: na::Isometry3<f32>) -> bool {
+        match self.instance_mut(id) {
+            Some(instance) => {
+                instance.isometry = isometry;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn id(&self) -> ObjectId {
+        self.id
+    }
+
+    pub fn instances(&self) -> &[Instance] {
+        self.instances.as_slice()
+    }
+
+    pub fn instance_ids(&self) -> &[InstanceId] {
+        self.instance_ids.as_slice()
+    }
+
+    pub fn instance_count(&self) -> usize {
+        self.instances.len()
+    }
+
+    //Replaces every instance with these isometries
+    pub fn set_instance(
+        &mut self,
+        renderer: &Renderer,
+        isometries: Vec<na::Isometry3<f32>>,
+    ) -> Vec<InstanceId> {
+        let instances = isometries
             .iter()
             .map(|&isometry| Instance::new(isometry))
             .collect();
 
-        self.instance_buffer =
-            renderer
-                .device
-                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some(&format!("{} Instance Buffer", &self.name)),
-                    contents: bytemuck::cast_slice(
-                        self.instances
-                            .iter()
-                            .map(|instance| instance.uniform.model)
-                            .collect::<Vec<_>>()
-                            .as_slice(),
+        self.set_instances(renderer, instances)
+    }
+
+    //for instances carrying a scale, tint or custom data
+    pub fn set_instances(
+        &mut self,
+        renderer: &Renderer,
+        instances: Vec<Instance>,
+    ) -> Vec<InstanceId> {
+        self.clear_instances();
+        let ids = self.add_instances(instances);
+        self.flush_instances(renderer);
+
+        ids
+    }
+
+    pub fn draw(&self, renderer: &mut Renderer) {
+        let camera_bind_group = match renderer.camera_bind_group() {
+            Some(bind_group) => bind_group,
+            None => return,
+        };
+        let camera_position = renderer.camera_position();
+
+        if let (Some(culler), Some(frustum)) = (self.gpu_culler.as_ref(), renderer.camera_frustum())
+        {
+            let (compute, culled) = culler.prepare(renderer, frustum, &self.bounding_sphere);
+            renderer.submit_compute(compute);
+            self.draw_indirect(renderer, &culled, camera_bind_group, camera_position);
+            return;
+        }
+
+        let visible = self.cull(renderer);
+        if visible.is_empty() {
+            return;
+        }
+
+        match self.lod.as_ref() {
+            Some(lod) => {
+                let buckets = lod.bucket(renderer, &visible, self.bounding_sphere.radius);
+                for (level, instances) in buckets.iter().enumerate() {
+                    let (meshes, instance_buffer) = match level {
+                        0 => (&self.meshes, self.instance_buffer.culled_buffer(renderer)),
+                        _ => (
+                            &lod.levels[level - 1].meshes,
+                            lod.instance_buffer(renderer, level),
+                        ),
+                    };
+
+                    self.submit_meshes(
+                        renderer,
+                        meshes,
+                        &instance_buffer,
+                        instances,
+                        true,
+                        &camera_bind_group,
+                        camera_position,
+                    );
+                }
+            }
+            None => {
+                let instances = visible
+                    .iter()
+                    .map(|(_, instance)| *instance)
+                    .collect::<Vec<_>>();
+
+                //nothing culled, the persistent buffer already holds them in order
+                let culled = instances.len() < self.instances.len();
+                let instance_buffer = match culled {
+                    true => self.instance_buffer.culled_buffer(renderer),
+                    false => Rc::clone(&self.instance_buffer.buffer),
+                };
+
+                self.submit_meshes(
+                    renderer,
+                    &self.meshes,
+                    &instance_buffer,
+                    &instances,
+                    culled,
+                    &camera_bind_group,
+                    camera_position,
+                );
+            }
+        }
+    }
+
+    fn submit_meshes(
+        &self,
+        renderer: &mut Renderer,
+        meshes: &[Mesh],
+        instance_buffer: &Rc<wgpu::Buffer>,
+        instances: &[&Instance],
+        upload: bool,
+        camera_bind_group: &Rc<wgpu::BindGroup>,
+        camera_position: na::Point3<f32>,
+    ) {
+        if instances.is_empty() {
+            return;
+        }
+
+        //the instances are packed at the start of the instance buffer, its own for each view
+        if upload {
+            renderer.queue.write_buffer(
+                instance_buffer,
+                0,
+                bytemuck::cast_slice(
+                    instances
+                        .iter()
+                        .map(|instance| instance.uniform)
+                        .collect::<Vec<_>>()
+                        .as_slice(),
+                ),
+            );
+        }
+
+        let debug_mode = renderer.debug_mode();
+        let debug_view = rot_debug_draw::view_pipeline(renderer, debug_mode);
+        let wireframe = match debug_mode {
+            RenderDebugMode::Wireframe => Some(rot_debug_draw::wireframe_pipeline(renderer)),
+            _ => None,
+        };
+        if debug_mode == RenderDebugMode::VertexNormals {
+            self.draw_vertex_normals(
+                renderer,
+                meshes,
+                instance_buffer,
+                instances.len() as u32,
+                camera_bind_group,
+            );
+        }
+
+        for mesh in meshes.iter() {
+            let material = &self.materials[mesh.material];
+            let pipeline = &self.pipelines[mesh.material];
+            let all_instances = 0..instances.len() as u32;
+
+            if let Some(view) = debug_view.as_ref() {
+                renderer.submit_opaque(DrawCommand {
+                    pipeline: view.clone(),
+                    bind_groups: vec![Rc::clone(camera_bind_group)],
+                    vertex_buffers: vec![
+                        Rc::clone(&mesh.vertex_buffer),
+                        Rc::clone(instance_buffer),
+                    ],
+                    index_buffer: Some(Rc::clone(&mesh.index_buffer)),
+                    indices: 0..mesh.size as u32,
+                    instances: all_instances,
+                    indirect: None,
+                    depth: 0.0,
+                });
+                continue;
+            }
+
+            if let Some((wireframe, native)) = wireframe.as_ref() {
+                let (vertex_buffer, index_buffer) = match native {
+                    true => (
+                        Rc::clone(&mesh.vertex_buffer),
+                        Some(Rc::clone(&mesh.index_buffer)),
                     ),
-                    usage: wgpu::BufferUsage::VERTEX | wgpu::BufferUsage::COPY_DST,
+                    false => (mesh.unindexed_buffer(renderer), None),
+                };
+
+                renderer.submit_debug(DrawCommand {
+                    pipeline: wireframe.clone(),
+                    bind_groups: vec![Rc::clone(camera_bind_group)],
+                    vertex_buffers: vec![vertex_buffer, Rc::clone(instance_buffer)],
+                    index_buffer,
+                    indices: 0..mesh.size as u32,
+                    instances: all_instances.clone(),
+                    indirect: None,
+                    depth: 0.0,
                 });
+            }
+
+            let command = |instances: std::ops::Range<u32>, depth: f32| DrawCommand {
+                pipeline: pipeline.clone(),
+                bind_groups: vec![
+                    Rc::clone(&material.bind_group),
+                    Rc::clone(camera_bind_group),
+                ],
+                vertex_buffers: vec![Rc::clone(&mesh.vertex_buffer), Rc::clone(instance_buffer)],
+                index_buffer: Some(Rc::clone(&mesh.index_buffer)),
+                indices: 0..mesh.size as u32,
+                instances,
+                indirect: None,
+                depth,
+            };
+
+            if material.is_transparent() {
+                //one command per instance so they sort against every other transparent draw
+                for (index, instance) in instances.iter().enumerate() {
+                    let position = na::Point3::from(instance.isometry.translation.vector);
+                    let depth = na::distance(&camera_position, &position);
+                    let index = index as u32;
+
+                    renderer.submit_transparent(command(index..index + 1, depth));
+                }
+            } else {
+                renderer.submit_opaque(command(all_instances, 0.0));
+            }
+        }
     }
 
-    pub fn set_instance(&mut self, renderer: &Renderer, isometries: Vec<na::Isometry3<f32>>) {
-        self.instances.clear();
+    //the lines are built once per mesh, the instance buffer places them
+    fn draw_vertex_normals(
+        &self,
+        renderer: &mut Renderer,
+        meshes: &[Mesh],
+        instance_buffer: &Rc<wgpu::Buffer>,
+        instance_count: u32,
+        camera_bind_group: &Rc<wgpu::BindGroup>,
+    ) {
+        let pipeline = rot_debug_draw::vertex_normals_pipeline(renderer);
 
-        self.add_instance(renderer, isometries);
+        for mesh in meshes.iter() {
+            renderer.submit_debug(DrawCommand {
+                pipeline: pipeline.clone(),
+                bind_groups: vec![Rc::clone(camera_bind_group)],
+                vertex_buffers: vec![
+                    mesh.normal_lines_buffer(renderer),
+                    Rc::clone(instance_buffer),
+                ],
+                index_buffer: None,
+                indices: 0..mesh.normal_lines_len(),
+                instances: 0..instance_count,
+                indirect: None,
+                depth: 0.0,
+            });
+        }
     }
 
-    pub fn draw(&self, renderer: &mut Renderer) {
-        let render_pass = renderer.get_render_pass();
-        render_pass.set_pipeline(&self.pipelines[0].render_pipeline);
-        render_pass.set_bind_group(0, &object.materials[0].bind_group, &[]);
-        render_pass.set_bind_group(1, &self.camera.unwrap().bind_group, &[]);
-        render_pass.set_vertex_buffer(0, object.meshes[0].vertex_buffer.slice(..));
-        render_pass.set_vertex_buffer(1, object.instance_buffer.slice(..));
-        render_pass.set_index_buffer(
-            object.meshes[0].index_buffer.slice(..),
-            wgpu::IndexFormat::Uint32,
-        );
-
-        render_pass.draw_indexed(
-            0..object.meshes[0].size as _,
-            0,
-            0..object.instances.len() as _,
-        );
+    fn draw_indirect(
+        &self,
+        renderer: &mut Renderer,
+        culled: &CulledView,
+        camera_bind_group: Rc<wgpu::BindGroup>,
+        camera_position: na::Point3<f32>,
+    ) {
+        let debug_mode = renderer.debug_mode();
+        let debug_view = rot_debug_draw::view_pipeline(renderer, debug_mode);
+        let wireframe = match debug_mode {
+            RenderDebugMode::Wireframe => Some(rot_debug_draw::wireframe_pipeline(renderer)),
+            _ => None,
+        };
+        if debug_mode == RenderDebugMode::VertexNormals {
+            //the CPU doesn't know which ones survived, so every instance gets them
+            self.draw_vertex_normals(
+                renderer,
+                &self.meshes,
+                &self.instance_buffer.buffer,
+                self.instances.len() as u32,
+                &camera_bind_group,
+            );
+        }
+
+        for (draw, mesh) in self.meshes.iter().enumerate() {
+            let material = &self.materials[mesh.material];
+            let indirect = Some((
+                Rc::clone(&culled.indirect_buffer),
+                GpuCuller::indirect_offset(draw),
+            ));
+
+            //the barycentric fallback needs non indexed draws, which the indirect
+            //arguments can't describe, so it is skipped here
+            let (pipeline, bind_groups) = match (debug_view.as_ref(), wireframe.as_ref()) {
+                (Some(view), _) => (view, vec![Rc::clone(&camera_bind_group)]),
+                (None, Some((wireframe, true))) => {
+                    renderer.submit_debug(DrawCommand {
+                        pipeline: wireframe.clone(),
+                        bind_groups: vec![Rc::clone(&camera_bind_group)],
+                        vertex_buffers: vec![
+                            Rc::clone(&mesh.vertex_buffer),
+                            Rc::clone(&culled.visible_buffer),
+                        ],
+                        index_buffer: Some(Rc::clone(&mesh.index_buffer)),
+                        indices: 0..mesh.size as u32,
+                        instances: 0..0,
+                        indirect: indirect.clone(),
+                        depth: 0.0,
+                    });
+
+                    (
+                        &self.pipelines[mesh.material],
+                        vec![
+                            Rc::clone(&material.bind_group),
+                            Rc::clone(&camera_bind_group),
+                        ],
+                    )
+                }
+                _ => (
+                    &self.pipelines[mesh.material],
+                    vec![
+                        Rc::clone(&material.bind_group),
+                        Rc::clone(&camera_bind_group),
+                    ],
+                ),
+            };
+
+            let command = DrawCommand {
+                pipeline: pipeline.clone(),
+                bind_groups,
+                vertex_buffers: vec![
+                    Rc::clone(&mesh.vertex_buffer),
+                    Rc::clone(&culled.visible_buffer),
+                ],
+                index_buffer: Some(Rc::clone(&mesh.index_buffer)),
+                indices: 0..mesh.size as u32,
+                instances: 0..0,
+                indirect,
+                depth: na::distance(&camera_position, &self.bounding_sphere.center),
+            };
+
+            if material.is_transparent() && debug_view.is_none() {
+                renderer.submit_transparent(command);
+            } else {
+                renderer.submit_opaque(command);
+            }
+        }
+    }
+
+    //every instance, unculled and without LODs, so gl_InstanceIndex is the index into instances()
+    pub(crate) fn pick_commands(
+        &self,
+        pipeline: &Pipeline,
+        camera_bind_group: &Rc<wgpu::BindGroup>,
+        pick_bind_group: &Rc<wgpu::BindGroup>,
+    ) -> Vec<DrawCommand> {
+        if self.instances.is_empty() {
+            return Vec::new();
+        }
+
+        self.meshes
+            .iter()
+            .map(|mesh| DrawCommand {
+                pipeline: pipeline.clone(),
+                bind_groups: vec![Rc::clone(camera_bind_group), Rc::clone(pick_bind_group)],
+                vertex_buffers: vec![
+                    Rc::clone(&mesh.vertex_buffer),
+                    Rc::clone(&self.instance_buffer.buffer),
+                ],
+                index_buffer: Some(Rc::clone(&mesh.index_buffer)),
+                indices: 0..mesh.size as u32,
+                instances: 0..self.instances.len() as u32,
+                indirect: None,
+                depth: 0.0,
+            })
+            .collect()
+    }
+
+    fn cull(&self, renderer: &Renderer) -> Vec<(InstanceId, &Instance)> {
+        let instances = self.instance_ids.iter().copied().zip(self.instances.iter());
+
+        match renderer.camera_frustum() {
+            Some(frustum) if self.frustum_culling => instances
+                .filter(|(_, instance)| {
+                    frustum.contains_sphere(&instance.transform_sphere(&self.bounding_sphere))
+                })
+                .collect(),
+            _ => instances.collect(),
+        }
     }
 
     pub fn on_update(&mut self, renderer: &Renderer) {
-        self.update_instance(renderer);
+        self.flush_instances(renderer);
     }
 }
@@ -0,0 +1,544 @@
+use crate::rot_primitives::Vertex;
+use nalgebra as na;
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+//Every generator is centered on the origin, counter clockwise seen from outside
+//and uses the top left corner as the UV origin, like the textures
+
+fn vertex(position: na::Vector3<f32>, tex_coords: [f32; 2], normal: na::Vector3<f32>) -> Vertex {
+    Vertex {
+        position: position.into(),
+        tex_coords,
+        normal: normal.into(),
+    }
+}
+
+//Quads between consecutive rows of columns + 1 vertices, row i + 1 under row i
+//and column j + 1 right of column j when seen from outside.
+//With poles the first and last rows collapse into a point, so half of their quads has no area
+fn stitch_rows(indices: &mut Vec<u32>, first: u32, rows: u32, columns: u32, poles: bool) {
+    for i in 0..rows - 1 {
+        for j in 0..columns {
+            let a = first + i * (columns + 1) + j;
+            let b = a + columns + 1;
+
+            if !(poles && i == rows - 2) {
+                indices.extend_from_slice(&[a, b, b + 1]);
+            }
+            if !(poles && i == 0) {
+                indices.extend_from_slice(&[a, b + 1, a + 1]);
+            }
+        }
+    }
+}
+
+//point of a ring around the Y axis, angle 0 faces +Z and grows towards +X
+fn ring(angle: f32) -> na::Vector3<f32> {
+    na::Vector3::new(angle.sin(), 0.0, angle.cos())
+}
+
+pub fn cube(half_extents: na::Vector3<f32>) -> (Vec<Vertex>, Vec<u32>) {
+    //normal, right, up with right x up = normal
+    let faces = [
+        (na::Vector3::x(), -na::Vector3::z(), na::Vector3::y()),
+        (-na::Vector3::x(), na::Vector3::z(), na::Vector3::y()),
+        (na::Vector3::y(), na::Vector3::x(), -na::Vector3::z()),
+        (-na::Vector3::y(), na::Vector3::x(), na::Vector3::z()),
+        (na::Vector3::z(), na::Vector3::x(), na::Vector3::y()),
+        (-na::Vector3::z(), -na::Vector3::x(), na::Vector3::y()),
+    ];
+    let corners: [(f32, f32); 4] = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)];
+
+    let mut vertices = Vec::with_capacity(24);
+    let mut indices = Vec::with_capacity(36);
+
+    for (normal, right, up) in faces.iter() {
+        let first = vertices.len() as u32;
+
+        for &(x, y) in corners.iter() {
+            let position = (normal + right * x + up * y).component_mul(&half_extents);
+            let tex_coords = [(x + 1.0) * 0.5, (1.0 - y) * 0.5];
+
+            vertices.push(vertex(position, tex_coords, *normal));
+        }
+
+        indices.extend_from_slice(&[first, first + 1, first + 2, first, first + 2, first + 3]);
+    }
+
+    (vertices, indices)
+}
+
+pub fn uv_sphere(radius: f32, sectors: u32, stacks: u32) -> (Vec<Vertex>, Vec<u32>) {
+    let sectors = sectors.max(3);
+    let stacks = stacks.max(2);
+
+    let mut vertices = Vec::with_capacity(((sectors + 1) * (stacks + 1)) as usize);
+    for i in 0..=stacks {
+        let v = i as f32 / stacks as f32;
+        let phi = PI * v;
+
+        for j in 0..=sectors {
+            let u = j as f32 / sectors as f32;
+            let normal = ring(2.0 * PI * u) * phi.sin() + na::Vector3::y() * phi.cos();
+
+            vertices.push(vertex(normal * radius, [u, v], normal));
+        }
+    }
+
+    let mut indices = Vec::with_capacity((sectors * stacks * 6) as usize);
+    stitch_rows(&mut indices, 0, stacks + 1, sectors, true);
+
+    (vertices, indices)
+}
+
+pub fn icosphere(radius: f32, subdivisions: u32) -> (Vec<Vertex>, Vec<u32>) {
+    let t = (1.0 + 5.0_f32.sqrt()) * 0.5;
+    let mut positions = vec![
+        na::Vector3::new(-1.0, t, 0.0),
+        na::Vector3::new(1.0, t, 0.0),
+        na::Vector3::new(-1.0, -t, 0.0),
+        na::Vector3::new(1.0, -t, 0.0),
+        na::Vector3::new(0.0, -1.0, t),
+        na::Vector3::new(0.0, 1.0, t),
+        na::Vector3::new(0.0, -1.0, -t),
+        na::Vector3::new(0.0, 1.0, -t),
+        na::Vector3::new(t, 0.0, -1.0),
+        na::Vector3::new(t, 0.0, 1.0),
+        na::Vector3::new(-t, 0.0, -1.0),
+        na::Vector3::new(-t, 0.0, 1.0),
+    ]
+    .into_iter()
+    .map(|position| position.normalize())
+    .collect::<Vec<_>>();
+
+    let mut triangles: Vec<[u32; 3]> = vec![
+        [0, 11, 5],
+        [0, 5, 1],
+        [0, 1, 7],
+        [0, 7, 10],
+        [0, 10, 11],
+        [1, 5, 9],
+        [5, 11, 4],
+        [11, 10, 2],
+        [10, 7, 6],
+        [7, 1, 8],
+        [3, 9, 4],
+        [3, 4, 2],
+        [3, 2, 6],
+        [3, 6, 8],
+        [3, 8, 9],
+        [4, 9, 5],
+        [2, 4, 11],
+        [6, 2, 10],
+        [8, 6, 7],
+        [9, 8, 1],
+    ];
+
+    // SUBDIVIDE ----------------------------------------------------
+    for _ in 0..subdivisions {
+        let mut midpoints: HashMap<(u32, u32), u32> = HashMap::new();
+        let mut midpoint = |a: u32, b: u32| {
+            let key = (a.min(b), a.max(b));
+            *midpoints.entry(key).or_insert_with(|| {
+                let position = (positions[a as usize] + positions[b as usize]).normalize();
+                positions.push(position);
+                (positions.len() - 1) as u32
+            })
+        };
+
+        triangles = triangles
+            .iter()
+            .flat_map(|&[a, b, c]| {
+                let ab = midpoint(a, b);
+                let bc = midpoint(b, c);
+                let ca = midpoint(c, a);
+
+                vec![[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+            })
+            .collect();
+    }
+
+    // UV -----------------------------------------------------------
+    let spherical = |normal: &na::Vector3<f32>| {
+        [
+            0.5 + normal.x.atan2(normal.z) / (2.0 * PI),
+            normal.y.max(-1.0).min(1.0).acos() / PI,
+        ]
+    };
+
+    let mut vertices = positions
+        .iter()
+        .map(|normal| vertex(normal * radius, spherical(normal), *normal))
+        .collect::<Vec<_>>();
+
+    //the poles have no longitude, every triangle around one gets a copy in the middle of its own
+    let is_pole = |vertex: &Vertex| vertex.normal[0].abs() < 1e-6 && vertex.normal[2].abs() < 1e-6;
+
+    //triangles crossing the seam would wrap the whole texture, they get their own copies
+    let mut seam_copies: HashMap<u32, u32> = HashMap::new();
+    let mut indices = Vec::with_capacity(triangles.len() * 3);
+    for triangle in triangles.iter() {
+        let us = triangle
+            .iter()
+            .map(|&index| &vertices[index as usize])
+            .filter(|vertex| !is_pole(vertex))
+            .map(|vertex| vertex.tex_coords[0])
+            .collect::<Vec<_>>();
+        let crosses_seam = us.iter().cloned().fold(f32::MIN, f32::max)
+            - us.iter().cloned().fold(f32::MAX, f32::min)
+            > 0.5;
+        let unwrapped = |u: f32| if crosses_seam && u < 0.5 { u + 1.0 } else { u };
+
+        for &index in triangle.iter() {
+            let vertex = vertices[index as usize];
+            if is_pole(&vertex) {
+                let mut copy = vertex;
+                copy.tex_coords[0] =
+                    us.iter().map(|&u| unwrapped(u)).sum::<f32>() / us.len() as f32;
+                vertices.push(copy);
+                indices.push((vertices.len() - 1) as u32);
+            } else if crosses_seam && vertex.tex_coords[0] < 0.5 {
+                let copy = *seam_copies.entry(index).or_insert_with(|| {
+                    let mut copy = vertex;
+                    copy.tex_coords[0] = unwrapped(copy.tex_coords[0]);
+                    vertices.push(copy);
+                    (vertices.len() - 1) as u32
+                });
+                indices.push(copy);
+            } else {
+                indices.push(index);
+            }
+        }
+    }
+
+    (vertices, indices)
+}
+
+//flat on XZ facing +Y, columns along X and rows along Z
+pub fn grid(width: f32, depth: f32, columns: u32, rows: u32) -> (Vec<Vertex>, Vec<u32>) {
+    let columns = columns.max(1);
+    let rows = rows.max(1);
+
+    let mut vertices = Vec::with_capacity(((columns + 1) * (rows + 1)) as usize);
+    for i in 0..=rows {
+        let v = i as f32 / rows as f32;
+
+        for j in 0..=columns {
+            let u = j as f32 / columns as f32;
+            let position = na::Vector3::new((u - 0.5) * width, 0.0, (v - 0.5) * depth);
+
+            vertices.push(vertex(position, [u, v], na::Vector3::y()));
+        }
+    }
+
+    let mut indices = Vec::with_capacity((columns * rows * 6) as usize);
+    stitch_rows(&mut indices, 0, rows + 1, columns, false);
+
+    (vertices, indices)
+}
+
+pub fn plane(width: f32, depth: f32) -> (Vec<Vertex>, Vec<u32>) {
+    grid(width, depth, 1, 1)
+}
+
+pub fn cylinder(radius: f32, height: f32, sectors: u32) -> (Vec<Vertex>, Vec<u32>) {
+    let sectors = sectors.max(3);
+    let half_height = height * 0.5;
+
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    for &(y, v) in [(half_height, 0.0), (-half_height, 1.0)].iter() {
+        for j in 0..=sectors {
+            let u = j as f32 / sectors as f32;
+            let normal = ring(2.0 * PI * u);
+            let position = normal * radius + na::Vector3::y() * y;
+
+            vertices.push(vertex(position, [u, v], normal));
+        }
+    }
+    stitch_rows(&mut indices, 0, 2, sectors, false);
+
+    cap(
+        &mut vertices,
+        &mut indices,
+        radius,
+        half_height,
+        sectors,
+        true,
+    );
+    cap(
+        &mut vertices,
+        &mut indices,
+        radius,
+        -half_height,
+        sectors,
+        false,
+    );
+
+    (vertices, indices)
+}
+
+//apex on +Y
+pub fn cone(radius: f32, height: f32, sectors: u32) -> (Vec<Vertex>, Vec<u32>) {
+    let sectors = sectors.max(3);
+    let half_height = height * 0.5;
+    let side_normal = |angle: f32| (ring(angle) * height + na::Vector3::y() * radius).normalize();
+
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+
+    //one apex per sector so each face gets its own normal there
+    for j in 0..sectors {
+        let u = (j as f32 + 0.5) / sectors as f32;
+        let position = na::Vector3::y() * half_height;
+
+        vertices.push(vertex(position, [u, 0.0], side_normal(2.0 * PI * u)));
+    }
+    for j in 0..=sectors {
+        let u = j as f32 / sectors as f32;
+        let position = ring(2.0 * PI * u) * radius - na::Vector3::y() * half_height;
+
+        vertices.push(vertex(position, [u, 1.0], side_normal(2.0 * PI * u)));
+    }
+    for j in 0..sectors {
+        let base = sectors + j;
+        indices.extend_from_slice(&[j, base, base + 1]);
+    }
+
+    cap(
+        &mut vertices,
+        &mut indices,
+        radius,
+        -half_height,
+        sectors,
+        false,
+    );
+
+    (vertices, indices)
+}
+
+//height is the length of the cylinder between both hemispheres
+pub fn capsule(radius: f32, height: f32, sectors: u32, rings: u32) -> (Vec<Vertex>, Vec<u32>) {
+    let sectors = sectors.max(3);
+    let rings = rings.max(1);
+    let half_height = height * 0.5;
+
+    //v follows the arc length of the profile so the texture isn't squashed on the caps
+    let quarter = PI * 0.5 * radius;
+    let length = 2.0 * quarter + height;
+
+    let mut vertices = Vec::new();
+    for &(offset, first_phi, first_arc) in [
+        (half_height, 0.0, 0.0),
+        (-half_height, PI * 0.5, quarter + height),
+    ]
+    .iter()
+    {
+        for i in 0..=rings {
+            let step = i as f32 / rings as f32;
+            let phi = first_phi + PI * 0.5 * step;
+            let v = (first_arc + quarter * step) / length;
+
+            for j in 0..=sectors {
+                let u = j as f32 / sectors as f32;
+                let normal = ring(2.0 * PI * u) * phi.sin() + na::Vector3::y() * phi.cos();
+                let position = normal * radius + na::Vector3::y() * offset;
+
+                vertices.push(vertex(position, [u, v], normal));
+            }
+        }
+    }
+
+    let mut indices = Vec::new();
+    stitch_rows(&mut indices, 0, 2 * (rings + 1), sectors, true);
+
+    (vertices, indices)
+}
+
+//lies on XZ around the Y axis
+pub fn torus(
+    major_radius: f32,
+    minor_radius: f32,
+    major_segments: u32,
+    minor_segments: u32,
+) -> (Vec<Vertex>, Vec<u32>) {
+    let major_segments = major_segments.max(3);
+    let minor_segments = minor_segments.max(3);
+
+    let mut vertices = Vec::new();
+    for i in 0..=major_segments {
+        let u = i as f32 / major_segments as f32;
+        let direction = ring(2.0 * PI * u);
+
+        for j in 0..=minor_segments {
+            let v = j as f32 / minor_segments as f32;
+            let phi = 2.0 * PI * v;
+            let normal = direction * phi.cos() + na::Vector3::y() * phi.sin();
+            let position = direction * major_radius + normal * minor_radius;
+
+            vertices.push(vertex(position, [u, 1.0 - v], normal));
+        }
+    }
+
+    //here segment i + 1 lies right of i and j + 1 above j
+    let mut indices = Vec::new();
+    for i in 0..major_segments {
+        for j in 0..minor_segments {
+            let a = i * (minor_segments + 1) + j;
+            let right = a + minor_segments + 1;
+
+            indices.extend_from_slice(&[a, right, right + 1, a, right + 1, a + 1]);
+        }
+    }
+
+    (vertices, indices)
+}
+
+//disc at height y, facing +Y when up and -Y otherwise
+fn cap(
+    vertices: &mut Vec<Vertex>,
+    indices: &mut Vec<u32>,
+    radius: f32,
+    y: f32,
+    sectors: u32,
+    up: bool,
+) {
+    let normal = if up {
+        na::Vector3::y()
+    } else {
+        -na::Vector3::y()
+    };
+    let center = vertices.len() as u32;
+
+    vertices.push(vertex(na::Vector3::y() * y, [0.5, 0.5], normal));
+    for j in 0..=sectors {
+        let direction = ring(2.0 * PI * j as f32 / sectors as f32);
+        let position = direction * radius + na::Vector3::y() * y;
+        let tex_coords = [0.5 + direction.x * 0.5, 0.5 + direction.z * 0.5];
+
+        vertices.push(vertex(position, tex_coords, normal));
+    }
+
+    for j in 0..sectors {
+        let a = center + 1 + j;
+        if up {
+            indices.extend_from_slice(&[center, a, a + 1]);
+        } else {
+            indices.extend_from_slice(&[center, a + 1, a]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all() -> Vec<(&'static str, (Vec<Vertex>, Vec<u32>))> {
+        vec![
+            ("cube", cube(na::Vector3::new(1.0, 2.0, 3.0))),
+            ("uv_sphere", uv_sphere(1.5, 16, 8)),
+            ("icosphere", icosphere(1.5, 2)),
+            ("grid", grid(4.0, 2.0, 4, 3)),
+            ("plane", plane(1.0, 1.0)),
+            ("cylinder", cylinder(1.0, 2.0, 12)),
+            ("cone", cone(1.0, 2.0, 12)),
+            ("capsule", capsule(0.5, 2.0, 12, 4)),
+            ("torus", torus(2.0, 0.5, 16, 8)),
+        ]
+    }
+
+    #[test]
+    fn indices_form_triangles_in_range() {
+        for (name, (vertices, indices)) in all().iter() {
+            assert!(!indices.is_empty(), "{}", name);
+            assert_eq!(indices.len() % 3, 0, "{}", name);
+            assert!(
+                indices
+                    .iter()
+                    .all(|&index| (index as usize) < vertices.len()),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn triangles_wind_counter_clockwise_towards_their_normals() {
+        for (name, (vertices, indices)) in all().iter() {
+            for triangle in indices.chunks(3) {
+                let corner = |i: usize| na::Vector3::from(vertices[triangle[i] as usize].position);
+                let face = (corner(1) - corner(0)).cross(&(corner(2) - corner(0)));
+                //the collapsed halves of the pole quads
+                if face.magnitude() < 1e-6 {
+                    continue;
+                }
+
+                let normal = triangle
+                    .iter()
+                    .map(|&index| na::Vector3::from(vertices[index as usize].normal))
+                    .fold(na::Vector3::zeros(), |sum, normal| sum + normal);
+                assert!(face.dot(&normal) > 0.0, "{} {:?}", name, triangle);
+            }
+        }
+    }
+
+    #[test]
+    fn normals_are_unit_and_uvs_in_range() {
+        for (name, (vertices, _)) in all().iter() {
+            for vertex in vertices.iter() {
+                let normal = na::Vector3::from(vertex.normal);
+                assert!((normal.magnitude() - 1.0).abs() < 1e-4, "{}", name);
+
+                //the icosphere seam copies run past 1.0, see below
+                let u_max = if *name == "icosphere" { 1.5 } else { 1.0 };
+                let [u, v] = vertex.tex_coords;
+                assert!((-1e-5..=u_max + 1e-5).contains(&u), "{}", name);
+                assert!((-1e-5..=1.0 + 1e-5).contains(&v), "{}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn no_icosphere_triangle_wraps_around_the_texture() {
+        let (vertices, indices) = icosphere(1.0, 3);
+
+        for triangle in indices.chunks(3) {
+            let us = triangle
+                .iter()
+                .map(|&index| vertices[index as usize].tex_coords[0])
+                .collect::<Vec<_>>();
+            let span = us.iter().cloned().fold(f32::MIN, f32::max)
+                - us.iter().cloned().fold(f32::MAX, f32::min);
+            assert!(span <= 0.5, "{:?}", us);
+        }
+    }
+
+    #[test]
+    fn shapes_have_the_requested_size() {
+        let (vertices, indices) = cube(na::Vector3::new(1.0, 2.0, 3.0));
+        assert_eq!((vertices.len(), indices.len()), (24, 36));
+        let aabb = crate::rot_primitives::Aabb::from_vertices(&vertices);
+        assert_eq!(aabb.max, na::Point3::new(1.0, 2.0, 3.0));
+        assert_eq!(aabb.min, na::Point3::new(-1.0, -2.0, -3.0));
+
+        for (vertices, _) in [uv_sphere(1.5, 16, 8), icosphere(1.5, 2)].iter() {
+            for vertex in vertices.iter() {
+                let distance = na::Vector3::from(vertex.position).magnitude();
+                assert!((distance - 1.5).abs() < 1e-4);
+            }
+        }
+
+        let (vertices, indices) = grid(4.0, 2.0, 4, 3);
+        assert_eq!((vertices.len(), indices.len()), (20, 72));
+
+        let (vertices, _) = torus(2.0, 0.5, 16, 8);
+        for vertex in vertices.iter() {
+            let position = na::Vector3::from(vertex.position);
+            let tube = na::Vector2::new(position.x, position.z).magnitude() - 2.0;
+            let distance = na::Vector2::new(tube, position.y).magnitude();
+            assert!((distance - 0.5).abs() < 1e-4);
+        }
+    }
+}
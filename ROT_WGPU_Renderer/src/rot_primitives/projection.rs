@@ -0,0 +1,216 @@
+use crate::rot_primitives::OPENGL_TO_WGPU_MATRIX;
+use nalgebra as na;
+
+//How a Camera maps view space to clip space. Every matrix comes out in wgpu clip space, 0..1 depth
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Projection {
+    Perspective {
+        //radians
+        fovy: f32,
+        znear: f32,
+        zfar: f32,
+    },
+    //reverse Z with the far plane at infinity, 1.0 at znear and 0.0 at infinity.
+    //Needs Renderer::set_reverse_z(true) to depth test the right way round
+    InfinitePerspective {
+        fovy: f32,
+        znear: f32,
+    },
+    //height of the view volume in world units, the width follows the aspect
+    Orthographic {
+        height: f32,
+        znear: f32,
+        zfar: f32,
+    },
+    OrthographicExtents {
+        left: f32,
+        right: f32,
+        bottom: f32,
+        top: f32,
+        znear: f32,
+        zfar: f32,
+    },
+    //already in wgpu clip space, used as is
+    Custom(na::Matrix4<f32>),
+}
+
+impl Projection {
+    pub fn perspective(fovy: f32, znear: f32, zfar: f32) -> Self {
+        Projection::Perspective { fovy, znear, zfar }
+    }
+
+    pub fn orthographic(height: f32, znear: f32, zfar: f32) -> Self {
+        Projection::Orthographic {
+            height,
+            znear,
+            zfar,
+        }
+    }
+
+    pub fn matrix(&self, aspect: f32) -> na::Matrix4<f32> {
+        match *self {
+            Projection::Perspective { fovy, znear, zfar } => {
+                OPENGL_TO_WGPU_MATRIX
+                    * na::Perspective3::new(aspect, fovy, znear, zfar).to_homogeneous()
+            }
+            Projection::InfinitePerspective { fovy, znear } => {
+                let f = 1.0 / (fovy * 0.5).tan();
+
+                #[rustfmt::skip]
+                let matrix = na::Matrix4::new(
+                    f / aspect, 0.0, 0.0, 0.0,
+                    0.0, f, 0.0, 0.0,
+                    0.0, 0.0, 0.0, znear,
+                    0.0, 0.0, -1.0, 0.0,
+                );
+                matrix
+            }
+            Projection::Orthographic {
+                height,
+                znear,
+                zfar,
+            } => {
+                let half = na::Vector2::new(height * aspect, height) * 0.5;
+                OPENGL_TO_WGPU_MATRIX
+                    * na::Orthographic3::new(-half.x, half.x, -half.y, half.y, znear, zfar)
+                        .to_homogeneous()
+            }
+            Projection::OrthographicExtents {
+                left,
+                right,
+                bottom,
+                top,
+                znear,
+                zfar,
+            } => {
+                OPENGL_TO_WGPU_MATRIX
+                    * na::Orthographic3::new(left, right, bottom, top, znear, zfar).to_homogeneous()
+            }
+            Projection::Custom(matrix) => matrix,
+        }
+    }
+
+    //None for the orthographic and custom projections
+    pub fn fovy(&self) -> Option<f32> {
+        match *self {
+            Projection::Perspective { fovy, .. } | Projection::InfinitePerspective { fovy, .. } => {
+                Some(fovy)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_reverse_z(&self) -> bool {
+        matches!(self, Projection::InfinitePerspective { .. })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //view space point to normalized device coordinates
+    fn ndc(matrix: &na::Matrix4<f32>, x: f32, y: f32, z: f32) -> na::Vector3<f32> {
+        let clip = matrix * na::Vector4::new(x, y, z, 1.0);
+        clip.xyz() / clip.w
+    }
+
+    fn close(a: na::Vector3<f32>, b: na::Vector3<f32>) -> bool {
+        (a - b).magnitude() < 1e-4
+    }
+
+    #[test]
+    fn perspective_depth_runs_0_to_1() {
+        let matrix = Projection::perspective(std::f32::consts::FRAC_PI_2, 1.0, 100.0).matrix(2.0);
+
+        assert!(close(
+            ndc(&matrix, 0.0, 0.0, -1.0),
+            na::Vector3::new(0.0, 0.0, 0.0)
+        ));
+        assert!(close(
+            ndc(&matrix, 0.0, 0.0, -100.0),
+            na::Vector3::new(0.0, 0.0, 1.0)
+        ));
+        //90 degrees high, twice as wide
+        let corner = ndc(&matrix, 20.0, 10.0, -10.0);
+        assert!((corner.x - 1.0).abs() < 1e-4 && (corner.y - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn infinite_perspective_reverses_depth() {
+        let projection = Projection::InfinitePerspective {
+            fovy: std::f32::consts::FRAC_PI_2,
+            znear: 0.5,
+        };
+        let matrix = projection.matrix(1.0);
+
+        assert!(close(
+            ndc(&matrix, 0.0, 0.0, -0.5),
+            na::Vector3::new(0.0, 0.0, 1.0)
+        ));
+        assert!((ndc(&matrix, 0.0, 0.0, -1.0).z - 0.5).abs() < 1e-5);
+
+        //closer to 0.0 the further, never past it
+        let far = ndc(&matrix, 0.0, 0.0, -1.0e6).z;
+        assert!(far > 0.0 && far < 1e-5);
+        assert!(ndc(&matrix, 0.0, 0.0, -10.0).z > ndc(&matrix, 0.0, 0.0, -20.0).z);
+
+        let corner = ndc(&matrix, 10.0, 10.0, -10.0);
+        assert!((corner.x - 1.0).abs() < 1e-4 && (corner.y - 1.0).abs() < 1e-4);
+        assert!(projection.is_reverse_z());
+    }
+
+    #[test]
+    fn orthographic_maps_the_box_to_clip_space() {
+        let matrix = Projection::orthographic(4.0, 1.0, 11.0).matrix(2.0);
+
+        assert!(close(
+            ndc(&matrix, -4.0, -2.0, -1.0),
+            na::Vector3::new(-1.0, -1.0, 0.0)
+        ));
+        assert!(close(
+            ndc(&matrix, 4.0, 2.0, -11.0),
+            na::Vector3::new(1.0, 1.0, 1.0)
+        ));
+        //no perspective divide, the distance doesn't shrink anything
+        assert!(close(
+            ndc(&matrix, 4.0, 2.0, -6.0),
+            na::Vector3::new(1.0, 1.0, 0.5)
+        ));
+    }
+
+    #[test]
+    fn orthographic_extents_may_be_off_center() {
+        let projection = Projection::OrthographicExtents {
+            left: 0.0,
+            right: 8.0,
+            bottom: 0.0,
+            top: 4.0,
+            znear: 0.0,
+            zfar: 10.0,
+        };
+        let matrix = projection.matrix(1.0);
+
+        assert!(close(
+            ndc(&matrix, 0.0, 0.0, 0.0),
+            na::Vector3::new(-1.0, -1.0, 0.0)
+        ));
+        assert!(close(
+            ndc(&matrix, 4.0, 2.0, -5.0),
+            na::Vector3::new(0.0, 0.0, 0.5)
+        ));
+        //the aspect is ignored, the extents already say it
+        assert_eq!(projection.matrix(3.0), matrix);
+    }
+
+    #[test]
+    fn only_perspectives_have_a_field_of_view() {
+        let custom = na::Matrix4::new_scaling(2.0);
+
+        assert_eq!(Projection::Custom(custom).matrix(4.0), custom);
+        assert_eq!(Projection::Custom(custom).fovy(), None);
+        assert_eq!(Projection::orthographic(1.0, 0.1, 10.0).fovy(), None);
+        assert_eq!(Projection::perspective(1.0, 0.1, 10.0).fovy(), Some(1.0));
+        assert!(!Projection::perspective(1.0, 0.1, 10.0).is_reverse_z());
+    }
+}
@@ -0,0 +1,113 @@
+use crate::rot_primitives::{AlphaMode, Material, Texture};
+use crate::rot_render_queue::DrawCommand;
+use crate::Renderer;
+use log::warn;
+use std::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
+
+//Color and depth textures a Camera draws into instead of the window, see Camera::with_render_target.
+//The color texture can then be sampled like any other, through material()
+pub struct RenderTarget {
+    pub name: String,
+
+    color: Rc<Texture>,
+    depth: Texture,
+    //bind groups of the materials made from it, a camera can't draw them into the target
+    samplers: RefCell<Vec<Weak<wgpu::BindGroup>>>,
+    warned: Cell<bool>,
+}
+
+impl RenderTarget {
+    //in the swapchain format, so the pipelines objects are built with can draw into it
+    pub fn new(renderer: &Renderer, width: u32, height: u32, name: &str) -> Self {
+        RenderTarget::with_format(
+            renderer,
+            width,
+            height,
+            renderer.swapchain_descriptor.format,
+            name,
+        )
+    }
+
+    //pipelines drawing into other formats need a matching PipelineBuilder::with_color_target
+    pub fn with_format(
+        renderer: &Renderer,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+        name: &str,
+    ) -> Self {
+        let dimensions = (width.max(1), height.max(1));
+
+        Self {
+            name: name.to_string(),
+            color: Rc::new(Texture::color_attachment(
+                renderer,
+                dimensions,
+                format,
+                &format!("{} color", name),
+            )),
+            depth: Texture::depth_attachment(renderer, dimensions, &format!("{} depth", name)),
+            samplers: RefCell::new(Vec::new()),
+            warned: Cell::new(false),
+        }
+    }
+
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.color.dimensions
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.color.format
+    }
+
+    pub fn aspect(&self) -> f32 {
+        let (width, height) = self.dimensions();
+        width as f32 / height as f32
+    }
+
+    //shared, so it stays alive in the materials made from it
+    pub fn color(&self) -> &Rc<Texture> {
+        &self.color
+    }
+
+    pub fn depth(&self) -> &Texture {
+        &self.depth
+    }
+
+    //shows the last frame drawn into the target
+    pub fn material(&self, renderer: &Renderer, name: &str) -> Material {
+        let material =
+            Material::from_texture(Rc::clone(&self.color), AlphaMode::Opaque, renderer, name);
+
+        let mut samplers = self.samplers.borrow_mut();
+        samplers.retain(|bind_group| bind_group.strong_count() > 0);
+        samplers.push(Rc::downgrade(&material.bind_group));
+
+        material
+    }
+
+    //whether one of the commands binds a material made from this target, warns the first time
+    pub(crate) fn sampled_by<'a>(
+        &self,
+        mut commands: impl Iterator<Item = &'a DrawCommand>,
+    ) -> bool {
+        let samplers = self.samplers.borrow();
+        let sampled = commands.any(|command| {
+            command.bind_groups.iter().any(|bind_group| {
+                samplers
+                    .iter()
+                    .any(|sampler| std::ptr::eq(sampler.as_ptr(), Rc::as_ptr(bind_group)))
+            })
+        });
+
+        if sampled && !self.warned.replace(true) {
+            warn!(
+                "Skipping the camera drawing into {}: it draws a material sampling the same target",
+                self.name
+            );
+        }
+
+        sampled
+    }
+}
@@ -0,0 +1,129 @@
+use crate::rot_primitives::{Aabb, Vertex};
+use nalgebra as na;
+use std::collections::HashMap;
+
+//Vertex clustering: every vertex inside the same grid cell collapses into their average,
+//triangles that end up with a repeated corner are dropped
+pub fn simplify(vertices: &[Vertex], indices: &[u32], resolution: u32) -> (Vec<Vertex>, Vec<u32>) {
+    if vertices.is_empty() || resolution == 0 {
+        return (vertices.to_vec(), indices.to_vec());
+    }
+
+    let aabb = Aabb::from_vertices(vertices);
+    let extent = aabb.max - aabb.min;
+    let cell_size = extent.max() / resolution as f32;
+    if cell_size <= 0.0 {
+        return (vertices.to_vec(), indices.to_vec());
+    }
+
+    let cell_of = |position: [f32; 3]| {
+        let offset = (na::Point3::from(position) - aabb.min) / cell_size;
+        (
+            offset.x.floor() as i32,
+            offset.y.floor() as i32,
+            offset.z.floor() as i32,
+        )
+    };
+
+    // CLUSTER ------------------------------------------------------
+    let mut clusters: HashMap<(i32, i32, i32), u32> = HashMap::new();
+    let mut sums: Vec<(na::Vector3<f32>, na::Vector2<f32>, na::Vector3<f32>, f32)> = Vec::new();
+    let mut remap = Vec::with_capacity(vertices.len());
+
+    for vertex in vertices.iter() {
+        let cluster = *clusters.entry(cell_of(vertex.position)).or_insert_with(|| {
+            sums.push((na::zero(), na::zero(), na::zero(), 0.0));
+            (sums.len() - 1) as u32
+        });
+
+        let sum = &mut sums[cluster as usize];
+        sum.0 += na::Vector3::from(vertex.position);
+        sum.1 += na::Vector2::from(vertex.tex_coords);
+        sum.2 += na::Vector3::from(vertex.normal);
+        sum.3 += 1.0;
+
+        remap.push(cluster);
+    }
+
+    let new_vertices = sums
+        .iter()
+        .map(|(position, tex_coords, normal, count)| {
+            let normal = if normal.magnitude() > 0.0 {
+                normal.normalize()
+            } else {
+                na::Vector3::y()
+            };
+
+            Vertex {
+                position: (position / *count).into(),
+                tex_coords: (tex_coords / *count).into(),
+                normal: normal.into(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // TRIANGLES ----------------------------------------------------
+    let mut new_indices = Vec::with_capacity(indices.len());
+    for triangle in indices.chunks_exact(3) {
+        let a = remap[triangle[0] as usize];
+        let b = remap[triangle[1] as usize];
+        let c = remap[triangle[2] as usize];
+
+        if a != b && b != c && a != c {
+            new_indices.extend_from_slice(&[a, b, c]);
+        }
+    }
+
+    (new_vertices, new_indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rot_primitives::procedural;
+
+    fn in_range(vertices: &[Vertex], indices: &[u32]) -> bool {
+        indices
+            .iter()
+            .all(|&index| (index as usize) < vertices.len())
+    }
+
+    #[test]
+    fn coarser_grids_leave_fewer_triangles() {
+        let (vertices, indices) = procedural::uv_sphere(1.0, 32, 16);
+
+        let (fine_vertices, fine_indices) = simplify(&vertices, &indices, 16);
+        let (coarse_vertices, coarse_indices) = simplify(&vertices, &indices, 4);
+
+        assert!(fine_indices.len() < indices.len());
+        assert!(coarse_indices.len() < fine_indices.len());
+        assert!(!coarse_indices.is_empty());
+        assert!(coarse_vertices.len() < fine_vertices.len());
+
+        for (vertices, indices) in [
+            (&fine_vertices, &fine_indices),
+            (&coarse_vertices, &coarse_indices),
+        ]
+        .iter()
+        {
+            assert_eq!(indices.len() % 3, 0);
+            assert!(in_range(vertices, indices));
+            //collapsed triangles are dropped, not kept with a repeated corner
+            assert!(indices.chunks(3).all(|triangle| triangle[0] != triangle[1]
+                && triangle[1] != triangle[2]
+                && triangle[0] != triangle[2]));
+        }
+    }
+
+    #[test]
+    fn nothing_to_cluster_is_left_alone() {
+        let (vertices, indices) = procedural::cube(na::Vector3::new(1.0, 1.0, 1.0));
+
+        let (same_vertices, same_indices) = simplify(&vertices, &indices, 0);
+        assert_eq!(same_vertices.len(), vertices.len());
+        assert_eq!(same_indices, indices);
+
+        let (empty_vertices, empty_indices) = simplify(&[], &[], 8);
+        assert!(empty_vertices.is_empty() && empty_indices.is_empty());
+    }
+}
@@ -1,3 +1,127 @@
-pub(crate) struct Texture {}
+use crate::Renderer;
 
-impl Texture {}
+//A 2D texture with the view and sampler it is bound with
+pub struct Texture {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub sampler: wgpu::Sampler,
+
+    pub format: wgpu::TextureFormat,
+    //in pixels
+    pub dimensions: (u32, u32),
+}
+
+impl Texture {
+    pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+    pub fn from_rgba(
+        rgba: &image::RgbaImage,
+        renderer: &Renderer,
+        name: &str,
+        format: wgpu::TextureFormat,
+    ) -> Self {
+        let texture = Texture::new(
+            renderer,
+            rgba.dimensions(),
+            format,
+            wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST,
+            name,
+        );
+
+        renderer.queue.write_texture(
+            wgpu::TextureCopyView {
+                texture: &texture.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            rgba.as_raw(),
+            wgpu::TextureDataLayout {
+                offset: 0,
+                bytes_per_row: 4 * texture.dimensions.0,
+                rows_per_image: texture.dimensions.1,
+            },
+            texture.extent(),
+        );
+
+        texture
+    }
+
+    //drawn into and then sampled, or copied out for thumbnails
+    pub fn color_attachment(
+        renderer: &Renderer,
+        dimensions: (u32, u32),
+        format: wgpu::TextureFormat,
+        name: &str,
+    ) -> Self {
+        Texture::new(
+            renderer,
+            dimensions,
+            format,
+            wgpu::TextureUsage::RENDER_ATTACHMENT
+                | wgpu::TextureUsage::SAMPLED
+                | wgpu::TextureUsage::COPY_SRC,
+            name,
+        )
+    }
+
+    //the format every pipeline is built with, see PipelineBuilder::depth_format
+    pub fn depth_attachment(renderer: &Renderer, dimensions: (u32, u32), name: &str) -> Self {
+        Texture::new(
+            renderer,
+            dimensions,
+            Texture::DEPTH_FORMAT,
+            wgpu::TextureUsage::RENDER_ATTACHMENT | wgpu::TextureUsage::SAMPLED,
+            name,
+        )
+    }
+
+    pub fn new(
+        renderer: &Renderer,
+        dimensions: (u32, u32),
+        format: wgpu::TextureFormat,
+        usage: wgpu::TextureUsage,
+        name: &str,
+    ) -> Self {
+        let texture = renderer.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(&format!("{} texture", name)),
+            size: wgpu::Extent3d {
+                width: dimensions.0,
+                height: dimensions.1,
+                depth: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage,
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = renderer.device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some(&format!(" {} Sampler", name)),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        Self {
+            texture,
+            view,
+            sampler,
+            format,
+            dimensions,
+        }
+    }
+
+    pub fn extent(&self) -> wgpu::Extent3d {
+        wgpu::Extent3d {
+            width: self.dimensions.0,
+            height: self.dimensions.1,
+            depth: 1,
+        }
+    }
+}
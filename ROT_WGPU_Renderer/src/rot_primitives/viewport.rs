@@ -0,0 +1,222 @@
+//Part of the window, in 0.0..1.0 of its size from the top-left corner
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ViewportRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl ViewportRect {
+    pub const FULL: ViewportRect = ViewportRect {
+        x: 0.0,
+        y: 0.0,
+        width: 1.0,
+        height: 1.0,
+    };
+
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    //x, y, width and height in pixels, clamped to the target. None when nothing is left
+    pub fn to_pixels(&self, target_width: u32, target_height: u32) -> Option<[u32; 4]> {
+        let to_pixels =
+            |value: f32, size: u32| (value.max(0.0).min(1.0) * size as f32).round() as u32;
+
+        let left = to_pixels(self.x, target_width);
+        let top = to_pixels(self.y, target_height);
+        let right = to_pixels(self.x + self.width, target_width);
+        let bottom = to_pixels(self.y + self.height, target_height);
+
+        match right > left && bottom > top {
+            true => Some([left, top, right - left, bottom - top]),
+            false => None,
+        }
+    }
+
+    pub fn intersection(&self, other: &ViewportRect) -> ViewportRect {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+
+        ViewportRect {
+            x,
+            y,
+            width: ((self.x + self.width).min(other.x + other.width) - x).max(0.0),
+            height: ((self.y + self.height).min(other.y + other.height) - y).max(0.0),
+        }
+    }
+
+    pub fn aspect(&self, target_width: u32, target_height: u32) -> f32 {
+        (self.width * target_width as f32) / (self.height * target_height as f32)
+    }
+
+    //a normalized window position, to 0.0..1.0 inside the rect. None when outside
+    pub fn to_local(&self, x: f32, y: f32) -> Option<(f32, f32)> {
+        let local = ((x - self.x) / self.width, (y - self.y) / self.height);
+
+        match (0.0..=1.0).contains(&local.0) && (0.0..=1.0).contains(&local.1) {
+            true => Some(local),
+            false => None,
+        }
+    }
+}
+
+impl Default for ViewportRect {
+    fn default() -> Self {
+        ViewportRect::FULL
+    }
+}
+
+//What a camera does to its part of the frame before drawing
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ClearPolicy {
+    //Renderer::set_clear_color and depth
+    Background,
+    Color([f64; 3]),
+    //keeps the color of the cameras drawn before, for overlaid views
+    DepthOnly,
+    //draws straight over the cameras before
+    Load,
+}
+
+//Where and when a camera draws. Cameras are drawn from the lowest priority up,
+//so a minimap over the main view gets a higher one
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Viewport {
+    pub rect: ViewportRect,
+    //clips the drawing further, in the same window coordinates as rect
+    pub scissor: Option<ViewportRect>,
+    pub priority: i32,
+    pub clear: ClearPolicy,
+    pub active: bool,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            rect: ViewportRect::FULL,
+            scissor: None,
+            priority: 0,
+            clear: ClearPolicy::Background,
+            active: true,
+        }
+    }
+}
+
+impl Viewport {
+    pub fn new(rect: ViewportRect) -> Self {
+        Self {
+            rect,
+            ..Viewport::default()
+        }
+    }
+
+    pub fn with_scissor(mut self, scissor: ViewportRect) -> Self {
+        self.scissor = Some(scissor);
+        self
+    }
+
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_clear(mut self, clear: ClearPolicy) -> Self {
+        self.clear = clear;
+        self
+    }
+
+    //the rect cut by the scissor
+    pub fn visible_rect(&self) -> ViewportRect {
+        match self.scissor.as_ref() {
+            Some(scissor) => self.rect.intersection(scissor),
+            None => self.rect,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rects_become_pixels_clamped_to_the_target() {
+        assert_eq!(
+            ViewportRect::FULL.to_pixels(1280, 720),
+            Some([0, 0, 1280, 720])
+        );
+        assert_eq!(
+            ViewportRect::new(0.75, 0.0, 0.25, 0.25).to_pixels(1280, 720),
+            Some([960, 0, 320, 180])
+        );
+        assert_eq!(
+            ViewportRect::new(0.5, -0.5, 1.0, 1.0).to_pixels(100, 100),
+            Some([50, 0, 50, 50])
+        );
+
+        assert_eq!(
+            ViewportRect::new(1.2, 0.0, 0.5, 1.0).to_pixels(100, 100),
+            None
+        );
+        assert_eq!(
+            ViewportRect::new(0.0, 0.0, 0.001, 1.0).to_pixels(100, 100),
+            None
+        );
+    }
+
+    #[test]
+    fn split_screen_halves_meet_without_a_gap() {
+        let left = ViewportRect::new(0.0, 0.0, 0.5, 1.0)
+            .to_pixels(1281, 721)
+            .unwrap();
+        let right = ViewportRect::new(0.5, 0.0, 0.5, 1.0)
+            .to_pixels(1281, 721)
+            .unwrap();
+
+        assert_eq!(left[0] + left[2], right[0]);
+        assert_eq!(right[0] + right[2], 1281);
+        assert_eq!((left[3], right[3]), (721, 721));
+    }
+
+    #[test]
+    fn aspect_follows_the_target() {
+        let half = ViewportRect::new(0.0, 0.0, 0.5, 1.0);
+
+        assert!((ViewportRect::FULL.aspect(1600, 900) - 16.0 / 9.0).abs() < 1e-5);
+        assert!((half.aspect(1600, 900) - 8.0 / 9.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn window_positions_map_into_the_rect() {
+        let rect = ViewportRect::new(0.5, 0.5, 0.25, 0.5);
+
+        assert_eq!(rect.to_local(0.5, 0.5), Some((0.0, 0.0)));
+        assert_eq!(rect.to_local(0.625, 0.75), Some((0.5, 0.5)));
+        assert_eq!(rect.to_local(0.75, 1.0), Some((1.0, 1.0)));
+        assert_eq!(rect.to_local(0.4, 0.75), None);
+        assert_eq!(rect.to_local(0.625, 0.2), None);
+    }
+
+    #[test]
+    fn scissor_cuts_the_visible_rect() {
+        let viewport = Viewport::new(ViewportRect::new(0.0, 0.0, 0.5, 1.0));
+        assert_eq!(viewport.visible_rect(), viewport.rect);
+
+        let scissored = viewport.with_scissor(ViewportRect::new(0.25, 0.5, 1.0, 1.0));
+        assert_eq!(
+            scissored.visible_rect(),
+            ViewportRect::new(0.25, 0.5, 0.25, 0.5)
+        );
+
+        let apart = viewport.with_scissor(ViewportRect::new(0.75, 0.0, 0.25, 1.0));
+        let visible = apart.visible_rect();
+        assert_eq!(visible.width, 0.0);
+        assert_eq!(visible.to_pixels(100, 100), None);
+    }
+}
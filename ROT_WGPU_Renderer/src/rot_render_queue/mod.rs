@@ -0,0 +1,196 @@
+mod per_view;
+mod render_view;
+
+pub use per_view::PerView;
+pub use render_view::{RenderView, ViewClearPipelines};
+
+use crate::rot_pipeline::Pipeline;
+use crate::Renderer;
+use std::ops::Range;
+use std::rc::Rc;
+
+//Everything needed to record one draw call after the caller is gone.
+//GPU resources are shared through Rc so the queue doesn't borrow from the layers
+pub struct DrawCommand {
+    //rebuilt for the attachment it ends up recorded into, see retarget
+    pub pipeline: Pipeline,
+    //bound in order, index 0 goes to set 0
+    pub bind_groups: Vec<Rc<wgpu::BindGroup>>,
+    //bound in order, index 0 goes to slot 0
+    pub vertex_buffers: Vec<Rc<wgpu::Buffer>>,
+    //without one, indices is the range of vertices to draw
+    pub index_buffer: Option<Rc<wgpu::Buffer>>,
+
+    pub indices: Range<u32>,
+    pub instances: Range<u32>,
+    //when set, the draw arguments are read from this buffer instead of indices/instances
+    pub indirect: Option<(Rc<wgpu::Buffer>, wgpu::BufferAddress)>,
+
+    //distance to the camera, only used to sort the transparent queue
+    pub depth: f32,
+}
+
+impl DrawCommand {
+    pub fn record<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        render_pass.set_pipeline(&self.pipeline.render_pipeline);
+
+        for (index, bind_group) in self.bind_groups.iter().enumerate() {
+            render_pass.set_bind_group(index as u32, bind_group, &[]);
+        }
+
+        for (slot, vertex_buffer) in self.vertex_buffers.iter().enumerate() {
+            render_pass.set_vertex_buffer(slot as u32, vertex_buffer.slice(..));
+        }
+
+        match (&self.index_buffer, &self.indirect) {
+            (Some(index_buffer), indirect) => {
+                render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                match indirect {
+                    Some((buffer, offset)) => render_pass.draw_indexed_indirect(buffer, *offset),
+                    None => {
+                        render_pass.draw_indexed(self.indices.clone(), 0, self.instances.clone())
+                    }
+                }
+            }
+            (None, Some((buffer, offset))) => render_pass.draw_indirect(buffer, *offset),
+            (None, None) => render_pass.draw(self.indices.clone(), self.instances.clone()),
+        }
+    }
+
+    pub fn retarget(
+        &mut self,
+        renderer: &Renderer,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) {
+        if !self.pipeline.fits(format, sample_count) {
+            self.pipeline = self.pipeline.for_target(renderer, format, sample_count);
+        }
+    }
+}
+
+//Recorded in a compute pass before any render pass of the frame
+pub struct ComputeCommand {
+    pub pipeline: Rc<wgpu::ComputePipeline>,
+    pub bind_groups: Vec<Rc<wgpu::BindGroup>>,
+    pub workgroups: [u32; 3],
+}
+
+impl ComputeCommand {
+    pub fn record<'a>(&'a self, compute_pass: &mut wgpu::ComputePass<'a>) {
+        compute_pass.set_pipeline(&self.pipeline);
+
+        for (index, bind_group) in self.bind_groups.iter().enumerate() {
+            compute_pass.set_bind_group(index as u32, bind_group, &[]);
+        }
+
+        compute_pass.dispatch(self.workgroups[0], self.workgroups[1], self.workgroups[2]);
+    }
+}
+
+#[derive(Default)]
+pub struct RenderQueue {
+    compute: Vec<ComputeCommand>,
+    opaque: Vec<DrawCommand>,
+    transparent: Vec<DrawCommand>,
+    //drawn over the finished scene
+    debug: Vec<DrawCommand>,
+    //2D on top of everything, in submission order
+    overlay: Vec<DrawCommand>,
+}
+
+impl RenderQueue {
+    pub fn new() -> Self {
+        Self {
+            compute: Vec::new(),
+            opaque: Vec::new(),
+            transparent: Vec::new(),
+            debug: Vec::new(),
+            overlay: Vec::new(),
+        }
+    }
+
+    pub fn push_compute(&mut self, command: ComputeCommand) {
+        self.compute.push(command);
+    }
+
+    pub fn push_opaque(&mut self, command: DrawCommand) {
+        self.opaque.push(command);
+    }
+
+    pub fn push_transparent(&mut self, command: DrawCommand) {
+        self.transparent.push(command);
+    }
+
+    pub fn push_debug(&mut self, command: DrawCommand) {
+        self.debug.push(command);
+    }
+
+    pub fn push_overlay(&mut self, command: DrawCommand) {
+        self.overlay.push(command);
+    }
+
+    //back-to-front, the farthest one is drawn first
+    pub fn sort_transparent(&mut self) {
+        self.transparent.sort_by(|a, b| {
+            b.depth
+                .partial_cmp(&a.depth)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+    }
+
+    pub fn compute(&self) -> &[ComputeCommand] {
+        self.compute.as_slice()
+    }
+
+    pub fn opaque(&self) -> &[DrawCommand] {
+        self.opaque.as_slice()
+    }
+
+    pub fn transparent(&self) -> &[DrawCommand] {
+        self.transparent.as_slice()
+    }
+
+    pub fn debug(&self) -> &[DrawCommand] {
+        self.debug.as_slice()
+    }
+
+    pub fn overlay(&self) -> &[DrawCommand] {
+        self.overlay.as_slice()
+    }
+
+    //every draw, for the attachment the queue is recorded into
+    pub fn retarget(
+        &mut self,
+        renderer: &Renderer,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) {
+        self.opaque
+            .iter_mut()
+            .chain(self.transparent.iter_mut())
+            .chain(self.debug.iter_mut())
+            .chain(self.overlay.iter_mut())
+            .for_each(|command| command.retarget(renderer, format, sample_count));
+    }
+
+    //only the opaque draws, RenderDebugMode::Overdraw counts them into its own target
+    pub fn retarget_opaque(
+        &mut self,
+        renderer: &Renderer,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) {
+        for command in self.opaque.iter_mut() {
+            command.retarget(renderer, format, sample_count);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.compute.clear();
+        self.opaque.clear();
+        self.transparent.clear();
+        self.debug.clear();
+        self.overlay.clear();
+    }
+}
@@ -0,0 +1,39 @@
+use crate::Renderer;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+//One T per view of the frame, for what a draw writes with queue.write_buffer.
+//Every write lands before the frame is submitted, so views sharing one would all see the last
+pub struct PerView<T> {
+    slots: RefCell<Vec<Rc<T>>>,
+}
+
+impl<T> PerView<T> {
+    pub fn new() -> Self {
+        Self {
+            slots: RefCell::new(Vec::new()),
+        }
+    }
+
+    //the one of the view the current camera draws into, created the first time that view needs it
+    pub fn get(&self, renderer: &Renderer, create: impl Fn() -> T) -> Rc<T> {
+        let index = renderer.view_index();
+        let mut slots = self.slots.borrow_mut();
+        while slots.len() <= index {
+            slots.push(Rc::new(create()));
+        }
+
+        Rc::clone(&slots[index])
+    }
+
+    //when what they were created from changed, they are created again as the views need them
+    pub fn clear(&mut self) {
+        self.slots.get_mut().clear();
+    }
+}
+
+impl<T> Default for PerView<T> {
+    fn default() -> Self {
+        PerView::new()
+    }
+}
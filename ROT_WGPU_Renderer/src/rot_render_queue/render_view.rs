@@ -0,0 +1,179 @@
+use crate::rot_pipeline::{Pipeline, PipelineBuilder};
+use crate::rot_primitives::{ClearPolicy, RenderTarget, Viewport};
+use crate::rot_render_queue::RenderQueue;
+use crate::Renderer;
+use std::rc::Rc;
+
+//The draws of one camera, recorded into its part of the frame
+pub struct RenderView {
+    pub viewport: Viewport,
+    pub camera_bind_group: Option<Rc<wgpu::BindGroup>>,
+    //None draws into the window
+    pub render_target: Option<Rc<RenderTarget>>,
+    //only the opaque, transparent and debug queues are used, compute and overlay are per frame
+    pub queue: RenderQueue,
+}
+
+impl RenderView {
+    pub fn new(
+        viewport: Viewport,
+        camera_bind_group: Option<Rc<wgpu::BindGroup>>,
+        render_target: Option<Rc<RenderTarget>>,
+    ) -> Self {
+        Self {
+            viewport,
+            camera_bind_group,
+            render_target,
+            queue: RenderQueue::new(),
+        }
+    }
+
+    //x, y, width and height in pixels, None when the camera is inactive or out of the target
+    pub fn pixel_rect(&self, target_width: u32, target_height: u32) -> Option<[u32; 4]> {
+        if !self.viewport.active {
+            return None;
+        }
+
+        self.viewport
+            .visible_rect()
+            .to_pixels(target_width, target_height)
+    }
+
+    //the viewport keeps the full rect so the projection isn't squashed, the scissor cuts it
+    pub fn begin<'a>(
+        &self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        target_width: u32,
+        target_height: u32,
+    ) {
+        let rect = self.viewport.rect;
+        render_pass.set_viewport(
+            rect.x * target_width as f32,
+            rect.y * target_height as f32,
+            rect.width * target_width as f32,
+            rect.height * target_height as f32,
+            0.0,
+            1.0,
+        );
+
+        if let Some([x, y, width, height]) = self.pixel_rect(target_width, target_height) {
+            render_pass.set_scissor_rect(x, y, width, height);
+        }
+    }
+
+    //a target can't be an attachment of the pass its own color texture is sampled in
+    pub fn samples_own_target(&self) -> bool {
+        match self.render_target.as_ref() {
+            Some(render_target) => render_target.sampled_by(
+                self.queue
+                    .opaque()
+                    .iter()
+                    .chain(self.queue.transparent())
+                    .chain(self.queue.debug()),
+            ),
+            None => false,
+        }
+    }
+
+    //a load op clears the whole attachment, so each view clears its own part with a triangle.
+    //Call after begin
+    pub fn clear<'a>(
+        &self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        pipelines: &'a ViewClearPipelines,
+        background: [f64; 3],
+        reverse_z: bool,
+    ) {
+        let (pipeline, color) = match self.viewport.clear {
+            ClearPolicy::Background => (&pipelines.color, background),
+            ClearPolicy::Color(color) => (&pipelines.color, color),
+            ClearPolicy::DepthOnly => (&pipelines.depth_only, background),
+            ClearPolicy::Load => return,
+        };
+
+        render_pass.set_pipeline(&pipeline.render_pipeline);
+        render_pass.set_blend_color(wgpu::Color {
+            r: color[0],
+            g: color[1],
+            b: color[2],
+            a: 1.0,
+        });
+        //the instance index picks the far depth, see clear.vert
+        let instance = reverse_z as u32;
+        render_pass.draw(0..3, instance..instance + 1);
+    }
+}
+
+//Full screen triangle writing the blend color and the far depth
+pub struct ViewClearPipelines {
+    pub color: Pipeline,
+    pub depth_only: Pipeline,
+}
+
+impl ViewClearPipelines {
+    //shared by every view drawing into the same kind of attachment, built the first time
+    pub fn get(
+        renderer: &Renderer,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Rc<ViewClearPipelines> {
+        let cached = renderer
+            .pipeline_cache
+            .borrow()
+            .get_clear(format, sample_count);
+        match cached {
+            Some(pipelines) => pipelines,
+            None => {
+                let pipelines = ViewClearPipelines::new(renderer, format, sample_count);
+                renderer
+                    .pipeline_cache
+                    .borrow_mut()
+                    .insert_clear(format, sample_count, pipelines)
+            }
+        }
+    }
+
+    //format and sample count of the color attachment, the swapchain or a RenderTarget
+    fn new(renderer: &Renderer, format: wgpu::TextureFormat, sample_count: u32) -> Self {
+        let blend_color = wgpu::BlendState {
+            src_factor: wgpu::BlendFactor::BlendColor,
+            dst_factor: wgpu::BlendFactor::Zero,
+            operation: wgpu::BlendOperation::Add,
+        };
+        let pipeline_builder = PipelineBuilder {
+            name: "view clear pipeline",
+            vertex_shader_path: "shaders/clear.vert.spv",
+            fragment_shader_path: "shaders/clear.frag.spv",
+            cull_mode: wgpu::CullMode::None,
+            ..PipelineBuilder::default()
+        }
+        .with_depth(false, true);
+
+        let color_target = wgpu::ColorTargetState {
+            format,
+            alpha_blend: blend_color.clone(),
+            color_blend: blend_color,
+            write_mask: wgpu::ColorWrite::ALL,
+        };
+        let depth_only = pipeline_builder
+            .clone()
+            .with_color_target(wgpu::ColorTargetState {
+                write_mask: wgpu::ColorWrite::empty(),
+                ..color_target.clone()
+            });
+        let pipeline_builder = pipeline_builder.with_color_target(color_target);
+
+        Self {
+            color: Pipeline::new(renderer, &pipeline_builder).for_target(
+                renderer,
+                format,
+                sample_count,
+            ),
+            depth_only: Pipeline::new(renderer, &depth_only).for_target(
+                renderer,
+                format,
+                sample_count,
+            ),
+        }
+    }
+}
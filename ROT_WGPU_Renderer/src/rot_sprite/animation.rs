@@ -0,0 +1,177 @@
+use crate::rot_sprite::{Sprite, TextureAtlas, UvRect};
+
+//Flipbook over uv rects of one texture, either cells of a sprite sheet or atlas regions
+#[derive(Debug, Clone)]
+pub struct SpriteAnimation {
+    frames: Vec<UvRect>,
+    //seconds per frame
+    frame_time: f32,
+    time: f32,
+
+    pub looping: bool,
+    pub playing: bool,
+}
+
+impl SpriteAnimation {
+    pub fn new(frames: Vec<UvRect>, fps: f32) -> Self {
+        Self {
+            frames,
+            frame_time: 1.0 / fps.max(f32::EPSILON),
+            time: 0.0,
+            looping: true,
+            playing: true,
+        }
+    }
+
+    //A sheet of equally sized cells inside region (UvRect::FULL for a whole texture),
+    //read left to right then top to bottom, stopping after frame_count cells
+    pub fn from_grid(region: UvRect, columns: u32, rows: u32, frame_count: u32, fps: f32) -> Self {
+        let (columns, rows) = (columns.max(1), rows.max(1));
+        let frames = (0..frame_count.min(columns * rows))
+            .map(|frame| {
+                let (column, row) = ((frame % columns) as f32, (frame / columns) as f32);
+                let cell = [1.0 / columns as f32, 1.0 / rows as f32];
+
+                region.sub_rect(
+                    [column * cell[0], row * cell[1]],
+                    [(column + 1.0) * cell[0], (row + 1.0) * cell[1]],
+                )
+            })
+            .collect();
+
+        SpriteAnimation::new(frames, fps)
+    }
+
+    //one frame per atlas region, None if any name is missing
+    pub fn from_atlas(atlas: &TextureAtlas, names: &[&str], fps: f32) -> Option<Self> {
+        let frames = names
+            .iter()
+            .map(|name| atlas.region(name).map(|region| region.uv_rect))
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(SpriteAnimation::new(frames, fps))
+    }
+
+    pub fn with_looping(mut self, looping: bool) -> Self {
+        self.looping = looping;
+        self
+    }
+
+    pub fn set_fps(&mut self, fps: f32) {
+        self.frame_time = 1.0 / fps.max(f32::EPSILON);
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.frame_time * self.frames.len() as f32
+    }
+
+    pub fn advance(&mut self, delta_time: f32) {
+        if !self.playing || self.frames.is_empty() {
+            return;
+        }
+
+        self.time += delta_time;
+        if self.looping {
+            self.time %= self.duration();
+        } else if self.time >= self.duration() {
+            self.time = self.duration();
+            self.playing = false;
+        }
+    }
+
+    pub fn play(&mut self) {
+        self.playing = true;
+    }
+
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    pub fn reset(&mut self) {
+        self.time = 0.0;
+        self.playing = true;
+    }
+
+    pub fn set_frame(&mut self, frame: usize) {
+        self.time = frame.min(self.frames.len().saturating_sub(1)) as f32 * self.frame_time;
+    }
+
+    pub fn frame_index(&self) -> usize {
+        ((self.time / self.frame_time) as usize).min(self.frames.len().saturating_sub(1))
+    }
+
+    pub fn frame(&self) -> UvRect {
+        self.frames
+            .get(self.frame_index())
+            .copied()
+            .unwrap_or(UvRect::FULL)
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    //a non looping animation that reached its last frame
+    pub fn is_finished(&self) -> bool {
+        !self.looping && self.time >= self.duration()
+    }
+
+    pub fn apply(&self, sprite: &mut Sprite) {
+        sprite.uv_rect = self.frame();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //4 frames at 10 fps, 0.4 seconds
+    fn animation() -> SpriteAnimation {
+        SpriteAnimation::from_grid(UvRect::FULL, 2, 2, 4, 10.0)
+    }
+
+    #[test]
+    fn grid_cells_read_left_to_right_then_down() {
+        let animation = SpriteAnimation::from_grid(UvRect::FULL, 2, 2, 3, 10.0);
+
+        assert_eq!(animation.frame_count(), 3);
+        assert_eq!(animation.frames[0], UvRect::new([0.0, 0.0], [0.5, 0.5]));
+        assert_eq!(animation.frames[1], UvRect::new([0.5, 0.0], [1.0, 0.5]));
+        assert_eq!(animation.frames[2], UvRect::new([0.0, 0.5], [0.5, 1.0]));
+    }
+
+    #[test]
+    fn looping_wraps_back_to_the_first_frame() {
+        let mut animation = animation();
+
+        animation.advance(0.25);
+        assert_eq!(animation.frame_index(), 2);
+        animation.advance(0.2);
+        assert_eq!(animation.frame_index(), 0);
+        assert!(animation.playing && !animation.is_finished());
+    }
+
+    #[test]
+    fn once_stops_on_the_last_frame() {
+        let mut animation = animation().with_looping(false);
+
+        animation.advance(1.0);
+        assert_eq!(animation.frame_index(), 3);
+        assert!(!animation.playing && animation.is_finished());
+
+        animation.reset();
+        assert_eq!(animation.frame_index(), 0);
+        assert!(animation.playing);
+    }
+
+    #[test]
+    fn paused_animations_hold_their_frame() {
+        let mut animation = animation();
+        animation.set_frame(1);
+        animation.pause();
+
+        animation.advance(0.3);
+        assert_eq!(animation.frame_index(), 1);
+        assert_eq!(animation.frame(), animation.frames[1]);
+    }
+}
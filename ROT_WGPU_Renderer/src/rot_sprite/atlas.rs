@@ -0,0 +1,229 @@
+use crate::rot_primitives::Material;
+use crate::rot_sprite::{Sprite, UvRect};
+use crate::Renderer;
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+use std::rc::Rc;
+
+//empty pixels around every image so linear filtering doesn't bleed the neighbours in
+const PADDING: u32 = 1;
+const MAX_SIZE: u32 = 8192;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AtlasRegion {
+    pub uv_rect: UvRect,
+    //in pixels of the atlas texture
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+//Many images packed into one texture, so their sprites share a single draw
+pub struct TextureAtlas {
+    pub texture: Rc<Material>,
+    regions: HashMap<String, AtlasRegion>,
+}
+
+impl TextureAtlas {
+    //every PNG in the directory, named by its file stem ("texture/viking.png" is "viking")
+    pub fn from_directory<P: AsRef<Path>>(
+        renderer: &Renderer,
+        directory: P,
+        name: &str,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut paths = std::fs::read_dir(directory)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .map(|extension| extension.eq_ignore_ascii_case("png"))
+                    .unwrap_or(false)
+            })
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        let mut images = Vec::with_capacity(paths.len());
+        for path in paths {
+            let stem = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let bytes = std::fs::read(&path)?;
+            let image = image::load_from_memory(bytes.as_slice())?;
+
+            images.push((stem, image.to_rgba8()));
+        }
+
+        TextureAtlas::from_images(renderer, images, name)
+    }
+
+    pub fn from_images(
+        renderer: &Renderer,
+        images: Vec<(String, image::RgbaImage)>,
+        name: &str,
+    ) -> Result<Self, Box<dyn Error>> {
+        let sizes = images
+            .iter()
+            .map(|(_, image)| image.dimensions())
+            .collect::<Vec<_>>();
+        let (width, height, positions) = pack(sizes.as_slice(), PADDING).ok_or_else(|| {
+            format!(
+                "{} images don't fit a {}x{} atlas",
+                images.len(),
+                MAX_SIZE,
+                MAX_SIZE
+            )
+        })?;
+
+        let mut atlas_image = image::RgbaImage::new(width, height);
+        let mut regions = HashMap::with_capacity(images.len());
+        for ((region_name, image), (x, y)) in images.iter().zip(positions.into_iter()) {
+            image::imageops::replace(&mut atlas_image, image, x, y);
+
+            let (region_width, region_height) = image.dimensions();
+            regions.insert(
+                region_name.clone(),
+                AtlasRegion {
+                    uv_rect: UvRect::from_pixels(
+                        x,
+                        y,
+                        region_width,
+                        region_height,
+                        (width, height),
+                    ),
+                    x,
+                    y,
+                    width: region_width,
+                    height: region_height,
+                },
+            );
+        }
+
+        Ok(Self {
+            texture: Rc::new(Material::from_rgba(&atlas_image, renderer, name)),
+            regions,
+        })
+    }
+
+    pub fn region(&self, name: &str) -> Option<&AtlasRegion> {
+        self.regions.get(name)
+    }
+
+    pub fn regions(&self) -> impl Iterator<Item = (&String, &AtlasRegion)> {
+        self.regions.iter()
+    }
+
+    //a sprite showing the region at its size in pixels
+    pub fn sprite(&self, name: &str) -> Option<Sprite> {
+        self.regions.get(name).map(|region| {
+            Sprite::from_region(
+                Rc::clone(&self.texture),
+                region.uv_rect,
+                [region.width as f32, region.height as f32],
+            )
+        })
+    }
+}
+
+//Shelf packing, tallest first, with padding empty pixels around every rect. Tries power of two
+//widths until the height fits too. Returns the atlas size and the top-left corner of every
+//size, in the given order
+pub(crate) fn pack(sizes: &[(u32, u32)], padding: u32) -> Option<(u32, u32, Vec<(u32, u32)>)> {
+    let mut order = (0..sizes.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| sizes[b].1.cmp(&sizes[a].1));
+
+    let padded = |index: usize| (sizes[index].0 + 2 * padding, sizes[index].1 + 2 * padding);
+    let area = order
+        .iter()
+        .map(|&index| {
+            let (width, height) = padded(index);
+            (width * height) as u64
+        })
+        .sum::<u64>();
+    let widest = order
+        .iter()
+        .map(|&index| padded(index).0)
+        .max()
+        .unwrap_or(1);
+
+    let mut width = ((area as f64).sqrt() as u32)
+        .max(widest)
+        .next_power_of_two();
+    while width <= MAX_SIZE {
+        let mut positions = vec![(0, 0); sizes.len()];
+        let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+
+        for &index in order.iter() {
+            let (padded_width, padded_height) = padded(index);
+            if x + padded_width > width {
+                x = 0;
+                y += shelf_height;
+                shelf_height = 0;
+            }
+
+            positions[index] = (x + padding, y + padding);
+            x += padded_width;
+            shelf_height = shelf_height.max(padded_height);
+        }
+
+        let height = (y + shelf_height).max(1).next_power_of_two();
+        if height <= MAX_SIZE {
+            return Some((width, height, positions));
+        }
+
+        width *= 2;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overlap(a: (u32, u32, u32, u32), b: (u32, u32, u32, u32)) -> bool {
+        a.0 < b.0 + b.2 && b.0 < a.0 + a.2 && a.1 < b.1 + b.3 && b.1 < a.1 + a.3
+    }
+
+    #[test]
+    fn packed_rects_stay_inside_and_apart() {
+        let sizes = [
+            (30, 10),
+            (12, 40),
+            (64, 64),
+            (5, 5),
+            (20, 33),
+            (7, 50),
+            (16, 16),
+        ];
+        let padding = 2;
+        let (width, height, positions) = pack(&sizes, padding).unwrap();
+
+        assert!(width.is_power_of_two() && height.is_power_of_two());
+        assert_eq!(positions.len(), sizes.len());
+
+        //padding included, so neighbours never share a border pixel
+        let padded = sizes
+            .iter()
+            .zip(positions.iter())
+            .map(|(&(w, h), &(x, y))| (x - padding, y - padding, w + 2 * padding, h + 2 * padding))
+            .collect::<Vec<_>>();
+        for (i, &a) in padded.iter().enumerate() {
+            assert!(a.0 + a.2 <= width && a.1 + a.3 <= height);
+            for &b in padded[i + 1..].iter() {
+                assert!(!overlap(a, b), "{:?} {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn too_large_for_one_atlas() {
+        assert!(pack(&[(MAX_SIZE + 1, 1)], 0).is_none());
+        assert!(pack(&[(MAX_SIZE, MAX_SIZE), (1, 1)], 0).is_none());
+        assert_eq!(
+            pack(&[(MAX_SIZE, MAX_SIZE)], 0),
+            Some((MAX_SIZE, MAX_SIZE, vec![(0, 0)]))
+        );
+    }
+}
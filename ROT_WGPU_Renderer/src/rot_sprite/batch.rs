@@ -0,0 +1,235 @@
+use crate::rot_pipeline::{Pipeline, PipelineBuilder};
+use crate::rot_render_queue::DrawCommand;
+use crate::rot_sprite::{Camera2D, Sprite};
+use crate::Renderer;
+use std::rc::Rc;
+use wgpu::util::DeviceExt;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct SpriteVertex {
+    pub position: [f32; 3],
+    pub tex_coords: [f32; 2],
+    pub color: [f32; 4],
+}
+
+unsafe impl bytemuck::Pod for SpriteVertex {}
+unsafe impl bytemuck::Zeroable for SpriteVertex {}
+
+impl SpriteVertex {
+    pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<SpriteVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::InputStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float3,
+                    offset: 0,
+                    shader_location: 0,
+                },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float2,
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float4,
+                    offset: std::mem::size_of::<[f32; 5]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                },
+            ],
+        }
+    }
+}
+
+const QUAD_INDICES: [u32; 6] = [0, 1, 2, 0, 2, 3];
+
+//Collects the sprites of a frame and draws them in as few calls as possible.
+//Sprites are sorted back-to-front by layer, then by texture, and every run sharing
+//a texture becomes a single indexed draw. Submit once per frame, the buffers are reused
+pub struct SpriteBatch {
+    pub name: String,
+
+    sprites: Vec<Sprite>,
+    quads: QuadBuffer,
+}
+
+impl SpriteBatch {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            sprites: Vec::new(),
+            quads: QuadBuffer::new(&format!("{} Sprite", name)),
+        }
+    }
+
+    pub fn draw(&mut self, sprite: Sprite) {
+        self.sprites.push(sprite);
+    }
+
+    pub fn len(&self) -> usize {
+        self.sprites.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sprites.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.sprites.clear();
+    }
+
+    //uploads the queued sprites and hands the draws to the overlay pass, then clears the batch
+    pub fn submit(&mut self, renderer: &mut Renderer, camera: &Camera2D) {
+        if self.sprites.is_empty() {
+            return;
+        }
+
+        self.sprites.sort_by(|a, b| {
+            b.layer
+                .partial_cmp(&a.layer)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| Rc::as_ptr(&a.texture).cmp(&Rc::as_ptr(&b.texture)))
+        });
+
+        let mut vertices = Vec::with_capacity(self.sprites.len() * 4);
+        for sprite in self.sprites.iter() {
+            let corners = sprite.corners();
+            let uv = &sprite.uv_rect;
+            let tex_coords = [
+                [uv.min[0], uv.max[1]],
+                [uv.max[0], uv.max[1]],
+                [uv.max[0], uv.min[1]],
+                [uv.min[0], uv.min[1]],
+            ];
+
+            for (corner, tex_coords) in corners.iter().zip(tex_coords.iter()) {
+                vertices.push(SpriteVertex {
+                    position: [corner.x, corner.y, -sprite.layer],
+                    tex_coords: *tex_coords,
+                    color: sprite.tint,
+                });
+            }
+        }
+
+        let (vertex_buffer, index_buffer) = self.quads.write(renderer, vertices.as_slice());
+
+        let pipeline = SpriteBatch::pipeline(renderer);
+
+        let mut start = 0;
+        while start < self.sprites.len() {
+            let texture = &self.sprites[start].texture;
+            let end = start
+                + self.sprites[start..]
+                    .iter()
+                    .take_while(|sprite| Rc::ptr_eq(&sprite.texture, texture))
+                    .count();
+
+            renderer.submit_overlay(DrawCommand {
+                pipeline: pipeline.clone(),
+                bind_groups: vec![
+                    Rc::clone(&texture.bind_group),
+                    Rc::clone(&camera.bind_group),
+                ],
+                vertex_buffers: vec![Rc::clone(&vertex_buffer)],
+                index_buffer: Some(Rc::clone(&index_buffer)),
+                indices: (start * 6) as u32..(end * 6) as u32,
+                instances: 0..1,
+                indirect: None,
+                depth: 0.0,
+            });
+
+            start = end;
+        }
+
+        self.sprites.clear();
+    }
+
+    //painter's order from the sort, so no depth at all
+    fn pipeline(renderer: &Renderer) -> Pipeline {
+        let pipeline_builder = PipelineBuilder {
+            name: "sprite pipeline",
+            uniform_material: true,
+            uniform_camera: true,
+            vertex_shader_path: "shaders/sprite.vert.spv",
+            fragment_shader_path: "shaders/sprite.frag.spv",
+            vertex_buffer_layout: vec![SpriteVertex::desc()],
+            cull_mode: wgpu::CullMode::None,
+            ..PipelineBuilder::default()
+        }
+        .with_alpha_blending()
+        .without_depth();
+
+        Pipeline::new(renderer, &pipeline_builder)
+    }
+}
+
+//Dynamic vertex buffer of 4-vertex quads plus the matching static index buffer,
+//both doubled whenever a frame needs more room
+pub(crate) struct QuadBuffer {
+    name: String,
+
+    //in quads
+    capacity: usize,
+    vertex_buffer: Option<Rc<wgpu::Buffer>>,
+    index_buffer: Option<Rc<wgpu::Buffer>>,
+}
+
+impl QuadBuffer {
+    pub(crate) fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            capacity: 0,
+            vertex_buffer: None,
+            index_buffer: None,
+        }
+    }
+
+    //vertices holds 4 per quad: bottom-left, bottom-right, top-right, top-left
+    pub(crate) fn write<V: bytemuck::Pod>(
+        &mut self,
+        renderer: &Renderer,
+        vertices: &[V],
+    ) -> (Rc<wgpu::Buffer>, Rc<wgpu::Buffer>) {
+        self.reserve(renderer, vertices.len() / 4, std::mem::size_of::<V>());
+
+        let vertex_buffer = Rc::clone(self.vertex_buffer.as_ref().unwrap());
+        let index_buffer = Rc::clone(self.index_buffer.as_ref().unwrap());
+        renderer
+            .queue
+            .write_buffer(&vertex_buffer, 0, bytemuck::cast_slice(vertices));
+
+        (vertex_buffer, index_buffer)
+    }
+
+    fn reserve(&mut self, renderer: &Renderer, count: usize, vertex_size: usize) {
+        if count <= self.capacity && self.vertex_buffer.is_some() {
+            return;
+        }
+
+        self.capacity = self.capacity.max(64);
+        while self.capacity < count {
+            self.capacity *= 2;
+        }
+
+        self.vertex_buffer = Some(Rc::new(renderer.device.create_buffer(
+            &wgpu::BufferDescriptor {
+                label: Some(&format!("{} Vertex Buffer", self.name)),
+                size: (self.capacity * 4 * vertex_size) as wgpu::BufferAddress,
+                usage: wgpu::BufferUsage::VERTEX | wgpu::BufferUsage::COPY_DST,
+                mapped_at_creation: false,
+            },
+        )));
+
+        let indices = (0..self.capacity as u32)
+            .flat_map(|quad| QUAD_INDICES.iter().map(move |index| quad * 4 + index))
+            .collect::<Vec<u32>>();
+        self.index_buffer = Some(Rc::new(renderer.device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some(&format!("{} Index Buffer", self.name)),
+                contents: bytemuck::cast_slice(indices.as_slice()),
+                usage: wgpu::BufferUsage::INDEX,
+            },
+        )));
+    }
+}
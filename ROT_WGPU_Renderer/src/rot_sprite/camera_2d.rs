@@ -0,0 +1,135 @@
+use crate::rot_primitives::{Camera, CameraUniform, Projection};
+use crate::Renderer;
+use nalgebra as na;
+use std::rc::Rc;
+use wgpu::util::DeviceExt;
+
+//Orthographic y-up camera for sprites, centered on position.
+//Its bind group uses the Camera layout, so sprite pipelines only need uniform_camera
+pub struct Camera2D {
+    pub position: na::Point2<f32>,
+    //radians, counter-clockwise
+    pub rotation: f32,
+    //2.0 shows everything twice as big
+    pub zoom: f32,
+
+    //visible area at zoom 1.0, in world units
+    viewport: na::Vector2<f32>,
+
+    pub uniform: CameraUniform,
+    pub buffer: wgpu::Buffer,
+    pub bind_group: Rc<wgpu::BindGroup>,
+}
+
+impl Camera2D {
+    //one world unit per pixel of the swapchain
+    pub fn new(renderer: &Renderer) -> Self {
+        let viewport = na::Vector2::new(
+            renderer.swapchain_descriptor.width as f32,
+            renderer.swapchain_descriptor.height as f32,
+        );
+
+        Camera2D::with_viewport(renderer, viewport)
+    }
+
+    pub fn with_viewport(renderer: &Renderer, viewport: na::Vector2<f32>) -> Self {
+        let uniform = CameraUniform::default();
+        let buffer = renderer
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Camera2D Buffer"),
+                contents: bytemuck::cast_slice(&[uniform]),
+                usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+            });
+        let bind_group = Camera2D::create_bind_group(renderer, &buffer);
+
+        let mut camera = Self {
+            position: na::Point2::origin(),
+            rotation: 0.0,
+            zoom: 1.0,
+            viewport,
+            uniform,
+            buffer,
+            bind_group: Rc::new(bind_group),
+        };
+        camera.write_uniform(renderer);
+
+        camera
+    }
+
+    pub fn viewport(&self) -> na::Vector2<f32> {
+        self.viewport
+    }
+
+    pub fn set_viewport(&mut self, viewport: na::Vector2<f32>) {
+        self.viewport = viewport;
+    }
+
+    pub fn view(&self) -> na::Matrix4<f32> {
+        na::Matrix4::from_axis_angle(&na::Vector3::z_axis(), -self.rotation)
+            * na::Matrix4::new_translation(&-self.position.coords.push(0.0))
+    }
+
+    pub fn projection(&self) -> Projection {
+        let half = self.viewport / (2.0 * self.zoom);
+        //sprites sit at z = -layer, so the 0..1 layers land inside the depth range
+        Projection::OrthographicExtents {
+            left: -half.x,
+            right: half.x,
+            bottom: -half.y,
+            top: half.y,
+            znear: -1.0,
+            zfar: 1.0,
+        }
+    }
+
+    pub fn view_proj(&self) -> na::Matrix4<f32> {
+        self.projection().matrix(1.0) * self.view()
+    }
+
+    //window pixels, origin at the top-left, to world units
+    pub fn screen_to_world(
+        &self,
+        screen: na::Point2<f32>,
+        screen_size: na::Vector2<f32>,
+    ) -> na::Point2<f32> {
+        let ndc = na::Vector2::new(
+            screen.x / screen_size.x * 2.0 - 1.0,
+            1.0 - screen.y / screen_size.y * 2.0,
+        );
+        let local = ndc.component_mul(&self.viewport) / (2.0 * self.zoom);
+
+        self.position + na::Rotation2::new(self.rotation) * local
+    }
+
+    //follows the swapchain size, like Camera::on_update follows its aspect
+    pub fn on_update(&mut self, renderer: &Renderer) {
+        self.viewport = na::Vector2::new(
+            renderer.swapchain_descriptor.width as f32,
+            renderer.swapchain_descriptor.height as f32,
+        );
+        self.write_uniform(renderer);
+    }
+
+    pub fn write_uniform(&mut self, renderer: &Renderer) {
+        let position = na::Point3::new(self.position.x, self.position.y, 0.0);
+        self.uniform
+            .set_matrices(&self.view(), &self.projection().matrix(1.0), &position);
+        renderer
+            .queue
+            .write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[self.uniform]));
+    }
+
+    fn create_bind_group(renderer: &Renderer, buffer: &wgpu::Buffer) -> wgpu::BindGroup {
+        renderer
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Camera2D Bind Group"),
+                layout: &Camera::get_bind_group_layout(renderer),
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                }],
+            })
+    }
+}
@@ -0,0 +1,181 @@
+use crate::rot_primitives::Material;
+use nalgebra as na;
+use std::rc::Rc;
+
+mod animation;
+mod atlas;
+mod batch;
+mod camera_2d;
+
+pub use animation::SpriteAnimation;
+pub use atlas::{AtlasRegion, TextureAtlas};
+pub use batch::{SpriteBatch, SpriteVertex};
+pub use camera_2d::Camera2D;
+
+pub(crate) use atlas::pack;
+pub(crate) use batch::QuadBuffer;
+
+//Normalized texture coordinates, origin at the top-left like the images
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct UvRect {
+    pub min: [f32; 2],
+    pub max: [f32; 2],
+}
+
+impl UvRect {
+    pub const FULL: UvRect = UvRect {
+        min: [0.0, 0.0],
+        max: [1.0, 1.0],
+    };
+
+    pub fn new(min: [f32; 2], max: [f32; 2]) -> Self {
+        Self { min, max }
+    }
+
+    pub fn from_pixels(x: u32, y: u32, width: u32, height: u32, texture_size: (u32, u32)) -> Self {
+        let (texture_width, texture_height) = (texture_size.0 as f32, texture_size.1 as f32);
+
+        Self {
+            min: [x as f32 / texture_width, y as f32 / texture_height],
+            max: [
+                (x + width) as f32 / texture_width,
+                (y + height) as f32 / texture_height,
+            ],
+        }
+    }
+
+    //the sub-rect of this one, with min and max given as fractions of it
+    pub fn sub_rect(&self, min: [f32; 2], max: [f32; 2]) -> Self {
+        let size = [self.max[0] - self.min[0], self.max[1] - self.min[1]];
+
+        Self {
+            min: [
+                self.min[0] + min[0] * size[0],
+                self.min[1] + min[1] * size[1],
+            ],
+            max: [
+                self.min[0] + max[0] * size[0],
+                self.min[1] + max[1] * size[1],
+            ],
+        }
+    }
+
+    pub fn flip_x(self) -> Self {
+        Self {
+            min: [self.max[0], self.min[1]],
+            max: [self.min[0], self.max[1]],
+        }
+    }
+
+    pub fn flip_y(self) -> Self {
+        Self {
+            min: [self.min[0], self.max[1]],
+            max: [self.max[0], self.min[1]],
+        }
+    }
+}
+
+impl Default for UvRect {
+    fn default() -> Self {
+        UvRect::FULL
+    }
+}
+
+//One textured quad. World space is y-up, with one unit per pixel under a Camera2D at zoom 1.0
+#[derive(Clone)]
+pub struct Sprite {
+    pub texture: Rc<Material>,
+    pub uv_rect: UvRect,
+
+    pub position: na::Point2<f32>,
+    //radians, counter-clockwise around the origin
+    pub rotation: f32,
+    pub scale: na::Vector2<f32>,
+    //unscaled size, the region size in pixels by default
+    pub size: na::Vector2<f32>,
+    //pivot for position, rotation and scale, (0, 0) is the bottom-left and (1, 1) the top-right
+    pub origin: na::Vector2<f32>,
+
+    pub tint: [f32; 4],
+    //0.0 is the front and 1.0 the back, the batch draws back-to-front
+    pub layer: f32,
+}
+
+impl Sprite {
+    pub fn new(texture: Rc<Material>) -> Self {
+        let (width, height) = texture.dimensions;
+
+        Sprite::from_region(texture, UvRect::FULL, [width as f32, height as f32])
+    }
+
+    pub fn from_region(texture: Rc<Material>, uv_rect: UvRect, size: [f32; 2]) -> Self {
+        Self {
+            texture,
+            uv_rect,
+            position: na::Point2::origin(),
+            rotation: 0.0,
+            scale: na::Vector2::new(1.0, 1.0),
+            size: na::Vector2::new(size[0], size[1]),
+            origin: na::Vector2::new(0.5, 0.5),
+            tint: [1.0; 4],
+            layer: 0.0,
+        }
+    }
+
+    pub fn with_position(mut self, position: na::Point2<f32>) -> Self {
+        self.position = position;
+        self
+    }
+
+    pub fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_scale(mut self, scale: na::Vector2<f32>) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn with_size(mut self, size: na::Vector2<f32>) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn with_origin(mut self, origin: na::Vector2<f32>) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    pub fn with_tint(mut self, tint: [f32; 4]) -> Self {
+        self.tint = tint;
+        self
+    }
+
+    pub fn with_uv_rect(mut self, uv_rect: UvRect) -> Self {
+        self.uv_rect = uv_rect;
+        self
+    }
+
+    pub fn with_layer(mut self, layer: f32) -> Self {
+        self.layer = layer;
+        self
+    }
+
+    //bottom-left, bottom-right, top-right, top-left
+    pub fn corners(&self) -> [na::Point2<f32>; 4] {
+        let size = self.size.component_mul(&self.scale);
+        let min = -self.origin.component_mul(&size);
+        let max = min + size;
+        let rotation = na::Rotation2::new(self.rotation);
+
+        let corner = |x: f32, y: f32| self.position + rotation * na::Vector2::new(x, y);
+
+        [
+            corner(min.x, min.y),
+            corner(max.x, min.y),
+            corner(max.x, max.y),
+            corner(min.x, max.y),
+        ]
+    }
+}
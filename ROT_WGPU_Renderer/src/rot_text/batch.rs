@@ -0,0 +1,327 @@
+use crate::rot_pipeline::{Pipeline, PipelineBuilder};
+use crate::rot_primitives::Camera;
+use crate::rot_render_queue::DrawCommand;
+use crate::rot_sprite::{Camera2D, QuadBuffer};
+use crate::rot_text::{SdfFont, TextLayout, TextStyle};
+use crate::Renderer;
+use nalgebra as na;
+use std::rc::Rc;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct TextVertex {
+    pub position: [f32; 3],
+    pub tex_coords: [f32; 2],
+    pub color: [f32; 4],
+    pub outline_color: [f32; 4],
+    pub shadow_color: [f32; 4],
+    //outline field value, shadow uv offset, shadow softness
+    pub params: [f32; 4],
+}
+
+unsafe impl bytemuck::Pod for TextVertex {}
+unsafe impl bytemuck::Zeroable for TextVertex {}
+
+impl TextVertex {
+    pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<TextVertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::InputStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float3,
+                    offset: 0,
+                    shader_location: 0,
+                },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float2,
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float4,
+                    offset: std::mem::size_of::<[f32; 5]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float4,
+                    offset: std::mem::size_of::<[f32; 9]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float4,
+                    offset: std::mem::size_of::<[f32; 13]>() as wgpu::BufferAddress,
+                    shader_location: 4,
+                },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float4,
+                    offset: std::mem::size_of::<[f32; 17]>() as wgpu::BufferAddress,
+                    shader_location: 5,
+                },
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TextPlacement {
+    //Camera2D units, drawn in the overlay pass
+    Screen(na::Point2<f32>),
+    //world position, the text always faces the camera
+    Billboard(na::Point3<f32>),
+    //on the local XY plane of the isometry, reading along +X
+    Plane(na::Isometry3<f32>),
+}
+
+struct QueuedText {
+    font: Rc<SdfFont>,
+    layout: TextLayout,
+    style: TextStyle,
+    placement: TextPlacement,
+}
+
+//Lays out strings as they are drawn and submits them as one draw per string.
+//Screen text goes to the overlay pass, world text to the transparent pass sorted with the rest.
+//Submit once per frame, the buffers are reused
+pub struct TextBatch {
+    pub name: String,
+
+    screen: Vec<QueuedText>,
+    world: Vec<QueuedText>,
+
+    screen_quads: QuadBuffer,
+    world_quads: QuadBuffer,
+}
+
+impl TextBatch {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            screen: Vec::new(),
+            world: Vec::new(),
+            screen_quads: QuadBuffer::new(&format!("{} Screen Text", name)),
+            world_quads: QuadBuffer::new(&format!("{} World Text", name)),
+        }
+    }
+
+    pub fn draw(
+        &mut self,
+        font: &Rc<SdfFont>,
+        text: &str,
+        placement: TextPlacement,
+        style: &TextStyle,
+    ) {
+        let queued = QueuedText {
+            font: Rc::clone(font),
+            layout: TextLayout::new(font, text, style),
+            style: *style,
+            placement,
+        };
+
+        match placement {
+            TextPlacement::Screen(_) => self.screen.push(queued),
+            _ => self.world.push(queued),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.screen.is_empty() && self.world.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.screen.clear();
+        self.world.clear();
+    }
+
+    pub fn submit_screen(&mut self, renderer: &mut Renderer, camera: &Camera2D) {
+        if self.screen.is_empty() {
+            return;
+        }
+
+        let mut vertices = Vec::new();
+        let mut ranges = Vec::with_capacity(self.screen.len());
+        for text in self.screen.iter() {
+            let origin = match text.placement {
+                TextPlacement::Screen(position) => na::Point3::new(position.x, position.y, 0.0),
+                _ => continue,
+            };
+
+            let start = vertices.len() / 4;
+            push_quads(
+                &mut vertices,
+                text,
+                origin,
+                na::Vector3::x(),
+                na::Vector3::y(),
+            );
+            if vertices.len() / 4 > start {
+                ranges.push((start, vertices.len() / 4, &text.font));
+            }
+        }
+
+        let pipeline = TextBatch::pipeline(renderer, false);
+        let (vertex_buffer, index_buffer) = self.screen_quads.write(renderer, vertices.as_slice());
+        for (start, end, font) in ranges {
+            renderer.submit_overlay(TextBatch::command(
+                &pipeline,
+                font,
+                &camera.bind_group,
+                &vertex_buffer,
+                &index_buffer,
+                start..end,
+                0.0,
+            ));
+        }
+
+        self.screen.clear();
+    }
+
+    pub fn submit_world(&mut self, renderer: &mut Renderer, camera: &Camera) {
+        if self.world.is_empty() {
+            return;
+        }
+
+        let (camera_right, camera_up) = camera.billboard_axes();
+        let camera_position = renderer.camera_position();
+
+        let mut vertices = Vec::new();
+        let mut ranges = Vec::with_capacity(self.world.len());
+        for text in self.world.iter() {
+            let (origin, right, up) = match text.placement {
+                TextPlacement::Billboard(position) => (position, camera_right, camera_up),
+                TextPlacement::Plane(isometry) => (
+                    isometry * na::Point3::origin(),
+                    isometry * na::Vector3::x(),
+                    isometry * na::Vector3::y(),
+                ),
+                TextPlacement::Screen(_) => continue,
+            };
+
+            let start = vertices.len() / 4;
+            push_quads(&mut vertices, text, origin, right, up);
+            if vertices.len() / 4 > start {
+                ranges.push((
+                    start,
+                    vertices.len() / 4,
+                    &text.font,
+                    na::distance(&camera_position, &origin),
+                ));
+            }
+        }
+
+        let pipeline = TextBatch::pipeline(renderer, true);
+        let (vertex_buffer, index_buffer) = self.world_quads.write(renderer, vertices.as_slice());
+        for (start, end, font, depth) in ranges {
+            renderer.submit_transparent(TextBatch::command(
+                &pipeline,
+                font,
+                &camera.bind_group,
+                &vertex_buffer,
+                &index_buffer,
+                start..end,
+                depth,
+            ));
+        }
+
+        self.world.clear();
+    }
+
+    fn command(
+        pipeline: &Pipeline,
+        font: &SdfFont,
+        camera_bind_group: &Rc<wgpu::BindGroup>,
+        vertex_buffer: &Rc<wgpu::Buffer>,
+        index_buffer: &Rc<wgpu::Buffer>,
+        quads: std::ops::Range<usize>,
+        depth: f32,
+    ) -> DrawCommand {
+        DrawCommand {
+            pipeline: pipeline.clone(),
+            bind_groups: vec![
+                Rc::clone(&font.texture.bind_group),
+                Rc::clone(camera_bind_group),
+            ],
+            vertex_buffers: vec![Rc::clone(vertex_buffer)],
+            index_buffer: Some(Rc::clone(index_buffer)),
+            indices: (quads.start * 6) as u32..(quads.end * 6) as u32,
+            instances: 0..1,
+            indirect: None,
+            depth,
+        }
+    }
+
+    //world text is depth tested against the scene but doesn't write depth
+    fn pipeline(renderer: &Renderer, world: bool) -> Pipeline {
+        let pipeline_builder = PipelineBuilder {
+            name: "sdf text pipeline",
+            uniform_material: true,
+            uniform_camera: true,
+            vertex_shader_path: "shaders/text_sdf.vert.spv",
+            fragment_shader_path: "shaders/text_sdf.frag.spv",
+            vertex_buffer_layout: vec![TextVertex::desc()],
+            cull_mode: wgpu::CullMode::None,
+            ..PipelineBuilder::default()
+        }
+        .with_alpha_blending();
+
+        let pipeline_builder = if world {
+            pipeline_builder.with_depth(true, false)
+        } else {
+            pipeline_builder.without_depth()
+        };
+
+        Pipeline::new(renderer, &pipeline_builder)
+    }
+}
+
+fn push_quads(
+    vertices: &mut Vec<TextVertex>,
+    text: &QueuedText,
+    origin: na::Point3<f32>,
+    right: na::Vector3<f32>,
+    up: na::Vector3<f32>,
+) {
+    let style = &text.style;
+    let font = &text.font;
+
+    let (outline_value, outline_color) = match style.outline {
+        Some((width, color)) => (font.field_value(width), color),
+        None => (0.5, style.color),
+    };
+    let (shadow_offset, shadow_color, shadow_softness) = match style.shadow {
+        Some(shadow) => (
+            font.uv_offset(shadow.offset),
+            shadow.color,
+            shadow.softness.max(0.0).min(1.0) * 0.5,
+        ),
+        None => ([0.0; 2], [0.0; 4], 0.0),
+    };
+    let params = [
+        outline_value,
+        shadow_offset[0],
+        shadow_offset[1],
+        shadow_softness,
+    ];
+
+    for glyph in text.layout.glyphs.iter() {
+        let uv = &glyph.uv_rect;
+        let corners = [
+            (glyph.min[0], glyph.min[1], [uv.min[0], uv.max[1]]),
+            (glyph.max[0], glyph.min[1], [uv.max[0], uv.max[1]]),
+            (glyph.max[0], glyph.max[1], [uv.max[0], uv.min[1]]),
+            (glyph.min[0], glyph.max[1], [uv.min[0], uv.min[1]]),
+        ];
+
+        for &(x, y, tex_coords) in corners.iter() {
+            let position = origin + right * x + up * y;
+            vertices.push(TextVertex {
+                position: [position.x, position.y, position.z],
+                tex_coords,
+                color: style.color,
+                outline_color,
+                shadow_color,
+                params,
+            });
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use crate::rot_primitives::Material;
+use crate::rot_sprite::{pack, UvRect};
+use crate::rot_text::sdf::distance_field;
+use crate::Renderer;
+use std::collections::HashMap;
+use std::error::Error;
+use std::rc::Rc;
+
+//pixels per em the glyphs are rasterized at, the field scales well above and below it
+const BASE_SIZE: f32 = 48.0;
+//how far the distance field reaches outside the outline, caps outline and shadow widths
+const SPREAD: f32 = 6.0;
+
+//printable ASCII
+pub const DEFAULT_CHARSET: &str = " !\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~";
+
+//In em units, y-up from the pen position on the baseline
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct Glyph {
+    pub uv_rect: UvRect,
+    pub min: [f32; 2],
+    pub max: [f32; 2],
+    pub advance: f32,
+}
+
+//A TTF/OTF font rasterized once into a distance field atlas
+pub struct SdfFont {
+    pub name: String,
+    pub texture: Rc<Material>,
+
+    glyphs: HashMap<char, Glyph>,
+    //em units, only pairs the font actually kerns
+    kerning: HashMap<(char, char), f32>,
+
+    //em units
+    ascent: f32,
+    descent: f32,
+    line_gap: f32,
+}
+
+//fontdue doesn't read the kern table, so look every charset pair up once at load
+fn kerning_pairs(
+    bytes: &[u8],
+    characters: &[char],
+) -> Result<HashMap<(char, char), f32>, Box<dyn Error>> {
+    let face = ttf_parser::Face::from_slice(bytes, 0)?;
+    let units_per_em = face.units_per_em().unwrap_or(1000) as f32;
+    let subtables = face
+        .kerning_subtables()
+        .filter(|subtable| subtable.is_horizontal() && !subtable.is_variable())
+        .collect::<Vec<_>>();
+
+    let glyph_ids = characters
+        .iter()
+        .filter_map(|&character| face.glyph_index(character).map(|id| (character, id)))
+        .collect::<Vec<_>>();
+
+    let mut pairs = HashMap::new();
+    if subtables.is_empty() {
+        return Ok(pairs);
+    }
+    for &(left, left_id) in glyph_ids.iter() {
+        for &(right, right_id) in glyph_ids.iter() {
+            let kern = subtables
+                .iter()
+                .find_map(|subtable| subtable.glyphs_kerning(left_id, right_id));
+            if let Some(kern) = kern {
+                pairs.insert((left, right), kern as f32 / units_per_em);
+            }
+        }
+    }
+
+    Ok(pairs)
+}
+
+impl SdfFont {
+    pub fn load<P: AsRef<std::path::Path>>(
+        renderer: &Renderer,
+        path: P,
+        name: &str,
+    ) -> Result<Self, Box<dyn Error>> {
+        let bytes = std::fs::read(path)?;
+
+        SdfFont::from_bytes(renderer, bytes.as_slice(), DEFAULT_CHARSET, name)
+    }
+
+    pub fn from_bytes(
+        renderer: &Renderer,
+        bytes: &[u8],
+        charset: &str,
+        name: &str,
+    ) -> Result<Self, Box<dyn Error>> {
+        let font = fontdue::Font::from_bytes(bytes, fontdue::FontSettings::default())?;
+
+        let mut characters = charset.chars().collect::<Vec<_>>();
+        characters.sort_unstable();
+        characters.dedup();
+        if !characters.contains(&'?') {
+            characters.push('?');
+        }
+
+        let padding = SPREAD.ceil() as usize;
+        let mut fields = Vec::with_capacity(characters.len());
+        for &character in characters.iter() {
+            let (metrics, coverage) = font.rasterize(character, BASE_SIZE);
+
+            //the outline plus room for the field to fade out around it
+            let width = metrics.width + 2 * padding;
+            let height = metrics.height + 2 * padding;
+            let mut padded = vec![0; width * height];
+            for row in 0..metrics.height {
+                let source = &coverage[row * metrics.width..(row + 1) * metrics.width];
+                let start = (row + padding) * width + padding;
+                padded[start..start + metrics.width].copy_from_slice(source);
+            }
+
+            fields.push((
+                metrics,
+                width as u32,
+                height as u32,
+                distance_field(padded.as_slice(), width, height, SPREAD),
+            ));
+        }
+
+        let sizes = fields
+            .iter()
+            .map(|(_, width, height, _)| (*width, *height))
+            .collect::<Vec<_>>();
+        //shadows sample up to SPREAD away from the glyph, which must not reach a neighbour
+        let (atlas_width, atlas_height, positions) = pack(sizes.as_slice(), padding as u32)
+            .ok_or_else(|| format!("{} glyphs don't fit a single atlas", characters.len()))?;
+
+        let mut atlas = image::RgbaImage::from_pixel(
+            atlas_width,
+            atlas_height,
+            image::Rgba([255, 255, 255, 0]),
+        );
+        let mut glyphs = HashMap::with_capacity(characters.len());
+        for ((&character, (metrics, width, height, field)), (x, y)) in characters
+            .iter()
+            .zip(fields.iter())
+            .zip(positions.into_iter())
+        {
+            for (index, &distance) in field.iter().enumerate() {
+                let (column, row) = (index as u32 % width, index as u32 / width);
+                atlas.put_pixel(x + column, y + row, image::Rgba([255, 255, 255, distance]));
+            }
+
+            let min = [
+                (metrics.xmin as f32 - padding as f32) / BASE_SIZE,
+                (metrics.ymin as f32 - padding as f32) / BASE_SIZE,
+            ];
+            glyphs.insert(
+                character,
+                Glyph {
+                    uv_rect: UvRect::from_pixels(
+                        x,
+                        y,
+                        *width,
+                        *height,
+                        (atlas_width, atlas_height),
+                    ),
+                    min,
+                    max: [
+                        min[0] + *width as f32 / BASE_SIZE,
+                        min[1] + *height as f32 / BASE_SIZE,
+                    ],
+                    advance: metrics.advance_width / BASE_SIZE,
+                },
+            );
+        }
+
+        let kerning = kerning_pairs(bytes, characters.as_slice())?;
+
+        let (ascent, descent, line_gap) = match font.horizontal_line_metrics(BASE_SIZE) {
+            Some(line) => (
+                line.ascent / BASE_SIZE,
+                line.descent / BASE_SIZE,
+                line.line_gap / BASE_SIZE,
+            ),
+            None => (0.8, -0.2, 0.0),
+        };
+
+        Ok(Self {
+            name: name.to_string(),
+            texture: Rc::new(Material::from_rgba_linear(&atlas, renderer, name)),
+            glyphs,
+            kerning,
+            ascent,
+            descent,
+            line_gap,
+        })
+    }
+
+    //missing characters show up as '?'
+    pub(crate) fn glyph(&self, character: char) -> Option<&Glyph> {
+        self.glyphs
+            .get(&character)
+            .or_else(|| self.glyphs.get(&'?'))
+    }
+
+    //in em units
+    pub(crate) fn kerning(&self, left: char, right: char) -> f32 {
+        self.kerning.get(&(left, right)).copied().unwrap_or(0.0)
+    }
+
+    pub fn ascent(&self) -> f32 {
+        self.ascent
+    }
+
+    pub fn descent(&self) -> f32 {
+        self.descent
+    }
+
+    //baseline to baseline, in em units
+    pub fn line_height(&self) -> f32 {
+        self.ascent - self.descent + self.line_gap
+    }
+
+    //how far outlines and shadows can reach, in em units
+    pub fn spread(&self) -> f32 {
+        SPREAD / BASE_SIZE
+    }
+
+    //field value distance em units outside the outline
+    pub(crate) fn field_value(&self, distance: f32) -> f32 {
+        0.5 - (distance * BASE_SIZE).max(0.0).min(SPREAD) / (2.0 * SPREAD)
+    }
+
+    //an em units offset as atlas uv, y-up like the layout
+    pub(crate) fn uv_offset(&self, offset: [f32; 2]) -> [f32; 2] {
+        let spread = self.spread();
+        let (width, height) = self.texture.dimensions;
+
+        [
+            offset[0].max(-spread).min(spread) * BASE_SIZE / width as f32,
+            -offset[1].max(-spread).min(spread) * BASE_SIZE / height as f32,
+        ]
+    }
+}
@@ -0,0 +1,220 @@
+use crate::rot_sprite::UvRect;
+use crate::rot_text::SdfFont;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TextShadow {
+    //em units, capped at SdfFont::spread
+    pub offset: [f32; 2],
+    pub color: [f32; 4],
+    //0.0 is as sharp as the text, 1.0 fades over the whole spread
+    pub softness: f32,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TextStyle {
+    //em size, pixels for screen text and world units for world text
+    pub size: f32,
+    pub color: [f32; 4],
+    //also picks which point of the first line the position refers to
+    pub align: TextAlign,
+    //wraps at spaces past this width, in the same units as size
+    pub max_width: Option<f32>,
+    //multiplies the font line height
+    pub line_spacing: f32,
+    //width in em units, capped at SdfFont::spread
+    pub outline: Option<(f32, [f32; 4])>,
+    pub shadow: Option<TextShadow>,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            size: 16.0,
+            color: [1.0; 4],
+            align: TextAlign::Left,
+            max_width: None,
+            line_spacing: 1.0,
+            outline: None,
+            shadow: None,
+        }
+    }
+}
+
+impl TextStyle {
+    pub fn with_size(mut self, size: f32) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn with_color(mut self, color: [f32; 4]) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_align(mut self, align: TextAlign) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn with_max_width(mut self, max_width: f32) -> Self {
+        self.max_width = Some(max_width);
+        self
+    }
+
+    pub fn with_outline(mut self, width: f32, color: [f32; 4]) -> Self {
+        self.outline = Some((width, color));
+        self
+    }
+
+    pub fn with_shadow(mut self, offset: [f32; 2], color: [f32; 4], softness: f32) -> Self {
+        self.shadow = Some(TextShadow {
+            offset,
+            color,
+            softness,
+        });
+        self
+    }
+}
+
+//A glyph quad in text space: y-up, the position of the text at the origin,
+//the first line hanging below it
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LaidOutGlyph {
+    pub uv_rect: UvRect,
+    pub min: [f32; 2],
+    pub max: [f32; 2],
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLayout {
+    pub glyphs: Vec<LaidOutGlyph>,
+    //bounds of every line, in style.size units
+    pub width: f32,
+    pub height: f32,
+}
+
+impl TextLayout {
+    pub fn new(font: &SdfFont, text: &str, style: &TextStyle) -> Self {
+        let scale = style.size;
+        let line_height = font.line_height() * style.line_spacing * scale;
+
+        let lines = text
+            .split('\n')
+            .flat_map(|paragraph| wrap(font, paragraph, style.max_width.map(|width| width / scale)))
+            .collect::<Vec<_>>();
+
+        let mut glyphs = Vec::with_capacity(text.len());
+        let mut width: f32 = 0.0;
+        for (index, line) in lines.iter().enumerate() {
+            let line_width = measure(font, line) * scale;
+            width = width.max(line_width);
+
+            let mut pen = [
+                match style.align {
+                    TextAlign::Left => 0.0,
+                    TextAlign::Center => -0.5 * line_width,
+                    TextAlign::Right => -line_width,
+                },
+                -font.ascent() * scale - index as f32 * line_height,
+            ];
+
+            let mut previous = None;
+            for character in line.chars() {
+                if let Some(previous) = previous {
+                    pen[0] += font.kerning(previous, character) * scale;
+                }
+                previous = Some(character);
+
+                let glyph = match font.glyph(character) {
+                    Some(glyph) => glyph,
+                    None => continue,
+                };
+
+                if !character.is_whitespace() {
+                    glyphs.push(LaidOutGlyph {
+                        uv_rect: glyph.uv_rect,
+                        min: [pen[0] + glyph.min[0] * scale, pen[1] + glyph.min[1] * scale],
+                        max: [pen[0] + glyph.max[0] * scale, pen[1] + glyph.max[1] * scale],
+                    });
+                }
+
+                pen[0] += glyph.advance * scale;
+            }
+        }
+
+        let height = match lines.len() {
+            0 => 0.0,
+            count => (count - 1) as f32 * line_height + (font.ascent() - font.descent()) * scale,
+        };
+
+        Self {
+            glyphs,
+            width,
+            height,
+        }
+    }
+}
+
+//em units
+fn measure(font: &SdfFont, text: &str) -> f32 {
+    let mut width = 0.0;
+    let mut previous = None;
+    for character in text.chars() {
+        if let Some(previous) = previous {
+            width += font.kerning(previous, character);
+        }
+        previous = Some(character);
+
+        width += font
+            .glyph(character)
+            .map(|glyph| glyph.advance)
+            .unwrap_or(0.0);
+    }
+
+    width
+}
+
+//Greedy word wrap, words longer than a whole line are split between characters
+fn wrap(font: &SdfFont, paragraph: &str, max_width: Option<f32>) -> Vec<String> {
+    let max_width = match max_width {
+        Some(max_width) => max_width,
+        None => return vec![paragraph.to_string()],
+    };
+
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in paragraph.split(' ') {
+        let candidate = if line.is_empty() {
+            word.to_string()
+        } else {
+            format!("{} {}", line, word)
+        };
+
+        if measure(font, &candidate) <= max_width {
+            line = candidate;
+            continue;
+        }
+
+        if !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+        }
+
+        for character in word.chars() {
+            line.push(character);
+            if measure(font, &line) > max_width && line.chars().count() > 1 {
+                line.pop();
+                lines.push(std::mem::replace(&mut line, character.to_string()));
+            }
+        }
+    }
+    lines.push(line);
+
+    lines
+}
@@ -0,0 +1,8 @@
+mod batch;
+mod font;
+mod layout;
+mod sdf;
+
+pub use batch::{TextBatch, TextPlacement, TextVertex};
+pub use font::{SdfFont, DEFAULT_CHARSET};
+pub use layout::{LaidOutGlyph, TextAlign, TextLayout, TextShadow, TextStyle};
@@ -0,0 +1,93 @@
+//Exact euclidean distance transform (Felzenszwalb & Huttenlocher), the same one tiny-sdf uses
+const INF: f64 = 1e20;
+
+//Turns an antialiased coverage bitmap into a distance field. 0.5 (128) is the outline,
+//higher is inside, and the values reach 0 or 1 spread pixels away from it
+pub(crate) fn distance_field(coverage: &[u8], width: usize, height: usize, spread: f32) -> Vec<u8> {
+    let size = width * height;
+    let mut outer = vec![INF; size];
+    let mut inner = vec![0.0; size];
+
+    for (index, &value) in coverage.iter().enumerate() {
+        let alpha = value as f64 / 255.0;
+        if alpha >= 1.0 {
+            outer[index] = 0.0;
+            inner[index] = INF;
+        } else if alpha > 0.0 {
+            //partially covered pixels know how far the edge is from their center
+            outer[index] = (0.5 - alpha).max(0.0).powi(2);
+            inner[index] = (alpha - 0.5).max(0.0).powi(2);
+        }
+    }
+
+    transform_2d(&mut outer, width, height);
+    transform_2d(&mut inner, width, height);
+
+    outer
+        .iter()
+        .zip(inner.iter())
+        .map(|(outer, inner)| {
+            let distance = (outer.sqrt() - inner.sqrt()) as f32;
+            let value = 0.5 - distance / (2.0 * spread);
+
+            (value.max(0.0).min(1.0) * 255.0).round() as u8
+        })
+        .collect()
+}
+
+fn transform_2d(grid: &mut [f64], width: usize, height: usize) {
+    let length = width.max(height);
+    let mut f = vec![0.0; length];
+    let mut d = vec![0.0; length];
+    let mut v = vec![0; length];
+    let mut z = vec![0.0; length + 1];
+
+    for x in 0..width {
+        for y in 0..height {
+            f[y] = grid[y * width + x];
+        }
+        transform_1d(&f[..height], &mut d, &mut v, &mut z);
+        for y in 0..height {
+            grid[y * width + x] = d[y];
+        }
+    }
+
+    for y in 0..height {
+        f[..width].copy_from_slice(&grid[y * width..(y + 1) * width]);
+        transform_1d(&f[..width], &mut d, &mut v, &mut z);
+        grid[y * width..(y + 1) * width].copy_from_slice(&d[..width]);
+    }
+}
+
+//lower envelope of the parabolas rooted at every sample
+fn transform_1d(f: &[f64], d: &mut [f64], v: &mut [usize], z: &mut [f64]) {
+    let parabola = |q: usize| f[q] + (q * q) as f64;
+
+    let mut k = 0;
+    v[0] = 0;
+    z[0] = -INF;
+    z[1] = INF;
+
+    for q in 1..f.len() {
+        let mut s = (parabola(q) - parabola(v[k])) / (2.0 * (q as f64 - v[k] as f64));
+        while s <= z[k] {
+            k -= 1;
+            s = (parabola(q) - parabola(v[k])) / (2.0 * (q as f64 - v[k] as f64));
+        }
+
+        k += 1;
+        v[k] = q;
+        z[k] = s;
+        z[k + 1] = INF;
+    }
+
+    k = 0;
+    for q in 0..f.len() {
+        while z[k + 1] < q as f64 {
+            k += 1;
+        }
+
+        let distance = q as f64 - v[k] as f64;
+        d[q] = distance * distance + f[v[k]];
+    }
+}
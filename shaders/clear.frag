@@ -0,0 +1,8 @@
+#version 450
+
+layout(location=0) out vec4 f_color;
+
+//the color comes from the blend constant of the render pass
+void main() {
+    f_color = vec4(1.0);
+}
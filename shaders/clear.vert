@@ -0,0 +1,10 @@
+#version 450
+
+//a triangle covering the viewport, at the far plane: 1.0, or 0.0 for reverse Z on instance 1
+
+void main() {
+    vec2 position = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2);
+    float depth = gl_InstanceIndex == 0 ? 1.0 : 0.0;
+
+    gl_Position = vec4(position * 2.0 - 1.0, depth, 1.0);
+}
@@ -8,12 +8,26 @@ layout(location=5) in vec4 model_matrix_0;
 layout(location=6) in vec4 model_matrix_1;
 layout(location=7) in vec4 model_matrix_2;
 layout(location=8) in vec4 model_matrix_3;
+layout(location=9) in vec4 normal_matrix_0;
+layout(location=10) in vec4 normal_matrix_1;
+layout(location=11) in vec4 normal_matrix_2;
+layout(location=12) in vec4 a_tint;
+layout(location=13) in vec4 a_custom;
+layout(location=14) in uvec4 a_indices;
 
 layout(location=0) out vec2 v_tex_coords;
+layout(location=1) out vec3 v_normal;
+layout(location=2) out vec4 v_tint;
 
 layout(set=1, binding=0) // 1.
 uniform Uniforms {
     mat4 u_view_proj; // 2.
+    mat4 u_view;
+    mat4 u_proj;
+    mat4 u_inv_view_proj;
+    mat4 u_inv_view;
+    mat4 u_inv_proj;
+    vec4 u_camera_position;
 };
 
 void main() {
@@ -26,6 +40,14 @@ void main() {
     );
 
 
+    mat3 normal_matrix = mat3(
+    normal_matrix_0.xyz,
+    normal_matrix_1.xyz,
+    normal_matrix_2.xyz
+    );
+
     v_tex_coords = a_tex_coords;
+    v_normal = normalize(normal_matrix * a_normals);
+    v_tint = a_tint;
     gl_Position = u_view_proj *model_matrix* vec4(a_position, 1.0);
 }
\ No newline at end of file
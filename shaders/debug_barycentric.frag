@@ -0,0 +1,19 @@
+#version 450
+
+layout(location=3) in vec3 v_barycentric;
+
+layout(location=0) out vec4 f_color;
+
+// edge width in pixels
+const float WIDTH = 1.0;
+
+void main() {
+    vec3 distance = v_barycentric / fwidth(v_barycentric);
+    float edge = min(min(distance.x, distance.y), distance.z);
+
+    if (edge > WIDTH) {
+        discard;
+    }
+
+    f_color = vec4(1.0, 1.0, 1.0, 0.8 * (1.0 - edge / WIDTH * 0.5));
+}
@@ -0,0 +1,13 @@
+#version 450
+
+layout(location=2) in float v_view_depth;
+
+layout(location=0) out vec4 f_color;
+
+// distance at which the gray falls to about a third
+const float FALLOFF = 20.0;
+
+void main() {
+    float intensity = exp(-v_view_depth / FALLOFF);
+    f_color = vec4(vec3(intensity), 1.0);
+}
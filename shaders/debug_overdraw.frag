@@ -0,0 +1,8 @@
+#version 450
+
+layout(location=0) out vec4 f_color;
+
+void main() {
+    // one more layer, added up in the overdraw counter
+    f_color = vec4(1.0);
+}
@@ -0,0 +1,26 @@
+#version 450
+
+layout(location=0) out vec4 f_color;
+
+layout(set=0, binding=0) uniform texture2D t_count;
+layout(set=0, binding=1) uniform sampler s_count;
+
+// black where nothing was drawn, then blue, green, yellow, red and white from MAX_LAYERS up
+const vec3 RAMP[6] = vec3[](
+    vec3(0.0, 0.0, 0.0),
+    vec3(0.0, 0.0, 1.0),
+    vec3(0.0, 1.0, 0.0),
+    vec3(1.0, 1.0, 0.0),
+    vec3(1.0, 0.0, 0.0),
+    vec3(1.0, 1.0, 1.0)
+);
+const float MAX_LAYERS = 10.0;
+
+void main() {
+    // the counter has the size of the attachment, so the fragment lands on its own texel
+    float count = texelFetch(sampler2D(t_count, s_count), ivec2(gl_FragCoord.xy), 0).r;
+
+    float position = clamp(count / MAX_LAYERS, 0.0, 1.0) * 5.0;
+    int index = min(int(position), 4);
+    f_color = vec4(mix(RAMP[index], RAMP[index + 1], position - float(index)), 1.0);
+}
@@ -0,0 +1,16 @@
+#version 450
+
+layout(location=0) in vec2 v_tex_coords;
+
+layout(location=0) out vec4 f_color;
+
+const float CELLS = 8.0;
+
+void main() {
+    vec2 cell = floor(v_tex_coords * CELLS);
+    float checker = mod(cell.x + cell.y, 2.0);
+
+    // red grows with u and green with v, so flipped or rotated UVs stand out
+    vec3 gradient = vec3(fract(v_tex_coords), 0.25);
+    f_color = vec4(mix(gradient * 0.35, gradient, checker), 1.0);
+}
@@ -0,0 +1,47 @@
+#version 450
+
+layout(location=0) in vec3 a_position;
+layout(location=1) in vec2 a_tex_coords;
+layout(location=2) in vec3 a_normals;
+
+layout(location=5) in vec4 model_matrix_0;
+layout(location=6) in vec4 model_matrix_1;
+layout(location=7) in vec4 model_matrix_2;
+layout(location=8) in vec4 model_matrix_3;
+layout(location=9) in vec4 normal_matrix_0;
+layout(location=10) in vec4 normal_matrix_1;
+layout(location=11) in vec4 normal_matrix_2;
+
+layout(location=0) out vec2 v_tex_coords;
+layout(location=1) out vec3 v_normal;
+layout(location=2) out float v_view_depth;
+// only meaningful for non indexed draws, every 3 vertices are a triangle
+layout(location=3) out vec3 v_barycentric;
+
+layout(set=0, binding=0)
+uniform Uniforms {
+    mat4 u_view_proj;
+};
+
+void main() {
+    mat4 model_matrix = mat4(
+    model_matrix_0,
+    model_matrix_1,
+    model_matrix_2,
+    model_matrix_3
+    );
+    mat3 normal_matrix = mat3(
+    normal_matrix_0.xyz,
+    normal_matrix_1.xyz,
+    normal_matrix_2.xyz
+    );
+
+    v_tex_coords = a_tex_coords;
+    v_normal = normalize(normal_matrix * a_normals);
+    v_barycentric = vec3(0.0);
+    v_barycentric[gl_VertexIndex % 3] = 1.0;
+
+    gl_Position = u_view_proj * model_matrix * vec4(a_position, 1.0);
+    // w is the view space distance along the camera axis for a perspective projection
+    v_view_depth = gl_Position.w;
+}
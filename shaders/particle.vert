@@ -0,0 +1,63 @@
+#version 450
+
+layout(location=0) in vec4 a_position_age;
+layout(location=1) in vec4 a_velocity_lifetime;
+
+layout(location=0) out vec2 v_tex_coords;
+layout(location=1) out vec4 v_color;
+
+layout(set=1, binding=0)
+uniform Uniforms {
+    mat4 u_view_proj;
+};
+
+layout(set=2, binding=0)
+uniform Particles {
+    vec4 u_camera_right;
+    vec4 u_camera_up;
+    vec4 u_color_over_life[16];
+    vec4 u_size_over_life[4];
+    // columns, rows
+    uvec4 u_flipbook;
+};
+
+const vec2 CORNERS[6] = vec2[6](
+    vec2(-0.5, -0.5), vec2(0.5, -0.5), vec2(0.5, 0.5),
+    vec2(-0.5, -0.5), vec2(0.5, 0.5), vec2(-0.5, 0.5)
+);
+
+float size_at(uint index) {
+    return u_size_over_life[index / 4][index % 4];
+}
+
+void main() {
+    float age = a_position_age.w;
+    float lifetime = a_velocity_lifetime.w;
+
+    // dead slots collapse outside the clip volume
+    if (age >= lifetime) {
+        v_tex_coords = vec2(0.0);
+        v_color = vec4(0.0);
+        gl_Position = vec4(0.0, 0.0, -2.0, 1.0);
+        return;
+    }
+
+    float life = clamp(age / lifetime, 0.0, 1.0);
+    float sample_position = life * 15.0;
+    uint first = uint(floor(sample_position));
+    uint second = min(first + 1u, 15u);
+    float blend = fract(sample_position);
+
+    float size = mix(size_at(first), size_at(second), blend);
+    v_color = mix(u_color_over_life[first], u_color_over_life[second], blend);
+
+    vec2 corner = CORNERS[gl_VertexIndex];
+    uint frame_count = u_flipbook.x * u_flipbook.y;
+    uint frame = min(uint(life * float(frame_count)), frame_count - 1);
+    vec2 cell = vec2(frame % u_flipbook.x, frame / u_flipbook.x);
+    v_tex_coords = (cell + vec2(corner.x + 0.5, 0.5 - corner.y)) / vec2(u_flipbook.xy);
+
+    vec3 position = a_position_age.xyz
+        + (u_camera_right.xyz * corner.x + u_camera_up.xyz * corner.y) * size;
+    gl_Position = u_view_proj * vec4(position, 1.0);
+}
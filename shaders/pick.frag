@@ -0,0 +1,18 @@
+#version 450
+
+layout(location=0) in vec3 v_world_position;
+layout(location=1) flat in uint v_instance;
+
+layout(set=1, binding=0)
+uniform Pick {
+    uint u_base_id;
+};
+
+// 0 is left for the background
+layout(location=0) out uint f_id;
+layout(location=1) out vec4 f_world_position;
+
+void main() {
+    f_id = u_base_id + v_instance;
+    f_world_position = vec4(v_world_position, 1.0);
+}
@@ -0,0 +1,31 @@
+#version 450
+
+layout(location=0) in vec3 a_position;
+
+layout(location=5) in vec4 model_matrix_0;
+layout(location=6) in vec4 model_matrix_1;
+layout(location=7) in vec4 model_matrix_2;
+layout(location=8) in vec4 model_matrix_3;
+
+layout(location=0) out vec3 v_world_position;
+layout(location=1) flat out uint v_instance;
+
+layout(set=0, binding=0)
+uniform Uniforms {
+    mat4 u_view_proj;
+};
+
+void main() {
+    mat4 model_matrix = mat4(
+    model_matrix_0,
+    model_matrix_1,
+    model_matrix_2,
+    model_matrix_3
+    );
+
+    vec4 world_position = model_matrix * vec4(a_position, 1.0);
+
+    v_world_position = world_position.xyz;
+    v_instance = gl_InstanceIndex;
+    gl_Position = u_view_proj * world_position;
+}
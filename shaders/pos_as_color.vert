@@ -1,12 +1,38 @@
 #version 450
 
-layout(location=0) in vec3 a_pos;
-layout(location=1) in vec3 a_color;
+layout(location=0) in vec3 a_position;
+layout(location=1) in vec2 a_tex_coords;
+layout(location=2) in vec3 a_normals;
 
+layout(location=5) in vec4 model_matrix_0;
+layout(location=6) in vec4 model_matrix_1;
+layout(location=7) in vec4 model_matrix_2;
+layout(location=8) in vec4 model_matrix_3;
+layout(location=9) in vec4 normal_matrix_0;
+layout(location=10) in vec4 normal_matrix_1;
+layout(location=11) in vec4 normal_matrix_2;
 
 layout(location=0) out vec3 v_color;
 
+layout(set=0, binding=0)
+uniform Uniforms {
+    mat4 u_view_proj;
+};
+
 void main(){
-    v_color = a_pos;
-    gl_Position = vec4(a_pos, 1.0);
-}
\ No newline at end of file
+    mat4 model_matrix = mat4(
+    model_matrix_0,
+    model_matrix_1,
+    model_matrix_2,
+    model_matrix_3
+    );
+    mat3 normal_matrix = mat3(
+    normal_matrix_0.xyz,
+    normal_matrix_1.xyz,
+    normal_matrix_2.xyz
+    );
+
+    // world space normal remapped from -1..1 to 0..1
+    v_color = normalize(normal_matrix * a_normals) * 0.5 + 0.5;
+    gl_Position = u_view_proj * model_matrix * vec4(a_position, 1.0);
+}
@@ -0,0 +1,59 @@
+#version 450
+
+layout(location=0) in vec3 a_position;
+layout(location=1) in vec2 a_tex_coords;
+layout(location=2) in vec3 a_normals;
+layout(location=3) in uvec4 a_joints;
+layout(location=4) in vec4 a_weights;
+
+layout(location=5) in vec4 model_matrix_0;
+layout(location=6) in vec4 model_matrix_1;
+layout(location=7) in vec4 model_matrix_2;
+layout(location=8) in vec4 model_matrix_3;
+layout(location=9) in vec4 normal_matrix_0;
+layout(location=10) in vec4 normal_matrix_1;
+layout(location=11) in vec4 normal_matrix_2;
+layout(location=12) in vec4 a_tint;
+layout(location=13) in vec4 a_custom;
+layout(location=14) in uvec4 a_indices;
+
+layout(location=0) out vec2 v_tex_coords;
+layout(location=1) out vec3 v_normal;
+layout(location=2) out vec4 v_tint;
+
+layout(set=1, binding=0)
+uniform Uniforms {
+    mat4 u_view_proj;
+};
+
+layout(set=2, binding=0)
+readonly buffer Joints {
+    mat4 joint_matrices[];
+};
+
+void main() {
+
+    mat4 model_matrix = mat4(
+    model_matrix_0,
+    model_matrix_1,
+    model_matrix_2,
+    model_matrix_3
+    );
+
+    mat3 normal_matrix = mat3(
+    normal_matrix_0.xyz,
+    normal_matrix_1.xyz,
+    normal_matrix_2.xyz
+    );
+
+    mat4 skin_matrix =
+    a_weights.x * joint_matrices[a_joints.x] +
+    a_weights.y * joint_matrices[a_joints.y] +
+    a_weights.z * joint_matrices[a_joints.z] +
+    a_weights.w * joint_matrices[a_joints.w];
+
+    v_tex_coords = a_tex_coords;
+    v_normal = normalize(normal_matrix * mat3(skin_matrix) * a_normals);
+    v_tint = a_tint;
+    gl_Position = u_view_proj * model_matrix * skin_matrix * vec4(a_position, 1.0);
+}
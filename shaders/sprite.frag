@@ -0,0 +1,25 @@
+#version 450
+
+layout(location=0) in vec2 v_tex_coords;
+layout(location=1) in vec4 v_color;
+
+layout(set = 0, binding = 0) uniform texture2D t_diffuse;
+layout(set = 0, binding = 1) uniform sampler s_diffuse;
+layout(set = 0, binding = 2)
+uniform Material {
+    float u_opacity;
+    float u_alpha_cutoff;
+};
+
+layout(location=0) out vec4 f_color;
+
+void main() {
+    vec4 color = texture(sampler2D(t_diffuse, s_diffuse), v_tex_coords) * v_color;
+    color.a *= u_opacity;
+
+    if (color.a < u_alpha_cutoff) {
+        discard;
+    }
+
+    f_color = color;
+}
@@ -0,0 +1,46 @@
+#version 450
+
+layout(location=0) in vec2 v_tex_coords;
+layout(location=1) in vec4 v_color;
+layout(location=2) in vec4 v_outline_color;
+layout(location=3) in vec4 v_shadow_color;
+//x: outline edge, yz: shadow uv offset, w: shadow softness
+layout(location=4) in vec4 v_params;
+
+layout(set = 0, binding = 0) uniform texture2D t_field;
+layout(set = 0, binding = 1) uniform sampler s_field;
+layout(set = 0, binding = 2)
+uniform Material {
+    float u_opacity;
+    float u_alpha_cutoff;
+};
+
+layout(location=0) out vec4 f_color;
+
+void main() {
+    //0.5 on the outline, higher inside
+    float field = texture(sampler2D(t_field, s_field), v_tex_coords).a;
+    //about a pixel wide whatever the text size
+    float smoothing = max(fwidth(field) * 0.75, 0.0001);
+
+    float outline_edge = v_params.x;
+    float fill = smoothstep(0.5 - smoothing, 0.5 + smoothing, field);
+    float body = smoothstep(outline_edge - smoothing, outline_edge + smoothing, field);
+
+    vec4 text = mix(v_outline_color, v_color, fill);
+    text.a *= body;
+
+    float shadow_field = texture(sampler2D(t_field, s_field), v_tex_coords - v_params.yz).a;
+    float shadow_smoothing = smoothing + v_params.w;
+    float shadow = v_shadow_color.a
+        * smoothstep(outline_edge - shadow_smoothing, outline_edge + shadow_smoothing, shadow_field);
+
+    //text over its own shadow
+    float alpha = text.a + shadow * (1.0 - text.a);
+    if (alpha <= 0.0) {
+        discard;
+    }
+    vec3 color = (text.rgb * text.a + v_shadow_color.rgb * shadow * (1.0 - text.a)) / alpha;
+
+    f_color = vec4(color, alpha * u_opacity);
+}
@@ -0,0 +1,28 @@
+#version 450
+
+layout(location=0) in vec3 a_position;
+layout(location=1) in vec2 a_tex_coords;
+layout(location=2) in vec4 a_color;
+layout(location=3) in vec4 a_outline_color;
+layout(location=4) in vec4 a_shadow_color;
+layout(location=5) in vec4 a_params;
+
+layout(location=0) out vec2 v_tex_coords;
+layout(location=1) out vec4 v_color;
+layout(location=2) out vec4 v_outline_color;
+layout(location=3) out vec4 v_shadow_color;
+layout(location=4) out vec4 v_params;
+
+layout(set=1, binding=0)
+uniform Uniforms {
+    mat4 u_view_proj;
+};
+
+void main() {
+    v_tex_coords = a_tex_coords;
+    v_color = a_color;
+    v_outline_color = a_outline_color;
+    v_shadow_color = a_shadow_color;
+    v_params = a_params;
+    gl_Position = u_view_proj * vec4(a_position, 1.0);
+}